@@ -2,7 +2,7 @@ use proc_macro::TokenStream;
 use proc_macro2::TokenStream as TokenStream2;
 use quote::quote;
 use std::str::FromStr;
-use syn::DeriveInput;
+use syn::{Data, DeriveInput, Fields, Lit, Meta, NestedMeta};
 
 #[proc_macro_attribute]
 pub fn adapter(_args: TokenStream, input: TokenStream) -> TokenStream {
@@ -29,17 +29,52 @@ pub fn api_handler(_args: TokenStream, input: TokenStream) -> TokenStream {
     apply_macro(input, "api_handler", "ApiHandler", None)
 }
 
+#[proc_macro_derive(SimpleInput, attributes(webthings))]
+pub fn simple_input(input: TokenStream) -> TokenStream {
+    match syn::parse2::<DeriveInput>(input.into()) {
+        Ok(ast) => derive_simple_input(ast).into(),
+        Err(err) => syn::Error::new(
+            err.span(),
+            "`SimpleInput` has to be used with structs or enums",
+        )
+        .to_compile_error()
+        .into(),
+    }
+}
+
+#[proc_macro_derive(StringEnumValue, attributes(serde))]
+pub fn string_enum_value(input: TokenStream) -> TokenStream {
+    match syn::parse2::<DeriveInput>(input.into()) {
+        Ok(ast) => derive_string_enum_value(ast).into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
 fn apply_macro(
     input: TokenStream,
     name_snail_case: &str,
     name_camel_case: &str,
     generic_name: Option<&str>,
 ) -> TokenStream {
-    if let Ok(ast) = syn::parse2::<DeriveInput>(input.into()) {
-        alter_struct(ast, name_snail_case, name_camel_case, generic_name).into()
-    } else {
-        panic!("`{}` has to be used with structs", name_snail_case)
-    }
+    let message = format!("`{}` has to be used with structs", name_snail_case);
+    let ast = match syn::parse2::<DeriveInput>(input.into()) {
+        Ok(ast) => ast,
+        Err(err) => {
+            return syn::Error::new(err.span(), message)
+                .to_compile_error()
+                .into()
+        }
+    };
+    let keyword_span = match &ast.data {
+        Data::Struct(_) => {
+            return alter_struct(ast, name_snail_case, name_camel_case, generic_name).into()
+        }
+        Data::Enum(data) => data.enum_token.span,
+        Data::Union(data) => data.union_token.span,
+    };
+    syn::Error::new(keyword_span, message)
+        .to_compile_error()
+        .into()
 }
 
 fn alter_struct(
@@ -50,6 +85,7 @@ fn alter_struct(
 ) -> TokenStream2 {
     let struct_name = ast.ident.clone();
     let visibility = ast.vis.clone();
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
     let struct_built_name = TokenStream2::from_str(&format!("Built{}", struct_name)).unwrap();
 
     let trait_handle_wrapper = TokenStream2::from_str(&format!(
@@ -63,49 +99,48 @@ fn alter_struct(
     ))
     .unwrap();
     let struct_built = TokenStream2::from_str(&format!("Built{}", name_camel_case)).unwrap();
-    let struct_handle = TokenStream2::from_str(&if let Some(generic_name) = generic_name {
-        format!(
-            "gateway_addon_rust::{name_snail_case}::{name_camel_case}Handle<<{struct_name} as gateway_addon_rust::{name_snail_case}::{name_camel_case}Structure>::{generic_name}>",
-            name_snail_case = name_snail_case,
-            name_camel_case = name_camel_case,
-            struct_name = struct_name,
-            generic_name = generic_name,
-        )
-    } else {
-        format!(
-            "gateway_addon_rust::{}::{}Handle",
-            name_snail_case, name_camel_case
-        )
-    })
+    let struct_handle_base = TokenStream2::from_str(&format!(
+        "gateway_addon_rust::{}::{}Handle",
+        name_snail_case, name_camel_case
+    ))
+    .unwrap();
+    let trait_structure = TokenStream2::from_str(&format!(
+        "gateway_addon_rust::{}::{}Structure",
+        name_snail_case, name_camel_case
+    ))
     .unwrap();
+    let struct_handle = if let Some(generic_name) = generic_name {
+        let generic_name = TokenStream2::from_str(generic_name).unwrap();
+        quote! {
+            #struct_handle_base<<#struct_name #ty_generics as #trait_structure>::#generic_name>
+        }
+    } else {
+        struct_handle_base
+    };
     let fn_handle = TokenStream2::from_str(&format!("{}_handle", name_snail_case)).unwrap();
     let fn_handle_mut = TokenStream2::from_str(&format!("{}_handle_mut", name_snail_case)).unwrap();
-    let typedef = TokenStream2::from_str(&if let Some(generic_name) = generic_name {
-        format!(
-            "type {generic_name} = <{struct_name} as gateway_addon_rust::{name_snail_case}::{name_camel_case}Structure>::{generic_name};",
-            name_snail_case = name_snail_case,
-            name_camel_case = name_camel_case,
-            struct_name = struct_name,
-            generic_name = generic_name,
-        )
+    let typedef = if let Some(generic_name) = generic_name {
+        let generic_name = TokenStream2::from_str(generic_name).unwrap();
+        quote! {
+            type #generic_name = <#struct_name #ty_generics as #trait_structure>::#generic_name;
+        }
     } else {
-        "".to_owned()
-    })
-    .unwrap();
+        quote! {}
+    };
 
     quote! {
         #ast
-        impl #trait_build for #struct_name {
-            type #struct_built = #struct_built_name;
+        impl #impl_generics #trait_build for #struct_name #ty_generics #where_clause {
+            type #struct_built = #struct_built_name #ty_generics;
             fn build(data: Self, #fn_handle: #struct_handle) -> Self::#struct_built {
                 #struct_built_name { data, #fn_handle }
             }
         }
-        #visibility struct #struct_built_name {
-            data: #struct_name,
+        #visibility struct #struct_built_name #impl_generics #where_clause {
+            data: #struct_name #ty_generics,
             #fn_handle: #struct_handle,
         }
-        impl #trait_handle_wrapper for #struct_built_name {
+        impl #impl_generics #trait_handle_wrapper for #struct_built_name #ty_generics #where_clause {
             #typedef
             fn #fn_handle(&self) -> &#struct_handle {
                 &self.#fn_handle
@@ -114,16 +149,186 @@ fn alter_struct(
                 &mut self.#fn_handle
             }
         }
-        impl std::ops::Deref for #struct_built_name {
-            type Target = #struct_name;
+        impl #impl_generics std::ops::Deref for #struct_built_name #ty_generics #where_clause {
+            type Target = #struct_name #ty_generics;
             fn deref(&self) -> &Self::Target {
                 &self.data
             }
         }
-        impl std::ops::DerefMut for #struct_built_name {
+        impl #impl_generics std::ops::DerefMut for #struct_built_name #ty_generics #where_clause {
             fn deref_mut(&mut self) -> &mut Self::Target {
                 &mut self.data
             }
         }
     }
 }
+
+fn derive_simple_input(ast: DeriveInput) -> TokenStream2 {
+    let name = &ast.ident;
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+
+    let mut annotations = Vec::new();
+    if let Data::Struct(data) = &ast.data {
+        if let Fields::Named(fields) = &data.fields {
+            for field in &fields.named {
+                let field_name = field
+                    .ident
+                    .as_ref()
+                    .unwrap()
+                    .to_string()
+                    .trim_start_matches("r#")
+                    .to_owned();
+                for attr in field.attrs.iter().filter(|a| a.path.is_ident("webthings")) {
+                    let pairs = match parse_webthings_attr(attr) {
+                        Ok(pairs) => pairs,
+                        Err(err) => return err.to_compile_error(),
+                    };
+                    for (keyword, value) in pairs {
+                        annotations.push(quote! {
+                            gateway_addon_rust::action::InputAnnotation::new(#field_name, #keyword, #value)
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    quote! {
+        impl #impl_generics gateway_addon_rust::action::SimpleInput for #name #ty_generics #where_clause {
+            fn annotations() -> Vec<gateway_addon_rust::action::InputAnnotation> {
+                vec![#(#annotations),*]
+            }
+        }
+    }
+}
+
+fn derive_string_enum_value(ast: DeriveInput) -> TokenStream2 {
+    let message = "`StringEnumValue` has to be used with enums";
+    let data = match &ast.data {
+        Data::Enum(data) => data,
+        Data::Struct(data) => {
+            return syn::Error::new(data.struct_token.span, message).to_compile_error()
+        }
+        Data::Union(data) => {
+            return syn::Error::new(data.union_token.span, message).to_compile_error()
+        }
+    };
+
+    let name = &ast.ident;
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+
+    let mut variants = Vec::new();
+    let mut names = Vec::new();
+    for variant in &data.variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            return syn::Error::new_spanned(
+                variant,
+                "`StringEnumValue` only supports variants without data",
+            )
+            .to_compile_error();
+        }
+        let ident = &variant.ident;
+        let variant_name = serde_rename(&variant.attrs)
+            .unwrap_or_else(|| ident.to_string().trim_start_matches("r#").to_owned());
+        variants.push(quote! { Self::#ident });
+        names.push(variant_name);
+    }
+
+    quote! {
+        impl #impl_generics gateway_addon_rust::property::Value for #name #ty_generics #where_clause {
+            fn type_() -> gateway_addon_rust::type_::Type {
+                gateway_addon_rust::type_::Type::String
+            }
+
+            fn description(
+                description: gateway_addon_rust::PropertyDescription<Self>,
+            ) -> gateway_addon_rust::PropertyDescription<Self> {
+                description.enum_(vec![#(#variants),*])
+            }
+
+            fn serialize(
+                value: Self,
+            ) -> Result<Option<serde_json::Value>, gateway_addon_rust::error::WebthingsError> {
+                let name = match value {
+                    #(#variants => #names),*
+                };
+                Ok(Some(serde_json::Value::String(name.to_owned())))
+            }
+
+            fn deserialize(
+                value: Option<serde_json::Value>,
+            ) -> Result<Self, gateway_addon_rust::error::WebthingsError> {
+                gateway_addon_rust::property::deserialize_string_enum(
+                    value,
+                    &[#((#names, #variants)),*],
+                )
+            }
+        }
+    }
+}
+
+fn serde_rename(attrs: &[syn::Attribute]) -> Option<String> {
+    for attr in attrs.iter().filter(|a| a.path.is_ident("serde")) {
+        if let Ok(Meta::List(list)) = attr.parse_meta() {
+            for nested in list.nested {
+                if let NestedMeta::Meta(Meta::NameValue(name_value)) = nested {
+                    if name_value.path.is_ident("rename") {
+                        if let Lit::Str(rename) = name_value.lit {
+                            return Some(rename.value());
+                        }
+                    }
+                }
+            }
+        }
+    }
+    None
+}
+
+fn parse_webthings_attr(attr: &syn::Attribute) -> syn::Result<Vec<(String, Lit)>> {
+    let list = match attr.parse_meta() {
+        Ok(Meta::List(list)) => list,
+        _ => {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "Expected `#[webthings(key = value, ...)]`",
+            ))
+        }
+    };
+    list.nested
+        .into_iter()
+        .map(|nested| match nested {
+            NestedMeta::Meta(Meta::NameValue(name_value)) => {
+                let key = name_value.path.get_ident().ok_or_else(|| {
+                    syn::Error::new_spanned(
+                        &name_value.path,
+                        "Expected a plain keyword in `#[webthings(...)]`",
+                    )
+                })?;
+                Ok((keyword(&key.to_string()), name_value.lit))
+            }
+            nested => Err(syn::Error::new_spanned(
+                nested,
+                "Expected `key = value` in `#[webthings(...)]`",
+            )),
+        })
+        .collect()
+}
+
+fn keyword(key: &str) -> String {
+    if key == "at_type" {
+        return "@type".to_owned();
+    }
+    let mut keyword = String::new();
+    let mut upper = false;
+    for c in key.chars() {
+        if c == '_' {
+            upper = true;
+        } else if upper {
+            keyword.extend(c.to_uppercase());
+            upper = false;
+        } else {
+            keyword.push(c);
+        }
+    }
+    keyword
+}
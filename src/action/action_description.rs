@@ -4,9 +4,17 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
  */
 
-use crate::action::Input;
-
-use std::marker::PhantomData;
+use crate::{
+    action::Input,
+    description::{parse_at_type, IntoFullDescription},
+    error::WebthingsError,
+    fingerprint::fingerprint,
+};
+use serde::Deserialize;
+use std::{
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+};
 use webthings_gateway_ipc_types::{Action as FullActionDescription, Link};
 
 /// A struct which represents a WoT [action description][webthings_gateway_ipc_types::Action].
@@ -32,21 +40,30 @@ pub struct ActionDescription<T: Input> {
     pub input: Option<serde_json::Value>,
     pub links: Option<Vec<Link>>,
     pub title: Option<String>,
+    /// Schema of the output passed to [ActionHandle::finish_with][crate::ActionHandle::finish_with].
+    ///
+    /// Not forwarded to the gateway, as the IPC action description has no `output` field.
+    pub output: Option<serde_json::Value>,
     pub _input: PhantomData<T>,
 }
 
 /// Possible values of `@type` for an [action][ActionDescription].
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Deserialize)]
 pub enum AtType {
     FadeAction,
     LockAction,
     ToggleAction,
     UnlockAction,
+    /// A `@type` unknown to this crate, e.g. of a description parsed with `from_full`.
+    Other(String),
 }
 
 impl ToString for AtType {
     fn to_string(&self) -> String {
-        format!("{:?}", self)
+        match self {
+            AtType::Other(at_type) => at_type.clone(),
+            at_type => format!("{:?}", at_type),
+        }
     }
 }
 
@@ -60,6 +77,7 @@ impl<T: Input> ActionDescription<T> {
             links: None,
             title: None,
             input: T::input(),
+            output: None,
             _input: PhantomData,
         }
     }
@@ -108,19 +126,11 @@ impl<T: Input> ActionDescription<T> {
     /// # Examples
     /// ```
     /// # use gateway_addon_rust::action::ActionDescription;
-    /// # use webthings_gateway_ipc_types::Link;
+    /// # use gateway_addon_rust::link::{Link, LinkBuilder};
     /// # let _: ActionDescription<i32> =
     /// ActionDescription::default()
-    ///     .link(Link {
-    ///         href: "https://www.rust-lang.org/".to_owned(),
-    ///         media_type: None,
-    ///         rel: None,
-    ///     })
-    ///     .link(Link {
-    ///         href: "https://www.reddit.com/".to_owned(),
-    ///         media_type: None,
-    ///         rel: None,
-    ///     })
+    ///     .link(Link::new("https://www.rust-lang.org/"))
+    ///     .link(Link::new("https://www.reddit.com/"))
     /// # ;
     /// ```
     #[must_use]
@@ -139,6 +149,57 @@ impl<T: Input> ActionDescription<T> {
         self
     }
 
+    /// Set the schema of the [output][ActionDescription::output].
+    ///
+    /// Combine this with [NoInput][crate::action::NoInput] for actions which only return data.
+    ///
+    /// # Examples
+    #[cfg_attr(feature = "runtime", doc = "```")]
+    #[cfg_attr(not(feature = "runtime"), doc = "```ignore")]
+    /// # use gateway_addon_rust::{prelude::*, action::NoInput};
+    /// # use async_trait::async_trait;
+    /// # use serde_json::json;
+    /// struct GetLogsAction;
+    ///
+    /// #[async_trait]
+    /// impl Action for GetLogsAction {
+    ///     type Input = NoInput;
+    ///
+    ///     fn name(&self) -> String {
+    ///         "get-logs".to_owned()
+    ///     }
+    ///
+    ///     fn description(&self) -> ActionDescription<Self::Input> {
+    ///         ActionDescription::default()
+    ///             .title("Get logs")
+    ///             .output(json!({"type": "string"}))
+    ///     }
+    ///
+    ///     async fn perform(
+    ///         &mut self,
+    ///         mut action_handle: ActionHandle<Self::Input>,
+    ///     ) -> Result<(), String> {
+    ///         action_handle.start().await.map_err(|err| err.to_string())?;
+    ///         action_handle
+    ///             .finish_with(json!("everything is fine"))
+    ///             .await
+    ///             .map_err(|err| err.to_string())
+    ///     }
+    /// }
+    /// ```
+    #[must_use]
+    pub fn output(mut self, output: serde_json::Value) -> Self {
+        self.output = Some(output);
+        self
+    }
+
+    /// A stable fingerprint of the serialized description.
+    ///
+    /// Use this to detect whether a description changed, e.g. across restarts.
+    pub fn fingerprint(&self) -> u64 {
+        fingerprint(&self.clone().into_full_description())
+    }
+
     #[doc(hidden)]
     pub fn into_full_description(self) -> FullActionDescription {
         FullActionDescription {
@@ -149,4 +210,100 @@ impl<T: Input> ActionDescription<T> {
             title: self.title,
         }
     }
+
+    /// Parse an existing [full action description][FullActionDescription],
+    /// e.g. one received from another WoT source.
+    ///
+    /// The `input` schema is taken over as is, regardless of `T`.
+    /// Unknown `@type`s are kept as [AtType::Other].
+    pub fn from_full(description: FullActionDescription) -> Result<Self, WebthingsError> {
+        Ok(Self {
+            at_type: description
+                .at_type
+                .map(|at_type| parse_at_type(at_type, AtType::Other)),
+            description: description.description,
+            input: description.input,
+            links: description.links,
+            title: description.title,
+            output: None,
+            _input: PhantomData,
+        })
+    }
+}
+
+/// The name is not part of the IPC form of an action description.
+impl<T: Input> IntoFullDescription for ActionDescription<T> {
+    type FullDescription = FullActionDescription;
+
+    fn into_full_description(self, _name: String) -> Result<FullActionDescription, WebthingsError> {
+        Ok(ActionDescription::into_full_description(self))
+    }
+}
+
+impl<T: Input> PartialEq for ActionDescription<T> {
+    fn eq(&self, other: &Self) -> bool {
+        serde_json::to_value(self.clone().into_full_description()).ok()
+            == serde_json::to_value(other.clone().into_full_description()).ok()
+    }
+}
+
+impl<T: Input> Eq for ActionDescription<T> {}
+
+impl<T: Input> Hash for ActionDescription<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.fingerprint());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        action::{AtType, NoInput},
+        ActionDescription,
+    };
+    use serde_json::json;
+
+    #[test]
+    fn test_output_without_input() {
+        let description = ActionDescription::<NoInput>::default().output(json!({"type": "string"}));
+        assert_eq!(description.input, None);
+        assert_eq!(description.output, Some(json!({"type": "string"})));
+        assert_eq!(description.into_full_description().input, None);
+    }
+
+    #[test]
+    fn test_from_full() {
+        let full = ActionDescription::<serde_json::Value>::default()
+            .at_type(AtType::FadeAction)
+            .title("Foo")
+            .input(json!({"type": "object", "properties": {"bar": {"type": "foo"}}}))
+            .into_full_description();
+        let description = ActionDescription::<serde_json::Value>::from_full(full.clone()).unwrap();
+        assert_eq!(description.into_full_description(), full);
+    }
+
+    #[test]
+    fn test_from_full_unknown_at_type() {
+        let mut full = ActionDescription::<serde_json::Value>::default().into_full_description();
+        full.at_type = Some("FooAction".to_owned());
+        let description = ActionDescription::<serde_json::Value>::from_full(full.clone()).unwrap();
+        assert!(
+            matches!(description.at_type, Some(AtType::Other(ref at_type)) if at_type == "FooAction")
+        );
+        assert_eq!(description.into_full_description(), full);
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let description = ActionDescription::<i32>::default().title("Foo");
+        assert_eq!(description.fingerprint(), description.clone().fingerprint());
+        assert!(description == ActionDescription::<i32>::default().title("Foo"));
+        assert_ne!(
+            description.fingerprint(),
+            ActionDescription::<i32>::default()
+                .title("Bar")
+                .fingerprint()
+        );
+        assert!(description != ActionDescription::<i32>::default().title("Bar"));
+    }
 }
@@ -4,11 +4,21 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
  */
 
-use crate::{action::Input, client::Client, error::WebthingsError, Device};
+#[cfg(feature = "cancellation")]
+use crate::action::CancellationToken;
+use crate::{
+    action::Input,
+    client::{self, Client},
+    error::WebthingsError,
+    Device,
+};
 
 use chrono::{DateTime, Utc};
 
+#[cfg(feature = "cancellation")]
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::{
+    str::FromStr,
     sync::{Arc, Weak},
     time::SystemTime,
 };
@@ -18,6 +28,8 @@ use webthings_gateway_ipc_types::DeviceActionStatusNotificationMessageData;
 /// A struct which represents an instance of a WoT action.
 ///
 /// Use it to notify the gateway.
+///
+/// Prefer the id getters over the public id fields, which are going to become private.
 #[derive(Clone)]
 pub struct ActionHandle<T: Input> {
     pub(crate) client: Arc<Mutex<Client>>,
@@ -33,6 +45,53 @@ pub struct ActionHandle<T: Input> {
     pub status: Status,
     pub time_requested: DateTime<Utc>,
     pub time_completed: Option<DateTime<Utc>>,
+    /// Output of this action instance as set by [finish_with][ActionHandle::finish_with].
+    pub output: Option<serde_json::Value>,
+    /// Cancelled when the gateway requests to remove this action instance.
+    ///
+    /// # Examples
+    /// ```
+    /// # use gateway_addon_rust::{prelude::*, action::NoInput};
+    /// # use async_trait::async_trait;
+    /// # use std::time::Duration;
+    /// struct ScanAction;
+    ///
+    /// #[async_trait]
+    /// impl Action for ScanAction {
+    ///     type Input = NoInput;
+    ///     # fn name(&self) -> String {
+    ///     #     "scan".to_owned()
+    ///     # }
+    ///     # fn description(&self) -> ActionDescription<Self::Input> {
+    ///     #     ActionDescription::default()
+    ///     # }
+    ///     // ...
+    ///     async fn perform(
+    ///         &mut self,
+    ///         mut action_handle: ActionHandle<Self::Input>,
+    ///     ) -> Result<(), String> {
+    ///         tokio::spawn(async move {
+    ///             action_handle.start().await.unwrap();
+    ///             for _channel in 0..100 {
+    ///                 if action_handle.cancellation_token.is_cancelled() {
+    ///                     break;
+    ///                 }
+    ///                 // scan channel
+    ///                 tokio::time::sleep(Duration::from_millis(100)).await;
+    ///             }
+    ///             action_handle.finish().await.unwrap();
+    ///         });
+    ///         Ok(())
+    ///     }
+    /// }
+    /// ```
+    #[cfg(feature = "cancellation")]
+    pub cancellation_token: CancellationToken,
+    #[cfg(feature = "cancellation")]
+    pub(crate) alive: Arc<()>,
+    /// Whether the completion or failure of this instance was reported to the gateway, shared by all clones.
+    #[cfg(feature = "cancellation")]
+    pub(crate) terminated: Arc<AtomicBool>,
 }
 
 impl<T: Input> ActionHandle<T> {
@@ -61,9 +120,41 @@ impl<T: Input> ActionHandle<T> {
             status: Status::Created,
             time_requested: SystemTime::now().into(),
             time_completed: None,
+            output: None,
+            #[cfg(feature = "cancellation")]
+            cancellation_token: CancellationToken::new(),
+            #[cfg(feature = "cancellation")]
+            alive: Arc::new(()),
+            #[cfg(feature = "cancellation")]
+            terminated: Arc::new(AtomicBool::new(false)),
         }
     }
 
+    /// ID of the plugin this action instance belongs to.
+    pub fn plugin_id(&self) -> &str {
+        &self.plugin_id
+    }
+
+    /// ID of the adapter this action instance belongs to.
+    pub fn adapter_id(&self) -> &str {
+        &self.adapter_id
+    }
+
+    /// ID of the device this action instance belongs to.
+    pub fn device_id(&self) -> &str {
+        &self.device_id
+    }
+
+    /// Name of the action.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// ID of this action instance.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
     /// Notify the gateway that execution of this action instance has started.
     pub async fn start(&mut self) -> Result<(), WebthingsError> {
         self.status = Status::Pending;
@@ -79,7 +170,30 @@ impl<T: Input> ActionHandle<T> {
         Ok(())
     }
 
+    /// Notify the gateway that execution of this action instance has finished and record its output.
+    ///
+    /// Note that neither the IPC action status notification nor the response to the action request
+    /// currently have a field for action outputs. The output is therefore only retained in
+    /// [output][ActionHandle::output] and is not forwarded to the gateway.
+    pub async fn finish_with(&mut self, output: serde_json::Value) -> Result<(), WebthingsError> {
+        self.output = Some(output);
+        self.finish().await
+    }
+
+    /// Notify the gateway that execution of this action instance has failed.
+    #[cfg(feature = "cancellation")]
+    pub(crate) async fn fail(&mut self) -> Result<(), WebthingsError> {
+        self.status = Status::Error;
+        self.time_completed = Some(SystemTime::now().into());
+        self.status_notify().await
+    }
+
     async fn status_notify(&self) -> Result<(), WebthingsError> {
+        #[cfg(feature = "cancellation")]
+        if matches!(self.status, Status::Completed | Status::Error) {
+            self.terminated.store(true, Ordering::Relaxed);
+        }
+
         let message = DeviceActionStatusNotificationMessageData {
             plugin_id: self.plugin_id.clone(),
             adapter_id: self.adapter_id.clone(),
@@ -95,18 +209,19 @@ impl<T: Input> ActionHandle<T> {
         }
         .into();
 
-        self.client.lock().await.send_message(&message).await?;
+        client::send_message(&self.client, &message).await?;
 
         Ok(())
     }
 }
 
 /// Possible states of an [action][ActionHandle].
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Status {
     Created,
     Pending,
     Completed,
+    Error,
 }
 
 impl ToString for Status {
@@ -115,14 +230,38 @@ impl ToString for Status {
             Status::Created => "created",
             Status::Pending => "pending",
             Status::Completed => "completed",
+            Status::Error => "error",
         }
         .to_owned()
     }
 }
 
+impl FromStr for Status {
+    type Err = WebthingsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "created" => Ok(Status::Created),
+            "pending" => Ok(Status::Pending),
+            "completed" => Ok(Status::Completed),
+            "error" => Ok(Status::Error),
+            _ => Err(WebthingsError::Serialization(
+                <serde_json::Error as serde::de::Error>::custom(format!(
+                    "Unknown action status {}",
+                    s
+                )),
+            )),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::{action::NoInput, client::Client, ActionHandle};
+    use crate::{
+        action::{NoInput, Status},
+        client::Client,
+        ActionHandle,
+    };
 
     use rstest::{fixture, rstest};
     use serde_json::json;
@@ -210,4 +349,43 @@ mod tests {
 
         action.finish().await.unwrap();
     }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_action_finish_with(mut action: ActionHandle<NoInput>) {
+        action
+            .client
+            .lock()
+            .await
+            .expect_send_message()
+            .withf(move |msg| match msg {
+                Message::DeviceActionStatusNotification(msg) => {
+                    msg.data.action.id == ACTION_ID
+                        && msg.data.action.status == COMPLETED
+                        && msg.data.action.time_completed.is_some()
+                }
+                _ => false,
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+
+        action.finish_with(json!(42)).await.unwrap();
+        assert_eq!(action.output, Some(json!(42)));
+    }
+
+    #[rstest]
+    #[case(Status::Created)]
+    #[case(Status::Pending)]
+    #[case(Status::Completed)]
+    fn test_status_roundtrip(#[case] status: Status) {
+        assert_eq!(status.to_string().parse::<Status>().unwrap(), status);
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("deleted")]
+    #[case("Pending")]
+    fn test_status_unknown(#[case] status: &str) {
+        assert!(status.parse::<Status>().is_err());
+    }
 }
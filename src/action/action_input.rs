@@ -86,16 +86,42 @@ pub trait Input: Clone + Send + Sync + 'static {
 /// }
 /// impl SimpleInput for Foo {}
 /// ```
+///
+/// WoT keywords which [JsonSchema] cannot express can be added per field using the
+/// [SimpleInput][macro@SimpleInput] derive.
 pub trait SimpleInput: DeserializeOwned + JsonSchema + Clone + Send + Sync + 'static {
     /// WoT type to be used in the form of a json schema.
+    ///
+    /// Logs a warning and advertises no input schema if [try_input][SimpleInput::try_input] fails,
+    /// in which case inputs are not validated by the gateway. Override this to return [None] in
+    /// order to intentionally have no input schema.
     fn input() -> Option<serde_json::Value> {
-        if let Ok(schema) = serde_json::to_value(&schema_for!(Self)) {
-            Some(schema)
-        } else {
-            None
+        match Self::try_input() {
+            Ok(schema) => Some(schema),
+            Err(err) => {
+                log::warn!(
+                    "Could not generate input schema of {}, inputs are not validated: {}",
+                    std::any::type_name::<Self>(),
+                    err
+                );
+                None
+            }
         }
     }
 
+    /// Generate the json schema of this type using [JsonSchema].
+    fn try_input() -> Result<serde_json::Value, WebthingsError> {
+        let mut schema =
+            serde_json::to_value(&schema_for!(Self)).map_err(WebthingsError::Serialization)?;
+        annotate_schema(&mut schema, Self::annotations());
+        Ok(schema)
+    }
+
+    /// WoT keywords which are injected into the generated json schema of the respective fields.
+    fn annotations() -> Vec<InputAnnotation> {
+        Vec::new()
+    }
+
     /// Deviations from the default [action description][ActionDescription].
     fn description(description: ActionDescription<Self>) -> ActionDescription<Self> {
         description
@@ -107,6 +133,66 @@ pub trait SimpleInput: DeserializeOwned + JsonSchema + Clone + Send + Sync + 'st
     }
 }
 
+/// Use this on a struct to derive [SimpleInput][trait@SimpleInput], injecting WoT keywords from
+/// `#[webthings(...)]` field attributes into the generated json schema.
+///
+/// Keywords are converted to camel case, `at_type` becomes `@type`. Fields are looked up by their
+/// Rust name, so do not combine this with `#[serde(rename)]` on annotated fields.
+///
+/// # Examples
+/// ```
+/// # use serde::Deserialize;
+/// # use schemars::JsonSchema;
+/// # use gateway_addon_rust::action::SimpleInput;
+/// #[derive(Deserialize, JsonSchema, Clone, SimpleInput)]
+/// struct FadeInput {
+///     #[webthings(unit = "percent", minimum = 0, maximum = 100)]
+///     level: u8,
+///     #[webthings(unit = "second", minimum = 0)]
+///     duration: u32,
+/// }
+/// ```
+pub use gateway_addon_rust_codegen::SimpleInput;
+
+/// A WoT keyword which is injected into the json schema of a field of a [SimpleInput].
+#[derive(Clone, Debug, PartialEq)]
+pub struct InputAnnotation {
+    pub field: String,
+    pub keyword: String,
+    pub value: serde_json::Value,
+}
+
+impl InputAnnotation {
+    pub fn new(
+        field: impl Into<String>,
+        keyword: impl Into<String>,
+        value: impl Into<serde_json::Value>,
+    ) -> Self {
+        Self {
+            field: field.into(),
+            keyword: keyword.into(),
+            value: value.into(),
+        }
+    }
+}
+
+fn annotate_schema(schema: &mut serde_json::Value, annotations: Vec<InputAnnotation>) {
+    for annotation in annotations {
+        let field_schema = schema
+            .get_mut("properties")
+            .and_then(|properties| properties.get_mut(&annotation.field))
+            .and_then(|field_schema| field_schema.as_object_mut());
+        if let Some(field_schema) = field_schema {
+            field_schema.insert(annotation.keyword, annotation.value);
+        } else {
+            log::warn!(
+                "Could not annotate unknown input field {:?}",
+                annotation.field
+            );
+        }
+    }
+}
+
 impl<T: SimpleInput> Input for T {
     fn input() -> Option<serde_json::Value> {
         <T as SimpleInput>::input()
@@ -411,4 +497,26 @@ mod tests {
         assert!(TestInput::deserialize(json!(42)).is_err());
         assert!(TestInput::deserialize(json!(null)).is_err());
     }
+
+    #[derive(Clone, JsonSchema, serde::Deserialize)]
+    struct NoSchemaInput;
+
+    impl action::SimpleInput for NoSchemaInput {
+        fn input() -> Option<serde_json::Value> {
+            None
+        }
+    }
+
+    #[test]
+    fn test_input_testinput() {
+        let schema = <TestInput as action::SimpleInput>::try_input().unwrap();
+        assert_eq!(schema["properties"]["i"]["type"], json!("integer"));
+        assert_eq!(TestInput::input(), Some(schema));
+    }
+
+    #[test]
+    fn test_input_intentionally_none() {
+        assert!(<NoSchemaInput as action::SimpleInput>::try_input().is_ok());
+        assert_eq!(NoSchemaInput::input(), None);
+    }
 }
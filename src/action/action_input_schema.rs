@@ -0,0 +1,284 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+
+use crate::type_::Type;
+use serde_json::{json, Map, Value};
+
+/// A builder for json schemas of [action inputs][crate::ActionDescription::input].
+///
+/// Modifiers like [min][InputSchema::min] apply to the property added last,
+/// or to the schema itself if no property has been added yet.
+///
+/// # Examples
+/// ```
+/// # use gateway_addon_rust::{prelude::*, action::InputSchema, type_::Type};
+/// let schema = InputSchema::object()
+///     .prop("level", Type::Integer)
+///     .min(0)
+///     .max(100)
+///     .unit("percent")
+///     .required()
+///     .prop("duration", Type::Integer)
+///     .min(0)
+///     .unit("second");
+///
+/// ActionDescription::<serde_json::Value>::default().input(schema.build())
+/// # ;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputSchema {
+    schema: Map<String, Value>,
+    properties: Map<String, Value>,
+    required: Vec<String>,
+    current: Option<String>,
+}
+
+/// # Builder methods
+impl InputSchema {
+    /// Build a schema of the given [type][Type].
+    pub fn of(type_: Type) -> Self {
+        let mut schema = Map::new();
+        schema.insert("type".to_owned(), json!(type_));
+        Self {
+            schema,
+            properties: Map::new(),
+            required: Vec::new(),
+            current: None,
+        }
+    }
+
+    /// Build a schema of an object.
+    pub fn object() -> Self {
+        Self::of(Type::Object)
+    }
+
+    /// Build a schema of an array whose items match the given schema.
+    pub fn array(items: InputSchema) -> Self {
+        Self::of(Type::Array).items(items)
+    }
+
+    /// Add a property of the given [type][Type].
+    #[must_use]
+    pub fn prop(self, name: impl Into<String>, type_: Type) -> Self {
+        self.prop_schema(name, Self::of(type_))
+    }
+
+    /// Add a property with the given schema, e.g. a nested object.
+    #[must_use]
+    pub fn prop_schema(mut self, name: impl Into<String>, schema: InputSchema) -> Self {
+        let name = name.into();
+        self.properties.insert(name.clone(), schema.build());
+        self.current = Some(name);
+        self
+    }
+
+    /// Mark the property added last as required.
+    #[must_use]
+    pub fn required(mut self) -> Self {
+        if let Some(ref current) = self.current {
+            if !self.required.contains(current) {
+                self.required.push(current.clone());
+            }
+        }
+        self
+    }
+
+    /// Set `minimum`.
+    #[must_use]
+    pub fn min(self, minimum: impl Into<Value>) -> Self {
+        self.keyword("minimum", minimum)
+    }
+
+    /// Set `maximum`.
+    #[must_use]
+    pub fn max(self, maximum: impl Into<Value>) -> Self {
+        self.keyword("maximum", maximum)
+    }
+
+    /// Set `multipleOf`.
+    #[must_use]
+    pub fn multiple_of(self, multiple_of: impl Into<Value>) -> Self {
+        self.keyword("multipleOf", multiple_of)
+    }
+
+    /// Set `items`, the schema of the items of an array.
+    #[must_use]
+    pub fn items(self, items: InputSchema) -> Self {
+        self.keyword("items", items.build())
+    }
+
+    /// Set `minItems`.
+    #[must_use]
+    pub fn min_items(self, min_items: u64) -> Self {
+        self.keyword("minItems", min_items)
+    }
+
+    /// Set `maxItems`.
+    #[must_use]
+    pub fn max_items(self, max_items: u64) -> Self {
+        self.keyword("maxItems", max_items)
+    }
+
+    /// Set `unit`.
+    #[must_use]
+    pub fn unit(self, unit: impl Into<String>) -> Self {
+        self.keyword("unit", unit.into())
+    }
+
+    /// Set `title`.
+    #[must_use]
+    pub fn title(self, title: impl Into<String>) -> Self {
+        self.keyword("title", title.into())
+    }
+
+    /// Set `description`.
+    #[must_use]
+    pub fn description(self, description: impl Into<String>) -> Self {
+        self.keyword("description", description.into())
+    }
+
+    /// Set an arbitrary keyword.
+    #[must_use]
+    pub fn keyword(mut self, keyword: impl Into<String>, value: impl Into<Value>) -> Self {
+        let target = match self.current {
+            Some(ref current) => self
+                .properties
+                .get_mut(current)
+                .and_then(Value::as_object_mut),
+            None => Some(&mut self.schema),
+        };
+        if let Some(target) = target {
+            target.insert(keyword.into(), value.into());
+        }
+        self
+    }
+}
+
+impl InputSchema {
+    /// Build the json schema.
+    pub fn build(self) -> Value {
+        let mut schema = self.schema;
+        if !self.properties.is_empty() {
+            schema.insert("properties".to_owned(), Value::Object(self.properties));
+        }
+        if !self.required.is_empty() {
+            schema.insert("required".to_owned(), json!(self.required));
+        }
+        Value::Object(schema)
+    }
+}
+
+impl From<InputSchema> for Value {
+    fn from(schema: InputSchema) -> Self {
+        schema.build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{action::InputSchema, type_::Type};
+    use serde_json::json;
+
+    #[test]
+    fn test_primitive() {
+        assert_eq!(
+            InputSchema::of(Type::Integer).min(0).max(100).build(),
+            json!({"type": "integer", "minimum": 0, "maximum": 100})
+        );
+    }
+
+    #[test]
+    fn test_object() {
+        let schema = InputSchema::object()
+            .prop("level", Type::Integer)
+            .min(0)
+            .max(100)
+            .unit("percent")
+            .required()
+            .prop("duration", Type::Integer)
+            .min(0)
+            .unit("second");
+        assert_eq!(
+            schema.build(),
+            json!({
+                "type": "object",
+                "properties": {
+                    "level": {
+                        "type": "integer",
+                        "unit": "percent",
+                        "minimum": 0,
+                        "maximum": 100,
+                    },
+                    "duration": {
+                        "type": "integer",
+                        "unit": "second",
+                        "minimum": 0,
+                    }
+                },
+                "required": ["level"],
+            })
+        );
+    }
+
+    #[test]
+    fn test_nested() {
+        let schema = InputSchema::object()
+            .prop_schema(
+                "color",
+                InputSchema::object()
+                    .prop("hue", Type::Number)
+                    .required()
+                    .required(),
+            )
+            .required();
+        assert_eq!(
+            schema.build(),
+            json!({
+                "type": "object",
+                "properties": {
+                    "color": {
+                        "type": "object",
+                        "properties": {
+                            "hue": {"type": "number"}
+                        },
+                        "required": ["hue"],
+                    }
+                },
+                "required": ["color"],
+            })
+        );
+    }
+
+    #[test]
+    fn test_array() {
+        let schema = InputSchema::object()
+            .prop_schema(
+                "levels",
+                InputSchema::array(InputSchema::of(Type::Integer).multiple_of(10)),
+            )
+            .max_items(3)
+            .required();
+        assert_eq!(
+            schema.build(),
+            json!({
+                "type": "object",
+                "properties": {
+                    "levels": {
+                        "type": "array",
+                        "items": {"type": "integer", "multipleOf": 10},
+                        "maxItems": 3,
+                    }
+                },
+                "required": ["levels"],
+            })
+        );
+    }
+
+    #[test]
+    fn test_empty_object() {
+        assert_eq!(InputSchema::object().build(), json!({"type": "object"}));
+    }
+}
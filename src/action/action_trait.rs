@@ -4,12 +4,13 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
  */
 
-use crate::{action::Input, ActionDescription, ActionHandle};
+use crate::{
+    action::{Input, InputValidator, JsonSchemaValidator},
+    ActionDescription, ActionHandle,
+};
 use as_any::{AsAny, Downcast};
 use async_trait::async_trait;
 
-use jsonschema::JSONSchema;
-
 use webthings_gateway_ipc_types::Action as FullActionDescription;
 
 /// A trait used to specify the structure and behaviour of a WoT action.
@@ -71,6 +72,14 @@ pub trait Action: Send + Sync + 'static {
     /// Called once after initialization.
     fn post_init(&mut self) {}
 
+    /// [Validator][InputValidator] used to check inputs against the input schema of this action.
+    ///
+    /// Defaults to [JsonSchemaValidator]. Return [NoValidation][crate::action::NoValidation]
+    /// to skip validation.
+    fn input_validator(&self) -> Box<dyn InputValidator> {
+        Box::new(JsonSchemaValidator)
+    }
+
     #[doc(hidden)]
     fn full_description(&self) -> FullActionDescription {
         self.description().into_full_description()
@@ -82,24 +91,20 @@ pub trait Action: Send + Sync + 'static {
         action_handle: ActionHandle<serde_json::Value>,
     ) -> Result<(), String> {
         if let Some(ref input_schema) = self.description().input {
-            let schema = JSONSchema::compile(input_schema).map_err(|err| {
-                format!(
-                    "Failed to parse input schema for action {:?}: {:?}",
-                    self.name(),
-                    err
-                )
-            })?;
-            schema.validate(&action_handle.input).map_err(|err| {
-                format!(
-                    "Failed to validate input for action {:?}: {:?}",
-                    self.name(),
-                    err.collect::<Vec<_>>()
-                )
-            })?;
+            self.input_validator()
+                .validate(input_schema, &action_handle.input)
+                .map_err(|err| {
+                    format!(
+                        "Failed to validate input for action {:?}: {}",
+                        self.name(),
+                        err
+                    )
+                })?;
         }
         let input = Self::Input::deserialize(action_handle.input.clone())
             .map_err(|err| format!("Could not deserialize input: {:?}", err))?;
-        self.perform(ActionHandle::new(
+        #[allow(unused_mut)]
+        let mut typed_action_handle = ActionHandle::new(
             action_handle.client,
             action_handle.device,
             action_handle.plugin_id,
@@ -109,8 +114,14 @@ pub trait Action: Send + Sync + 'static {
             action_handle.id,
             input,
             action_handle.input,
-        ))
-        .await
+        );
+        #[cfg(feature = "cancellation")]
+        {
+            typed_action_handle.cancellation_token = action_handle.cancellation_token;
+            typed_action_handle.alive = action_handle.alive;
+            typed_action_handle.terminated = action_handle.terminated;
+        }
+        self.perform(typed_action_handle).await
     }
 }
 
@@ -190,6 +201,7 @@ pub(crate) mod tests {
         action_name: String,
         pub action_helper: MockActionHelper<T>,
         pub expect_post_init: bool,
+        pub description: ActionDescription<T>,
     }
 
     impl<T: Input> MockAction<T> {
@@ -198,6 +210,7 @@ pub(crate) mod tests {
                 action_name,
                 expect_post_init: false,
                 action_helper: MockActionHelper::new(),
+                description: ActionDescription::default(),
             }
         }
     }
@@ -225,7 +238,7 @@ pub(crate) mod tests {
         }
 
         fn description(&self) -> ActionDescription<Self::Input> {
-            ActionDescription::default()
+            self.description.clone()
         }
 
         async fn perform(
@@ -0,0 +1,113 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+
+use crate::error::WebthingsError;
+use jsonschema::JSONSchema;
+
+/// A trait used to validate [action][crate::Action] inputs against the input schema of the action.
+///
+/// Implement this to control e.g. the schema draft or custom format checks.
+///
+/// # Examples
+/// ```
+/// # use gateway_addon_rust::action::InputValidator;
+/// struct EmailValidator;
+///
+/// impl InputValidator for EmailValidator {
+///     fn validate(
+///         &self,
+///         _schema: &serde_json::Value,
+///         input: &serde_json::Value,
+///     ) -> Result<(), String> {
+///         match input.as_str() {
+///             Some(email) if email.contains('@') => Ok(()),
+///             _ => Err("Expected an email address".to_owned()),
+///         }
+///     }
+/// }
+/// ```
+pub trait InputValidator: Send + Sync + 'static {
+    /// Validate the given input against the given schema.
+    fn validate(&self, schema: &serde_json::Value, input: &serde_json::Value)
+        -> Result<(), String>;
+}
+
+/// The default [InputValidator] which uses the [jsonschema] crate.
+pub struct JsonSchemaValidator;
+
+impl InputValidator for JsonSchemaValidator {
+    fn validate(
+        &self,
+        schema: &serde_json::Value,
+        input: &serde_json::Value,
+    ) -> Result<(), String> {
+        let schema = JSONSchema::compile(schema)
+            .map_err(|err| format!("Failed to parse input schema: {:?}", err))?;
+        schema
+            .validate(input)
+            .map_err(|err| format!("{:?}", err.collect::<Vec<_>>()))
+    }
+}
+
+/// Check that the input schema of an action is itself a valid json schema.
+#[cfg_attr(not(feature = "runtime"), allow(dead_code))]
+pub(crate) fn check_input_schema(
+    action_name: &str,
+    schema: &serde_json::Value,
+) -> Result<(), WebthingsError> {
+    JSONSchema::compile(schema)
+        .map(|_| ())
+        .map_err(|err| WebthingsError::InvalidInputSchema(action_name.to_owned(), err.to_string()))
+}
+
+/// An [InputValidator] which accepts every input.
+///
+/// Use this to skip validation for trusted inputs.
+pub struct NoValidation;
+
+impl InputValidator for NoValidation {
+    fn validate(
+        &self,
+        _schema: &serde_json::Value,
+        _input: &serde_json::Value,
+    ) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::action::{check_input_schema, InputValidator, JsonSchemaValidator, NoValidation};
+    use serde_json::json;
+
+    #[test]
+    fn test_json_schema_validator() {
+        let schema = json!({"type": "integer", "minimum": 0});
+        assert!(JsonSchemaValidator.validate(&schema, &json!(42)).is_ok());
+        assert!(JsonSchemaValidator.validate(&schema, &json!(-1)).is_err());
+        assert!(JsonSchemaValidator
+            .validate(&schema, &json!("foo"))
+            .is_err());
+    }
+
+    #[test]
+    fn test_json_schema_validator_invalid_schema() {
+        let schema = json!({"type": 42});
+        assert!(JsonSchemaValidator.validate(&schema, &json!(42)).is_err());
+    }
+
+    #[test]
+    fn test_check_input_schema() {
+        assert!(check_input_schema("fade", &json!({"type": "integer", "minimum": 0})).is_ok());
+        assert!(check_input_schema("fade", &json!({"type": 42})).is_err());
+    }
+
+    #[test]
+    fn test_no_validation() {
+        let schema = json!({"type": "integer"});
+        assert!(NoValidation.validate(&schema, &json!("foo")).is_ok());
+    }
+}
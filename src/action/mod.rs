@@ -7,16 +7,31 @@
 //! A module for everything related to WoT actions.
 
 mod action_description;
+#[cfg(feature = "runtime")]
 mod action_handle;
 mod action_input;
+mod action_input_schema;
+#[cfg(feature = "runtime")]
 mod action_trait;
+mod action_validator;
 
 pub use action_description::*;
+#[cfg(feature = "runtime")]
 pub use action_handle::*;
 pub use action_input::*;
+pub use action_input_schema::*;
+#[cfg(feature = "runtime")]
 pub use action_trait::*;
+pub use action_validator::*;
+
+/// A token which signals that an [action][Action] instance should stop, see [ActionHandle::cancellation_token].
+///
+/// Re-exported so that addons use the same version of `tokio-util` as this crate.
+#[cfg(feature = "cancellation")]
+pub use tokio_util::sync::CancellationToken;
 
 /// Convenience type for a collection of [ActionBase].
+#[cfg(feature = "runtime")]
 pub type Actions = Vec<Box<dyn ActionBase>>;
 
 /// Convenience macro for building an [Actions].
@@ -27,6 +42,7 @@ pub type Actions = Vec<Box<dyn ActionBase>>;
 /// actions![ExampleAction::new()]
 /// # ;
 /// ```
+#[cfg(feature = "runtime")]
 #[macro_export]
 macro_rules! actions [
     ($($e:expr),*) => ({
@@ -35,7 +51,7 @@ macro_rules! actions [
     })
 ];
 
-#[cfg(test)]
+#[cfg(all(test, feature = "runtime"))]
 pub(crate) mod tests {
     pub use super::action_trait::tests::*;
 }
@@ -5,21 +5,34 @@
  */
 
 use crate::{
-    client::Client, device::DeviceBuilder, error::WebthingsError, Adapter, Device, DeviceHandle,
+    client::{self, Client},
+    device::{
+        full_device_description, validate_device, validate_ids, AsyncDeviceBuilder, DeviceBuilder,
+        DeviceDescriptionDiff, DeviceStructure,
+    },
+    error::WebthingsError,
+    plugin::{default_log_prefix, mailbox_key, Mailboxes},
+    runtime::spawn,
+    Actions, Adapter, Device, DeviceDescription, DeviceHandle, Events, Properties,
 };
+use futures::future::BoxFuture;
 use std::{
     collections::HashMap,
+    future::Future,
     sync::{Arc, Weak},
+    time::{Duration, Instant},
 };
-use tokio::sync::Mutex;
+use tokio::{runtime::Handle, sync::Mutex, task::JoinHandle};
 use webthings_gateway_ipc_types::{
     AdapterRemoveDeviceResponseMessageData, AdapterUnloadResponseMessageData,
-    DeviceAddedNotificationMessageData, Message,
+    Device as FullDeviceDescription, DeviceAddedNotificationMessageData, Message,
 };
 
 /// A struct which represents an instance of a WebthingsIO adapter.
 ///
 /// Use it to notify the gateway.
+///
+/// Prefer the id getters over the public id fields, which are going to become private.
 #[derive(Clone)]
 pub struct AdapterHandle {
     pub(crate) client: Arc<Mutex<Client>>,
@@ -27,6 +40,32 @@ pub struct AdapterHandle {
     pub plugin_id: String,
     pub adapter_id: String,
     devices: HashMap<String, Arc<Mutex<Box<dyn Device>>>>,
+    pub(crate) runtime: Option<Handle>,
+    pub(crate) log_prefix: String,
+    pub(crate) mailboxes: Mailboxes,
+    pairing_deadline: Option<Instant>,
+    duplicate_pairing_behavior: DuplicatePairingBehavior,
+    discovery_tasks: Vec<Arc<JoinHandle<()>>>,
+    id_normalizer: Option<IdNormalizer>,
+    raw_ids: HashMap<String, String>,
+}
+
+/// A function which turns the raw id of a [device][crate::Device] into the id advertised to the gateway.
+///
+/// See [AdapterHandle::set_id_normalizer].
+pub type IdNormalizer = Arc<dyn Fn(&str) -> String + Send + Sync>;
+
+/// How an [adapter][AdapterHandle] reacts to the gateway starting pairing while pairing is already active.
+///
+/// This happens when the add things view is reopened before the previous pairing timed out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicatePairingBehavior {
+    /// Call [Adapter::on_start_pairing][crate::Adapter::on_start_pairing] again.
+    #[default]
+    Restart,
+    /// Only extend the [pairing deadline][AdapterHandle::pairing_deadline] without calling
+    /// [Adapter::on_start_pairing][crate::Adapter::on_start_pairing] again.
+    Extend,
 }
 
 impl AdapterHandle {
@@ -34,45 +73,177 @@ impl AdapterHandle {
         Self {
             client,
             weak: Weak::new(),
+            log_prefix: default_log_prefix(&plugin_id),
             plugin_id,
             adapter_id,
             devices: HashMap::new(),
+            runtime: None,
+            mailboxes: Mailboxes::default(),
+            pairing_deadline: None,
+            duplicate_pairing_behavior: DuplicatePairingBehavior::default(),
+            discovery_tasks: Vec::new(),
+            id_normalizer: None,
+            raw_ids: HashMap::new(),
+        }
+    }
+
+    /// ID of the plugin this adapter belongs to.
+    pub fn plugin_id(&self) -> &str {
+        &self.plugin_id
+    }
+
+    /// ID of this adapter.
+    pub fn adapter_id(&self) -> &str {
+        &self.adapter_id
+    }
+
+    /// Set a function which normalizes the ids of all devices added afterwards.
+    ///
+    /// Use it to sanitize ids derived from hardware, e.g. MAC addresses containing colons.
+    /// The device is advertised with the normalized id, [get_device][AdapterHandle::get_device]
+    /// and [remove_device][AdapterHandle::remove_device] accept both the raw and the normalized id.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use gateway_addon_rust::AdapterHandle;
+    /// # use std::sync::Arc;
+    /// # fn f(adapter_handle: &mut AdapterHandle) {
+    /// adapter_handle.set_id_normalizer(Arc::new(|id| id.replace(':', "-")));
+    /// # }
+    /// ```
+    pub fn set_id_normalizer(&mut self, id_normalizer: IdNormalizer) {
+        self.id_normalizer = Some(id_normalizer);
+    }
+
+    /// Remove the function set by [set_id_normalizer][AdapterHandle::set_id_normalizer].
+    pub fn clear_id_normalizer(&mut self) {
+        self.id_normalizer = None;
+    }
+
+    /// Resolve a raw or normalized device id to the id the device is known by.
+    fn resolve_id(&self, id: &str) -> Option<String> {
+        if self.devices.contains_key(id) {
+            Some(id.to_owned())
+        } else {
+            self.raw_ids.get(id).cloned()
+        }
+    }
+
+    /// Apply the [id normalizer][AdapterHandle::set_id_normalizer] to a raw device id.
+    ///
+    /// Fails with [WebthingsError::IdCollision] if a device with a different raw id already uses
+    /// the normalized id.
+    fn normalize_id(&self, raw_id: &str) -> Result<String, WebthingsError> {
+        let id = match &self.id_normalizer {
+            Some(id_normalizer) => id_normalizer(raw_id),
+            None => return Ok(raw_id.to_owned()),
+        };
+
+        if self.devices.contains_key(&id) {
+            let existing_raw_id = self
+                .raw_ids
+                .iter()
+                .find(|(_, normalized)| **normalized == id)
+                .map_or(id.as_str(), |(raw_id, _)| raw_id.as_str());
+            if existing_raw_id != raw_id {
+                return Err(WebthingsError::IdCollision(raw_id.to_owned(), id));
+            }
         }
+
+        Ok(id)
     }
 
     /// Build and add a new device using the given data struct.
+    ///
+    /// Fails with [WebthingsError::InvalidId] if the id of the device or the name of any of its
+    /// properties, actions or events is empty or contains control characters.
+    /// See [DeviceBuilder::validate] for the other checks run before the device is added.
+    ///
+    /// The id is normalized first if an [id normalizer][AdapterHandle::set_id_normalizer] is set.
+    ///
+    /// The device is only added to this adapter once the gateway has been notified successfully.
     pub async fn add_device<D: DeviceBuilder>(
         &mut self,
         device: D,
     ) -> Result<Arc<Mutex<Box<dyn Device>>>, WebthingsError> {
+        device.validate()?;
         let device_description = device.full_description()?;
+        let description = device.description();
+        let properties = device.properties();
+        let actions = device.actions();
+        let events = device.events();
 
-        let message: Message = DeviceAddedNotificationMessageData {
-            plugin_id: self.plugin_id.clone(),
-            adapter_id: self.adapter_id.clone(),
-            device: device_description.clone(),
-        }
-        .into();
+        self.add_built_device(
+            device_description,
+            description,
+            properties,
+            actions,
+            events,
+            |device_handle| Box::new(D::build(device, device_handle)),
+        )
+        .await
+    }
+
+    /// Build and add a new device using the given [asynchronous builder][AsyncDeviceBuilder].
+    pub async fn add_device_async<D: AsyncDeviceBuilder>(
+        &mut self,
+        device: D,
+    ) -> Result<Arc<Mutex<Box<dyn Device>>>, WebthingsError> {
+        let description = device.description().await;
+        let properties = device.properties().await;
+        let actions = device.actions().await;
+        let events = device.events().await;
+
+        validate_device(&device.id(), &properties, &actions, &events)?;
 
-        self.client.lock().await.send_message(&message).await?;
+        let device_description = full_device_description(
+            device.id(),
+            description.clone(),
+            &properties,
+            &actions,
+            &events,
+        )?;
+
+        self.add_built_device(
+            device_description,
+            description,
+            properties,
+            actions,
+            events,
+            |device_handle| Box::new(D::build(device, device_handle)),
+        )
+        .await
+    }
+
+    async fn add_built_device(
+        &mut self,
+        device_description: FullDeviceDescription,
+        description: DeviceDescription,
+        properties: Properties,
+        actions: Actions,
+        events: Events,
+        build: impl FnOnce(DeviceHandle) -> Box<dyn Device>,
+    ) -> Result<Arc<Mutex<Box<dyn Device>>>, WebthingsError> {
+        let mut device_description = device_description;
+        let raw_id = device_description.id.clone();
+        device_description.id = self.normalize_id(&raw_id)?;
+
+        validate_ids(&device_description)?;
 
         let id = device_description.id.clone();
 
-        let device_handle = DeviceHandle::new(
+        let mut device_handle = DeviceHandle::new(
             self.client.clone(),
             self.weak.clone(),
             self.plugin_id.clone(),
             self.adapter_id.clone(),
-            device.id(),
-            device.description(),
+            id.clone(),
+            description,
         );
+        device_handle.runtime = self.runtime.clone();
+        device_handle.log_prefix = self.log_prefix.clone();
 
-        let properties = device.properties();
-        let actions = device.actions();
-        let events = device.events();
-
-        let device: Arc<Mutex<Box<dyn Device>>> =
-            Arc::new(Mutex::new(Box::new(D::build(device, device_handle))));
+        let device: Arc<Mutex<Box<dyn Device>>> = Arc::new(Mutex::new(build(device_handle)));
         let device_weak = Arc::downgrade(&device);
 
         {
@@ -93,19 +264,230 @@ impl AdapterHandle {
             }
         }
 
+        // Only advertise and insert the device once it is fully wired up
+        let message: Message = DeviceAddedNotificationMessageData {
+            plugin_id: self.plugin_id.clone(),
+            adapter_id: self.adapter_id.clone(),
+            device: device_description.clone(),
+        }
+        .into();
+
+        client::send_message(&self.client, &message).await?;
+        device.lock().await.device_handle_mut().advertised = Some(device_description);
+
+        if raw_id != id {
+            self.raw_ids.insert(raw_id, id.clone());
+        }
         self.devices.insert(id, device.clone());
+        {
+            let mut device = device.lock().await;
+            let device_handle = device.device_handle();
+            if let Err(err) = device_handle.notify_connected().await {
+                log::warn!(
+                    "{}Could not send initial connected state of device {}: {}",
+                    self.log_prefix,
+                    device_handle.device_id,
+                    err
+                );
+            }
+            device.device_handle_mut().start_polling().await;
+            device.device_handle_mut().start_liveness();
+            device.post_init();
+        }
 
         Ok(device)
     }
 
+    /// Advertise the changed structure of a [device][crate::Device] which this adapter owns.
+    ///
+    /// The full description of the given data struct is compared to the one last sent to the
+    /// gateway. If only the descriptions of existing properties changed, each changed property is
+    /// advertised on its own. Otherwise the whole device is advertised again. Nothing is sent if
+    /// the structure did not change.
+    ///
+    /// The descriptions held by the [device handle][DeviceHandle] and its property handles are
+    /// updated once the messages were sent, but properties, actions and events are not added or removed.
+    /// If sending fails, the descriptions are left untouched, so calling this again advertises
+    /// the same changes.
+    pub async fn readvertise_device<D: DeviceStructure>(
+        &mut self,
+        device: &D,
+    ) -> Result<DeviceDescriptionDiff, WebthingsError> {
+        let raw_id = device.id();
+        let id = self
+            .resolve_id(&raw_id)
+            .ok_or_else(|| WebthingsError::UnknownDevice(raw_id.clone()))?;
+
+        let description = device.description();
+        let properties = device.properties();
+        let actions = device.actions();
+        let events = device.events();
+        validate_device(&raw_id, &properties, &actions, &events)?;
+        let mut device_description =
+            full_device_description(raw_id, description.clone(), &properties, &actions, &events)?;
+        device_description.id = id.clone();
+
+        let device = self.devices[&id].clone();
+        let mut device = device.lock().await;
+        let device_handle = device.device_handle_mut();
+
+        let diff = match &device_handle.advertised {
+            Some(advertised) => DeviceDescriptionDiff::new(advertised, &device_description),
+            None => DeviceDescriptionDiff {
+                device: true,
+                ..DeviceDescriptionDiff::default()
+            },
+        };
+        if diff.is_empty() {
+            return Ok(diff);
+        }
+
+        let changed: Vec<_> = properties
+            .iter()
+            .filter(|property_builder| diff.properties.changed.contains(&property_builder.name()))
+            .filter_map(|property_builder| {
+                device_handle
+                    .get_property(property_builder.name())
+                    .map(|property| (property_builder, property))
+            })
+            .collect();
+
+        let mut messages = Vec::new();
+        if diff.properties_only() {
+            for (property_builder, property) in &changed {
+                let property = property.lock().await;
+                messages.push(property_builder.update_message(property.property_handle())?);
+            }
+        } else {
+            messages.push(
+                DeviceAddedNotificationMessageData {
+                    plugin_id: self.plugin_id.clone(),
+                    adapter_id: self.adapter_id.clone(),
+                    device: device_description.clone(),
+                }
+                .into(),
+            );
+        }
+
+        for result in client::send_each(&self.client, &messages).await {
+            result?;
+        }
+
+        for (property_builder, property) in changed {
+            let mut property = property.lock().await;
+            property_builder.update_handle(property.property_handle_mut())?;
+        }
+        device_handle.description = description.connected(device_handle.description.connected);
+        device_handle.advertised = Some(device_description);
+        Ok(diff)
+    }
+
     /// Get a reference to all the [devices][crate::Device] which this adapter owns.
     pub fn devices(&self) -> &HashMap<String, Arc<Mutex<Box<dyn Device>>>> {
         &self.devices
     }
 
-    /// Get a [device][crate::Device] which this adapter owns by ID.
+    /// Get a [device][crate::Device] which this adapter owns by its raw or normalized ID.
     pub fn get_device(&self, id: impl Into<String>) -> Option<Arc<Mutex<Box<dyn Device>>>> {
-        self.devices.get(&id.into()).cloned()
+        self.resolve_id(&id.into())
+            .and_then(|id| self.devices.get(&id).cloned())
+    }
+
+    /// Run an asynchronous operation on every [device][crate::Device] which this adapter owns
+    /// and collect the results by device ID.
+    ///
+    /// The devices are snapshotted when this is called, so the returned future does not borrow
+    /// this adapter. Await it after releasing the lock of the [adapter][crate::Adapter] to let
+    /// other tasks access it meanwhile. The devices are locked one at a time in ID order, never
+    /// while holding the lock of another device.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use gateway_addon_rust::prelude::*;
+    /// # async fn example(adapter_handle: &AdapterHandle) {
+    /// let results = adapter_handle
+    ///     .for_each_device(|device| Box::pin(async move { device.device_handle().resync().await }))
+    ///     .await;
+    /// for (device_id, result) in results {
+    ///     if let Err(err) = result {
+    ///         log::warn!("Could not refresh device {}: {}", device_id, err);
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub fn for_each_device<F, R>(&self, mut f: F) -> impl Future<Output = Vec<(String, R)>>
+    where
+        F: for<'a> FnMut(&'a mut Box<dyn Device>) -> BoxFuture<'a, R>,
+    {
+        let mut devices: Vec<_> = self
+            .devices
+            .iter()
+            .map(|(id, device)| (id.clone(), device.clone()))
+            .collect();
+        devices.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        async move {
+            let mut results = Vec::with_capacity(devices.len());
+            for (id, device) in devices {
+                let mut device = device.lock().await;
+                results.push((id, f(&mut device).await));
+            }
+            results
+        }
+    }
+
+    /// Whether the gateway is currently pairing with this adapter.
+    pub fn pairing(&self) -> bool {
+        matches!(self.pairing_deadline, Some(deadline) if deadline > Instant::now())
+    }
+
+    /// When the current pairing of the gateway with this adapter times out.
+    pub fn pairing_deadline(&self) -> Option<Instant> {
+        self.pairing_deadline.filter(|_| self.pairing())
+    }
+
+    /// Set how to react to the gateway starting pairing while pairing is already active.
+    ///
+    /// Defaults to [DuplicatePairingBehavior::Restart].
+    pub fn set_duplicate_pairing_behavior(&mut self, behavior: DuplicatePairingBehavior) {
+        self.duplicate_pairing_behavior = behavior;
+    }
+
+    /// Record that the gateway started pairing.
+    ///
+    /// Returns whether [Adapter::on_start_pairing][crate::Adapter::on_start_pairing] should be called.
+    pub(crate) fn start_pairing(&mut self, timeout: Duration) -> bool {
+        let active = self.pairing();
+        self.pairing_deadline = Some(Instant::now() + timeout);
+        !active || self.duplicate_pairing_behavior == DuplicatePairingBehavior::Restart
+    }
+
+    /// Record that the gateway stopped pairing and abort all [discovery tasks][AdapterHandle::spawn_discovery].
+    pub(crate) fn cancel_pairing(&mut self) {
+        self.pairing_deadline = None;
+        for discovery_task in self.discovery_tasks.drain(..) {
+            discovery_task.abort();
+        }
+    }
+
+    /// Spawn a task which discovers devices, e.g. from [Adapter::on_start_pairing][crate::Adapter::on_start_pairing].
+    ///
+    /// The task is aborted when the gateway cancels pairing or unloads the plugin.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use gateway_addon_rust::prelude::*;
+    /// # fn example(adapter_handle: &mut AdapterHandle) {
+    /// adapter_handle.spawn_discovery(async {
+    ///     // Scan for devices and add them
+    /// });
+    /// # }
+    /// ```
+    pub fn spawn_discovery(&mut self, future: impl Future<Output = ()> + Send + 'static) {
+        self.discovery_tasks
+            .retain(|discovery_task| !discovery_task.is_finished());
+        self.discovery_tasks
+            .push(Arc::new(spawn(self.runtime.as_ref(), future)));
     }
 
     /// Unload this adapter.
@@ -116,18 +498,23 @@ impl AdapterHandle {
         }
         .into();
 
-        self.client.lock().await.send_message(&message).await
+        client::send_message(&self.client, &message).await
     }
 
-    /// Remove a [device][crate::Device] which this adapter owns by ID.
+    /// Remove a [device][crate::Device] which this adapter owns by its raw or normalized ID.
     pub async fn remove_device(
         &mut self,
         device_id: impl Into<String>,
     ) -> Result<(), WebthingsError> {
         let device_id = device_id.into();
-        if self.devices.remove(&device_id).is_none() {
-            return Err(WebthingsError::UnknownDevice(device_id.clone()));
+        let device_id = self.resolve_id(&device_id).unwrap_or(device_id);
+        self.raw_ids.retain(|_, id| *id != device_id);
+        match self.devices.remove(&device_id) {
+            Some(device) => device.lock().await.device_handle_mut().stop_polling(),
+            None => return Err(WebthingsError::UnknownDevice(device_id.clone())),
         }
+        self.mailboxes
+            .close(&mailbox_key(&self.adapter_id, &device_id));
 
         let message: Message = AdapterRemoveDeviceResponseMessageData {
             plugin_id: self.plugin_id.clone(),
@@ -136,7 +523,7 @@ impl AdapterHandle {
         }
         .into();
 
-        self.client.lock().await.send_message(&message).await
+        client::send_message(&self.client, &message).await
     }
 }
 
@@ -144,9 +531,16 @@ impl AdapterHandle {
 pub(crate) mod tests {
     use crate::{
         client::Client,
-        device::{tests::MockDevice, DeviceStructure},
-        AdapterHandle, Device,
+        device::{
+            tests::{BuiltMockDevice, MockAsyncDevice, MockDevice},
+            DeviceStructure,
+        },
+        error::WebthingsError,
+        property::tests::MockProperty,
+        Actions, AdapterHandle, Device, DeviceDescription, Events, Properties, PropertyDescription,
+        PropertyHandle,
     };
+    use as_any::Downcast;
     use rstest::{fixture, rstest};
     use std::sync::Arc;
     use tokio::sync::Mutex;
@@ -178,9 +572,33 @@ pub(crate) mod tests {
             .times(1)
             .returning(|_| Ok(()));
 
+        expect_initial_connected(adapter, device_id, true).await;
+
         adapter.add_device(device).await.unwrap()
     }
 
+    pub async fn expect_initial_connected(
+        adapter: &mut AdapterHandle,
+        device_id: &str,
+        connected: bool,
+    ) {
+        let device_id = device_id.to_owned();
+
+        adapter
+            .client
+            .lock()
+            .await
+            .expect_send_message()
+            .withf(move |msg| match msg {
+                Message::DeviceConnectedStateNotification(msg) => {
+                    msg.data.device_id == device_id && msg.data.connected == connected
+                }
+                _ => false,
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+    }
+
     const PLUGIN_ID: &str = "plugin_id";
     const ADAPTER_ID: &str = "adapter_id";
     const DEVICE_ID: &str = "device_id";
@@ -198,6 +616,191 @@ pub(crate) mod tests {
         assert!(adapter.get_device(DEVICE_ID).is_some())
     }
 
+    #[rstest]
+    #[case(true)]
+    #[case(false)]
+    #[tokio::test]
+    async fn test_add_device_connected(mut adapter: AdapterHandle, #[case] connected: bool) {
+        adapter
+            .client
+            .lock()
+            .await
+            .expect_send_message()
+            .withf(|msg| matches!(msg, Message::DeviceAddedNotification(_)))
+            .times(1)
+            .returning(|_| Ok(()));
+        expect_initial_connected(&mut adapter, DEVICE_ID, connected).await;
+
+        let mut device = MockDevice::new(DEVICE_ID.to_owned());
+        device.connected = connected;
+        let device = adapter.add_device(device).await.unwrap();
+
+        assert_eq!(device.lock().await.device_handle().connected(), connected);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_add_device_post_init(mut adapter: AdapterHandle) {
+        let device = add_mock_device(&mut adapter, DEVICE_ID).await;
+        let device = device.lock().await;
+        let device = device.downcast_ref::<BuiltMockDevice>().unwrap();
+        assert_eq!(device.post_init_device_valid, Some(true));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_add_device_async(mut adapter: AdapterHandle) {
+        adapter
+            .client
+            .lock()
+            .await
+            .expect_send_message()
+            .withf(move |msg| match msg {
+                Message::DeviceAddedNotification(msg) => {
+                    msg.data.device.id == DEVICE_ID
+                        && msg
+                            .data
+                            .device
+                            .properties
+                            .as_ref()
+                            .unwrap()
+                            .contains_key(MockDevice::PROPERTY_I32)
+                }
+                _ => false,
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+        expect_initial_connected(&mut adapter, DEVICE_ID, true).await;
+
+        let device = adapter
+            .add_device_async(MockAsyncDevice::new(DEVICE_ID.to_owned()))
+            .await
+            .unwrap();
+
+        assert!(adapter.get_device(DEVICE_ID).is_some());
+        assert!(device
+            .lock()
+            .await
+            .device_handle()
+            .get_property(MockDevice::PROPERTY_I32)
+            .is_some());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_add_device_invalid_id(mut adapter: AdapterHandle) {
+        adapter.client.lock().await.expect_send_message().times(0);
+
+        assert!(matches!(
+            adapter.add_device(MockDevice::new("".to_owned())).await,
+            Err(WebthingsError::InvalidId(_))
+        ));
+        assert!(adapter.devices().is_empty());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_add_device_send_failure(mut adapter: AdapterHandle) {
+        adapter
+            .client
+            .lock()
+            .await
+            .expect_send_message()
+            .times(1)
+            .returning(|_| Err(WebthingsError::Send(tungstenite::Error::ConnectionClosed)));
+
+        assert!(matches!(
+            adapter
+                .add_device(MockDevice::new(DEVICE_ID.to_owned()))
+                .await,
+            Err(WebthingsError::Send(_))
+        ));
+        assert!(adapter.get_device(DEVICE_ID).is_none());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_add_device_normalized_id(mut adapter: AdapterHandle) {
+        adapter.set_id_normalizer(Arc::new(|id| id.replace(':', "-")));
+
+        adapter
+            .client
+            .lock()
+            .await
+            .expect_send_message()
+            .withf(|msg| match msg {
+                Message::DeviceAddedNotification(msg) => msg.data.device.id == "aa-bb",
+                _ => false,
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+        expect_initial_connected(&mut adapter, "aa-bb", true).await;
+
+        let device = adapter
+            .add_device(MockDevice::new("aa:bb".to_owned()))
+            .await
+            .unwrap();
+
+        assert_eq!(device.lock().await.device_handle().device_id(), "aa-bb");
+        assert!(adapter.get_device("aa:bb").is_some());
+        assert!(adapter.get_device("aa-bb").is_some());
+
+        adapter
+            .client
+            .lock()
+            .await
+            .expect_send_message()
+            .times(1)
+            .returning(|_| Ok(()));
+
+        adapter.remove_device("aa:bb").await.unwrap();
+        assert!(adapter.get_device("aa:bb").is_none());
+        assert!(adapter.get_device("aa-bb").is_none());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_add_device_normalized_id_collision(mut adapter: AdapterHandle) {
+        adapter.set_id_normalizer(Arc::new(|id| id.replace(':', "-")));
+        add_mock_device(&mut adapter, "aa-bb").await;
+
+        assert!(matches!(
+            adapter.add_device(MockDevice::new("aa:bb".to_owned())).await,
+            Err(WebthingsError::IdCollision(raw_id, id)) if raw_id == "aa:bb" && id == "aa-bb"
+        ));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_for_each_device(mut adapter: AdapterHandle) {
+        add_mock_device(&mut adapter, "device_b").await;
+        add_mock_device(&mut adapter, "device_a").await;
+
+        let results = adapter
+            .for_each_device(|device| {
+                Box::pin(async move {
+                    let device_handle = device.device_handle_mut();
+                    device_handle.description = device_handle.description.clone().title("foo");
+                    device_handle.device_id().to_owned()
+                })
+            })
+            .await;
+
+        assert_eq!(
+            results,
+            vec![
+                ("device_a".to_owned(), "device_a".to_owned()),
+                ("device_b".to_owned(), "device_b".to_owned())
+            ]
+        );
+        for device in adapter.devices().values() {
+            assert_eq!(
+                device.lock().await.device_handle().description.title,
+                Some("foo".to_owned())
+            );
+        }
+    }
+
     #[rstest]
     #[tokio::test]
     async fn test_get_unknown_device(adapter: AdapterHandle) {
@@ -236,6 +839,226 @@ pub(crate) mod tests {
         assert!(adapter.remove_device(DEVICE_ID).await.is_err())
     }
 
+    /// A [MockDevice] with a changed structure, used to advertise it again.
+    struct ChangedMockDevice {
+        device: MockDevice,
+        title: Option<&'static str>,
+        property_title: Option<&'static str>,
+    }
+
+    impl ChangedMockDevice {
+        fn new(title: Option<&'static str>, property_title: Option<&'static str>) -> Self {
+            Self {
+                device: MockDevice::new(DEVICE_ID.to_owned()),
+                title,
+                property_title,
+            }
+        }
+    }
+
+    impl DeviceStructure for ChangedMockDevice {
+        fn id(&self) -> String {
+            self.device.id()
+        }
+
+        fn description(&self) -> DeviceDescription {
+            match self.title {
+                Some(title) => self.device.description().title(title),
+                None => self.device.description(),
+            }
+        }
+
+        fn properties(&self) -> Properties {
+            let mut properties = self.device.properties();
+            if let Some(title) = self.property_title {
+                properties.retain(|property| property.name() != MockDevice::PROPERTY_I32);
+                let mut property = MockProperty::<i32>::new(MockDevice::PROPERTY_I32.to_owned());
+                property.description = PropertyDescription::default().title(title);
+                properties.push(Box::new(property));
+            }
+            properties
+        }
+
+        fn actions(&self) -> Actions {
+            self.device.actions()
+        }
+
+        fn events(&self) -> Events {
+            self.device.events()
+        }
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_readvertise_device_unchanged(mut adapter: AdapterHandle) {
+        add_mock_device(&mut adapter, DEVICE_ID).await;
+        adapter.client.lock().await.checkpoint();
+        adapter.client.lock().await.expect_send_message().times(0);
+
+        let diff = adapter
+            .readvertise_device(&ChangedMockDevice::new(None, None))
+            .await
+            .unwrap();
+        assert!(diff.is_empty());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_readvertise_device_property(mut adapter: AdapterHandle) {
+        let device = add_mock_device(&mut adapter, DEVICE_ID).await;
+        adapter.client.lock().await.checkpoint();
+        adapter
+            .client
+            .lock()
+            .await
+            .expect_send_message()
+            .withf(|msg| match msg {
+                Message::DevicePropertyChangedNotification(msg) => {
+                    msg.data.device_id == DEVICE_ID
+                        && msg.data.property.name == Some(MockDevice::PROPERTY_I32.to_owned())
+                        && msg.data.property.title == Some("Changed".to_owned())
+                }
+                _ => false,
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let diff = adapter
+            .readvertise_device(&ChangedMockDevice::new(None, Some("Changed")))
+            .await
+            .unwrap();
+        assert!(diff.properties_only());
+        assert_eq!(diff.properties.changed, vec![MockDevice::PROPERTY_I32]);
+
+        let property = device
+            .lock()
+            .await
+            .device_handle()
+            .get_property(MockDevice::PROPERTY_I32)
+            .unwrap();
+        let property = property.lock().await;
+        let property_handle = property
+            .property_handle()
+            .downcast_ref::<PropertyHandle<i32>>()
+            .unwrap();
+        assert_eq!(
+            property_handle.description.title,
+            Some("Changed".to_owned())
+        );
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_readvertise_device_full(mut adapter: AdapterHandle) {
+        let device = add_mock_device(&mut adapter, DEVICE_ID).await;
+        adapter.client.lock().await.checkpoint();
+        adapter
+            .client
+            .lock()
+            .await
+            .expect_send_message()
+            .withf(|msg| match msg {
+                Message::DeviceAddedNotification(msg) => {
+                    msg.data.device.id == DEVICE_ID
+                        && msg.data.device.title == Some("Changed".to_owned())
+                }
+                _ => false,
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let diff = adapter
+            .readvertise_device(&ChangedMockDevice::new(Some("Changed"), None))
+            .await
+            .unwrap();
+        assert!(diff.device);
+        assert_eq!(
+            device.lock().await.device_handle().description.title,
+            Some("Changed".to_owned())
+        );
+    }
+
+    #[rstest]
+    #[case(None, Some("Changed"))]
+    #[case(Some("Changed"), None)]
+    #[tokio::test]
+    async fn test_readvertise_device_send_failure(
+        mut adapter: AdapterHandle,
+        #[case] device_title: Option<&'static str>,
+        #[case] property_title: Option<&'static str>,
+    ) {
+        let device = add_mock_device(&mut adapter, DEVICE_ID).await;
+        adapter.client.lock().await.checkpoint();
+        adapter
+            .client
+            .lock()
+            .await
+            .expect_send_message()
+            .times(1)
+            .returning(|_| Err(WebthingsError::Send(tungstenite::Error::ConnectionClosed)));
+
+        assert!(matches!(
+            adapter
+                .readvertise_device(&ChangedMockDevice::new(device_title, property_title))
+                .await,
+            Err(WebthingsError::Send(_))
+        ));
+        assert_eq!(titles(&device).await, (None, None));
+
+        adapter.client.lock().await.checkpoint();
+        adapter
+            .client
+            .lock()
+            .await
+            .expect_send_message()
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let diff = adapter
+            .readvertise_device(&ChangedMockDevice::new(device_title, property_title))
+            .await
+            .unwrap();
+        assert!(!diff.is_empty());
+        assert_eq!(
+            titles(&device).await,
+            (
+                device_title.map(str::to_owned),
+                property_title.map(str::to_owned)
+            )
+        );
+    }
+
+    async fn titles(device: &Arc<Mutex<Box<dyn Device>>>) -> (Option<String>, Option<String>) {
+        let device = device.lock().await;
+        let property = device
+            .device_handle()
+            .get_property(MockDevice::PROPERTY_I32)
+            .unwrap();
+        let property = property.lock().await;
+        let property_title = property
+            .property_handle()
+            .downcast_ref::<PropertyHandle<i32>>()
+            .unwrap()
+            .description
+            .title
+            .clone();
+        (
+            device.device_handle().description.title.clone(),
+            property_title,
+        )
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_readvertise_unknown_device(mut adapter: AdapterHandle) {
+        assert!(matches!(
+            adapter
+                .readvertise_device(&ChangedMockDevice::new(None, None))
+                .await,
+            Err(WebthingsError::UnknownDevice(_))
+        ));
+    }
+
     #[rstest]
     #[tokio::test]
     async fn test_unload(adapter: AdapterHandle) {
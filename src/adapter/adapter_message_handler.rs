@@ -5,6 +5,7 @@
  */
 
 use crate::{
+    error::{HandleError, WebthingsError},
     message_handler::{MessageHandler, MessageResult},
     Adapter,
 };
@@ -19,45 +20,68 @@ use webthings_gateway_ipc_types::{
 
 #[async_trait]
 impl MessageHandler for dyn Adapter {
-    async fn handle_message(&mut self, message: IPCMessage) -> Result<MessageResult, String> {
+    async fn handle_message(&mut self, message: IPCMessage) -> Result<MessageResult, HandleError> {
         match &message {
             IPCMessage::AdapterUnloadRequest(AdapterUnloadRequest { data, .. }) => {
-                log::info!("Received request to unload adapter '{}'", data.adapter_id);
+                log::info!(
+                    "{}Received request to unload adapter '{}'",
+                    self.adapter_handle().log_prefix,
+                    data.adapter_id
+                );
 
-                self.on_unload()
-                    .await
-                    .map_err(|err| format!("Could not unload adapter: {}", err))?;
+                self.on_unload().await.map_err(|err| {
+                    HandleError::Callback("Could not unload adapter".to_owned(), err)
+                })?;
 
-                self.adapter_handle()
-                    .unload()
-                    .await
-                    .map_err(|err| format!("Could not send unload response: {}", err))?;
+                self.adapter_handle().unload().await.map_err(|err| {
+                    HandleError::from_error("Could not send unload response", err)
+                })?;
             }
             IPCMessage::DeviceSavedNotification(DeviceSavedNotification { data, .. }) => {
+                if let Some(device) = self.adapter_handle().get_device(&data.device_id) {
+                    device.lock().await.device_handle_mut().record_saved();
+                }
                 self.on_device_saved(data.device_id.clone(), data.device.clone())
                     .await
-                    .map_err(|err| format!("Error during adapter.on_device_saved: {}", err))?;
+                    .map_err(|err| {
+                        HandleError::Callback(
+                            "Error during adapter.on_device_saved".to_owned(),
+                            err,
+                        )
+                    })?;
             }
             IPCMessage::AdapterStartPairingCommand(AdapterStartPairingCommand { data, .. }) => {
-                self.on_start_pairing(Duration::from_secs(data.timeout as u64))
-                    .await
-                    .map_err(|err| format!("Error during adapter.on_start_pairing: {}", err))?;
+                let timeout = Duration::from_secs(data.timeout as u64);
+                if self.adapter_handle_mut().start_pairing(timeout) {
+                    self.on_start_pairing(timeout).await.map_err(|err| {
+                        HandleError::Callback(
+                            "Error during adapter.on_start_pairing".to_owned(),
+                            err,
+                        )
+                    })?;
+                }
             }
             IPCMessage::AdapterCancelPairingCommand(_) => {
-                self.on_cancel_pairing()
-                    .await
-                    .map_err(|err| format!("Error during adapter.on_cancel_pairing: {}", err))?;
+                self.adapter_handle_mut().cancel_pairing();
+                self.on_cancel_pairing().await.map_err(|err| {
+                    HandleError::Callback("Error during adapter.on_cancel_pairing".to_owned(), err)
+                })?;
             }
             IPCMessage::AdapterRemoveDeviceRequest(AdapterRemoveDeviceRequest { data, .. }) => {
                 self.on_remove_device(data.device_id.clone())
                     .await
-                    .map_err(|err| format!("Could not execute remove device callback: {}", err))?;
+                    .map_err(|err| {
+                        HandleError::Callback(
+                            "Could not execute remove device callback".to_owned(),
+                            err,
+                        )
+                    })?;
 
                 self.adapter_handle_mut()
                     .remove_device(&data.device_id)
                     .await
                     .map_err(|err| {
-                        format!("Could not remove device from adapter handle: {}", err)
+                        HandleError::from_error("Could not remove device from adapter handle", err)
                     })?;
             }
             IPCMessage::DeviceSetPropertyCommand(DeviceSetPropertyCommand {
@@ -74,13 +98,15 @@ impl MessageHandler for dyn Adapter {
             }) => {
                 self.adapter_handle()
                     .get_device(device_id)
-                    .ok_or_else(|| format!("Unknown device: {}", device_id))?
+                    .ok_or_else(|| {
+                        HandleError::UnknownTarget(WebthingsError::UnknownDevice(device_id.clone()))
+                    })?
                     .lock()
                     .await
                     .handle_message(message)
                     .await?;
             }
-            msg => return Err(format!("Unexpected msg: {:?}", msg)),
+            msg => return Err(HandleError::UnexpectedMessage(format!("{:?}", msg))),
         }
 
         Ok(MessageResult::Continue)
@@ -89,18 +115,11 @@ impl MessageHandler for dyn Adapter {
 
 #[cfg(test)]
 mod tests {
-    use crate::{
-        adapter::tests::{add_mock_device, BuiltMockAdapter},
-        message_handler::MessageHandler,
-        plugin::tests::{add_mock_adapter, plugin},
-        Plugin,
-    };
-    use as_any::Downcast;
-    use rstest::rstest;
+    use crate::{adapter::DuplicatePairingBehavior, prelude::testing::*};
     use webthings_gateway_ipc_types::{
         AdapterCancelPairingCommandMessageData, AdapterRemoveDeviceRequestMessageData,
         AdapterStartPairingCommandMessageData, AdapterUnloadRequestMessageData,
-        DeviceSavedNotificationMessageData, DeviceWithoutId, Message,
+        DeviceSavedNotificationMessageData, DeviceWithoutId,
     };
 
     const PLUGIN_ID: &str = "plugin_id";
@@ -220,6 +239,59 @@ mod tests {
         plugin.handle_message(message).await.unwrap();
     }
 
+    #[rstest]
+    #[case(DuplicatePairingBehavior::Restart, 5000, false, 2)]
+    #[case(DuplicatePairingBehavior::Extend, 5000, false, 1)]
+    #[case(DuplicatePairingBehavior::Extend, 5000, true, 2)]
+    #[case(DuplicatePairingBehavior::Extend, 0, false, 2)]
+    #[tokio::test]
+    async fn test_request_adapter_start_pairing_twice(
+        mut plugin: Plugin,
+        #[case] behavior: DuplicatePairingBehavior,
+        #[case] timeout: i64,
+        #[case] cancel_in_between: bool,
+        #[case] expected_calls: usize,
+    ) {
+        let adapter = add_mock_adapter(&mut plugin, ADAPTER_ID).await;
+
+        let start_pairing = || -> Message {
+            AdapterStartPairingCommandMessageData {
+                plugin_id: PLUGIN_ID.to_owned(),
+                adapter_id: ADAPTER_ID.to_owned(),
+                timeout,
+            }
+            .into()
+        };
+
+        {
+            let mut adapter = adapter.lock().await;
+            adapter
+                .adapter_handle_mut()
+                .set_duplicate_pairing_behavior(behavior);
+            let adapter = adapter.downcast_mut::<BuiltMockAdapter>().unwrap();
+            adapter
+                .expect_on_start_pairing()
+                .times(expected_calls)
+                .returning(|_| Ok(()));
+            adapter.expect_on_cancel_pairing().returning(|| Ok(()));
+        }
+
+        plugin.handle_message(start_pairing()).await.unwrap();
+        assert_eq!(adapter.lock().await.adapter_handle().pairing(), timeout > 0);
+
+        if cancel_in_between {
+            let message: Message = AdapterCancelPairingCommandMessageData {
+                plugin_id: PLUGIN_ID.to_owned(),
+                adapter_id: ADAPTER_ID.to_owned(),
+            }
+            .into();
+            plugin.handle_message(message).await.unwrap();
+            assert!(!adapter.lock().await.adapter_handle().pairing());
+        }
+
+        plugin.handle_message(start_pairing()).await.unwrap();
+    }
+
     #[rstest]
     #[tokio::test]
     async fn test_request_adapter_cancel_pairing(mut plugin: Plugin) {
@@ -281,4 +353,47 @@ mod tests {
 
         plugin.handle_message(message).await.unwrap();
     }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_notification_device_saved_version(mut plugin: Plugin) {
+        let adapter = add_mock_adapter(&mut plugin, ADAPTER_ID).await;
+        let device = add_mock_device(adapter.lock().await.adapter_handle_mut(), DEVICE_ID).await;
+
+        {
+            let mut adapter = adapter.lock().await;
+            let adapter = adapter.downcast_mut::<BuiltMockAdapter>().unwrap();
+            adapter
+                .expect_on_device_saved()
+                .times(2)
+                .returning(|_, _| Ok(()));
+        }
+
+        for _ in 0..2 {
+            let message: Message = DeviceSavedNotificationMessageData {
+                plugin_id: PLUGIN_ID.to_owned(),
+                adapter_id: ADAPTER_ID.to_owned(),
+                device_id: DEVICE_ID.to_owned(),
+                device: DeviceWithoutId {
+                    at_context: None,
+                    at_type: None,
+                    actions: None,
+                    base_href: None,
+                    credentials_required: None,
+                    description: None,
+                    events: None,
+                    links: None,
+                    pin: None,
+                    properties: None,
+                    title: Some("My device".to_owned()),
+                },
+            }
+            .into();
+            plugin.handle_message(message).await.unwrap();
+        }
+
+        let device = device.lock().await;
+        assert_eq!(device.device_handle().saved_version(), 2);
+        assert!(device.device_handle().last_saved().is_some());
+    }
 }
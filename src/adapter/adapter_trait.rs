@@ -99,7 +99,7 @@ pub trait Adapter: BuiltAdapter + Send + Sync + AsAny + 'static {
 
     /// Called when the gateway stops pairing.
     ///
-    /// This happens when the add things view closes.
+    /// This happens when the add things view closes, or when the plugin is unloaded during pairing.
     async fn on_cancel_pairing(&mut self) -> Result<(), String> {
         Ok(())
     }
@@ -0,0 +1,117 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+
+use crate::api_handler::ApiResponse;
+use serde_json::json;
+use thiserror::Error;
+
+/// An error of an [API Handler][crate::api_handler::ApiHandler] request which is answered with the given status.
+///
+/// Plain string errors are converted into a `500` response with a `text/plain` body.
+///
+/// # Examples
+/// ```
+/// # use gateway_addon_rust::api_handler::{ApiHandlerError, ApiResponse};
+/// # use serde_json::json;
+/// fn find_device(id: &str) -> Result<ApiResponse, ApiHandlerError> {
+///     if id != "foo" {
+///         return Err(ApiHandlerError::not_found(json!({ "error": "unknown device" })));
+///     }
+///     // ...
+/// #   Err("".into())
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Error)]
+#[error("API request failed with status {status}: {content}")]
+pub struct ApiHandlerError {
+    pub status: i64,
+    pub content: serde_json::Value,
+    pub content_type: serde_json::Value,
+}
+
+impl ApiHandlerError {
+    /// Build a new [ApiHandlerError] with the given status and JSON content.
+    pub fn new(status: i64, content: serde_json::Value) -> Self {
+        Self {
+            status,
+            content,
+            content_type: json!("application/json"),
+        }
+    }
+
+    /// Build a new `400 Bad Request` [ApiHandlerError].
+    pub fn bad_request(content: serde_json::Value) -> Self {
+        Self::new(400, content)
+    }
+
+    /// Build a new `404 Not Found` [ApiHandlerError].
+    pub fn not_found(content: serde_json::Value) -> Self {
+        Self::new(404, content)
+    }
+
+    /// Build a new `500 Internal Server Error` [ApiHandlerError].
+    pub fn internal(content: serde_json::Value) -> Self {
+        Self::new(500, content)
+    }
+}
+
+impl From<String> for ApiHandlerError {
+    fn from(message: String) -> Self {
+        Self {
+            status: 500,
+            content: serde_json::Value::String(message),
+            content_type: json!("text/plain"),
+        }
+    }
+}
+
+impl From<&str> for ApiHandlerError {
+    fn from(message: &str) -> Self {
+        Self::from(message.to_owned())
+    }
+}
+
+impl From<ApiHandlerError> for ApiResponse {
+    fn from(err: ApiHandlerError) -> Self {
+        ApiResponse {
+            content: err.content,
+            content_type: err.content_type,
+            status: err.status,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api_handler::{ApiHandlerError, ApiResponse};
+    use serde_json::json;
+
+    #[test]
+    fn test_from_string() {
+        let response: ApiResponse = ApiHandlerError::from("foo").into();
+        assert_eq!(
+            response,
+            ApiResponse {
+                content: json!("foo"),
+                content_type: json!("text/plain"),
+                status: 500,
+            }
+        );
+    }
+
+    #[test]
+    fn test_not_found() {
+        let response: ApiResponse = ApiHandlerError::not_found(json!({"error": "foo"})).into();
+        assert_eq!(
+            response,
+            ApiResponse {
+                content: json!({"error": "foo"}),
+                content_type: json!("application/json"),
+                status: 404,
+            }
+        );
+    }
+}
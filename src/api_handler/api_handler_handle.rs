@@ -4,7 +4,11 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
  */
 
-use crate::{client::Client, error::WebthingsError};
+use crate::{
+    client::{self, Client},
+    error::WebthingsError,
+    plugin::default_log_prefix,
+};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use webthings_gateway_ipc_types::ApiHandlerUnloadResponseMessageData;
@@ -12,26 +16,45 @@ use webthings_gateway_ipc_types::ApiHandlerUnloadResponseMessageData;
 /// A struct which represents an instance of a WebthingsIO API Handler.
 ///
 /// Use it to notify the gateway.
+///
+/// Prefer the id getters over the public id fields, which are going to become private.
 #[derive(Clone)]
 pub struct ApiHandlerHandle {
     pub(crate) client: Arc<Mutex<Client>>,
     pub plugin_id: String,
+    pub(crate) package_name: String,
+    pub(crate) log_prefix: String,
 }
 
 impl ApiHandlerHandle {
     pub(crate) fn new(client: Arc<Mutex<Client>>, plugin_id: String) -> Self {
-        Self { client, plugin_id }
+        Self {
+            client,
+            package_name: plugin_id.clone(),
+            log_prefix: default_log_prefix(&plugin_id),
+            plugin_id,
+        }
+    }
+
+    /// ID of the plugin this API handler belongs to.
+    pub fn plugin_id(&self) -> &str {
+        &self.plugin_id
+    }
+
+    /// Name of the package of the plugin, see [Plugin::package_name][crate::Plugin::package_name].
+    pub fn package_name(&self) -> &str {
+        &self.package_name
     }
 
     /// Unload this API Handler.
     pub async fn unload(&self) -> Result<(), WebthingsError> {
         let message = ApiHandlerUnloadResponseMessageData {
             plugin_id: self.plugin_id.clone(),
-            package_name: self.plugin_id.clone(),
+            package_name: self.package_name.clone(),
         }
         .into();
 
-        self.client.lock().await.send_message(&message).await
+        client::send_message(&self.client, &message).await
     }
 }
 
@@ -2,7 +2,7 @@
 ///
 /// # Examples
 /// ```
-/// # use gateway_addon_rust::{prelude::*, api_handler::{api_handler, BuiltApiHandler, ApiHandler, ApiHandlerBuilder, ApiHandlerHandle, ApiRequest, ApiResponse}};
+/// # use gateway_addon_rust::{prelude::*, api_handler::{api_handler, BuiltApiHandler, ApiHandler, ApiHandlerBuilder, ApiHandlerError, ApiHandlerHandle, ApiRequest, ApiResponse}};
 /// # use async_trait::async_trait;
 /// #[api_handler]
 /// struct ExampleApiHandler {
@@ -12,14 +12,14 @@
 /// #[async_trait]
 /// impl ApiHandler for BuiltExampleApiHandler {
 ///     // ...
-///     # async fn handle_request(&mut self, _: ApiRequest) -> Result<ApiResponse, String> {
-///     #   Err("".to_owned())
+///     # async fn handle_request(&mut self, _: ApiRequest) -> Result<ApiResponse, ApiHandlerError> {
+///     #   Err("".into())
 ///     # }
 /// }
 /// ```
 /// will expand to
 /// ```
-/// # use gateway_addon_rust::{prelude::*, api_handler::{BuiltApiHandler, ApiHandlerBuilder, ApiHandler, ApiHandlerHandle, ApiRequest, ApiResponse}};
+/// # use gateway_addon_rust::{prelude::*, api_handler::{BuiltApiHandler, ApiHandlerBuilder, ApiHandler, ApiHandlerError, ApiHandlerHandle, ApiRequest, ApiResponse}};
 /// # use std::ops::{Deref, DerefMut};
 /// # use async_trait::async_trait;
 /// struct ExampleApiHandler {
@@ -66,8 +66,8 @@
 /// #[async_trait]
 /// impl ApiHandler for BuiltExampleApiHandler {
 ///     // ...
-///     # async fn handle_request(&mut self, _: ApiRequest) -> Result<ApiResponse, String> {
-///     #   Err("".to_owned())
+///     # async fn handle_request(&mut self, _: ApiRequest) -> Result<ApiResponse, ApiHandlerError> {
+///     #   Err("".into())
 ///     # }
 /// }
 /// ```
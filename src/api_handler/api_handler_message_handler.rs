@@ -6,58 +6,57 @@
 
 use crate::{
     api_handler::{ApiHandler, ApiResponse},
+    client,
+    error::HandleError,
     message_handler::{MessageHandler, MessageResult},
 };
 use async_trait::async_trait;
-use serde_json::json;
 use webthings_gateway_ipc_types::{
     ApiHandlerApiRequest, ApiHandlerApiResponseMessageData, Message as IPCMessage,
 };
 
 #[async_trait]
 impl MessageHandler for dyn ApiHandler {
-    async fn handle_message(&mut self, message: IPCMessage) -> Result<MessageResult, String> {
+    async fn handle_message(&mut self, message: IPCMessage) -> Result<MessageResult, HandleError> {
         match message {
             IPCMessage::ApiHandlerUnloadRequest(_) => {
-                log::info!("Received request to unload api handler");
-
-                self.on_unload()
-                    .await
-                    .map_err(|err| format!("Could not unload api handler: {}", err))?;
-
-                self.api_handler_handle()
-                    .unload()
-                    .await
-                    .map_err(|err| format!("Could not send unload response: {}", err))?;
+                log::info!(
+                    "{}Received request to unload api handler",
+                    self.api_handler_handle().log_prefix
+                );
+
+                self.on_unload().await.map_err(|err| {
+                    HandleError::Callback("Could not unload api handler".to_owned(), err)
+                })?;
+
+                self.api_handler_handle().unload().await.map_err(|err| {
+                    HandleError::from_error("Could not send unload response", err)
+                })?;
             }
             IPCMessage::ApiHandlerApiRequest(ApiHandlerApiRequest { data, .. }) => {
                 let result = self.handle_request(data.request).await;
 
-                let response = result.clone().unwrap_or_else(|err| ApiResponse {
-                    content: serde_json::Value::String(err),
-                    content_type: json!("text/plain"),
-                    status: 500,
-                });
+                let response = result.clone().unwrap_or_else(ApiResponse::from);
                 let message = ApiHandlerApiResponseMessageData {
                     message_id: data.message_id,
-                    package_name: data.plugin_id.clone(),
+                    package_name: self.api_handler_handle().package_name.clone(),
                     plugin_id: data.plugin_id.clone(),
                     response,
                 }
                 .into();
 
-                self.api_handler_handle()
-                    .client
-                    .lock()
-                    .await
-                    .send_message(&message)
+                client::send_message(&self.api_handler_handle().client, &message)
                     .await
-                    .map_err(|err| format!("{:?}", err))?;
-
-                result
-                    .map_err(|err| format!("Error during api_handler.handle_request: {}", err))?;
+                    .map_err(|err| HandleError::from_error("Could not send api response", err))?;
+
+                result.map_err(|err| {
+                    HandleError::Callback(
+                        "Error during api_handler.handle_request".to_owned(),
+                        err.to_string(),
+                    )
+                })?;
             }
-            msg => return Err(format!("Unexpected msg: {:?}", msg)),
+            msg => return Err(HandleError::UnexpectedMessage(format!("{:?}", msg))),
         }
         Ok(MessageResult::Continue)
     }
@@ -66,7 +65,9 @@ impl MessageHandler for dyn ApiHandler {
 #[cfg(test)]
 mod tests {
     use crate::{
-        api_handler::{api_handler_trait::tests::BuiltMockApiHandler, ApiRequest, ApiResponse},
+        api_handler::{
+            api_handler_trait::tests::BuiltMockApiHandler, ApiHandlerError, ApiRequest, ApiResponse,
+        },
         message_handler::MessageHandler,
         plugin::tests::{plugin, set_mock_api_handler},
         Plugin,
@@ -160,7 +161,10 @@ mod tests {
             .expect_send_message()
             .withf(move |msg| match msg {
                 Message::ApiHandlerApiResponse(msg) => {
-                    msg.data.plugin_id == PLUGIN_ID && msg.data.response == expected_response
+                    msg.data.plugin_id == PLUGIN_ID
+                        && msg.data.package_name == PLUGIN_ID
+                        && msg.data.message_id == message_id
+                        && msg.data.response == expected_response
                 }
                 _ => false,
             })
@@ -169,4 +173,58 @@ mod tests {
 
         plugin.handle_message(message).await.unwrap();
     }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_request_api_handler_handle_request_error(mut plugin: Plugin) {
+        set_mock_api_handler(&mut plugin).await;
+
+        let message: Message = ApiHandlerApiRequestMessageData {
+            plugin_id: PLUGIN_ID.to_owned(),
+            package_name: PLUGIN_ID.to_owned(),
+            message_id: 42,
+            request: ApiRequest {
+                body: BTreeMap::new(),
+                method: "GET".to_owned(),
+                path: "/unknown".to_string(),
+                query: BTreeMap::new(),
+            },
+        }
+        .into();
+
+        plugin
+            .api_handler
+            .lock()
+            .await
+            .downcast_mut::<BuiltMockApiHandler>()
+            .unwrap()
+            .expect_handle_request()
+            .times(1)
+            .returning(|_| {
+                Err(ApiHandlerError::not_found(
+                    json!({"error": "unknown route"}),
+                ))
+            });
+
+        plugin
+            .client
+            .lock()
+            .await
+            .expect_send_message()
+            .withf(move |msg| match msg {
+                Message::ApiHandlerApiResponse(msg) => {
+                    msg.data.response
+                        == ApiResponse {
+                            content: json!({"error": "unknown route"}),
+                            content_type: json!("application/json"),
+                            status: 404,
+                        }
+                }
+                _ => false,
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+
+        assert!(plugin.handle_message(message).await.is_err());
+    }
 }
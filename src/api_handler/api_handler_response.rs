@@ -0,0 +1,126 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+
+use crate::api_handler::{ApiHandlerError, ApiRequest, ApiResponse};
+use serde_json::json;
+
+/// Helpers to build an [ApiResponse].
+///
+/// The IPC `message_id` and `package_name` of a request are copied into its response by the
+/// [API handler][crate::api_handler::ApiHandler] message handling, so a reply only needs
+/// a status and content.
+///
+/// # Examples
+/// ```
+/// # use gateway_addon_rust::api_handler::{ApiHandlerError, ApiRequest, ApiResponse, ApiResponseExt};
+/// # use serde_json::json;
+/// fn handle_request(request: ApiRequest) -> Result<ApiResponse, ApiHandlerError> {
+///     Ok(ApiResponse::reply_to(&request)?.content(json!({ "foo": 42 })))
+/// }
+/// ```
+pub trait ApiResponseExt: Sized {
+    /// Build an empty `200` response with a JSON content type to the given request.
+    ///
+    /// Fails with a `400` [ApiHandlerError] if the request lacks a `method` or `path`,
+    /// which the gateway always sends.
+    fn reply_to(request: &ApiRequest) -> Result<Self, ApiHandlerError>;
+
+    /// Set the status.
+    #[must_use]
+    fn status(self, status: i64) -> Self;
+
+    /// Set the content.
+    #[must_use]
+    fn content(self, content: serde_json::Value) -> Self;
+
+    /// Set the content type.
+    #[must_use]
+    fn content_type(self, content_type: impl Into<String>) -> Self;
+}
+
+impl ApiResponseExt for ApiResponse {
+    fn reply_to(request: &ApiRequest) -> Result<Self, ApiHandlerError> {
+        if request.method.is_empty() {
+            return Err(ApiHandlerError::bad_request(json!({
+                "error": "Cannot reply to a request without method"
+            })));
+        }
+        if request.path.is_empty() {
+            return Err(ApiHandlerError::bad_request(json!({
+                "error": "Cannot reply to a request without path"
+            })));
+        }
+        Ok(ApiResponse {
+            content: serde_json::Value::Null,
+            content_type: json!("application/json"),
+            status: 200,
+        })
+    }
+
+    fn status(mut self, status: i64) -> Self {
+        self.status = status;
+        self
+    }
+
+    fn content(mut self, content: serde_json::Value) -> Self {
+        self.content = content;
+        self
+    }
+
+    fn content_type(mut self, content_type: impl Into<String>) -> Self {
+        self.content_type = json!(content_type.into());
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api_handler::{ApiRequest, ApiResponse, ApiResponseExt};
+    use serde_json::json;
+    use std::collections::BTreeMap;
+
+    fn request(method: &str, path: &str) -> ApiRequest {
+        ApiRequest {
+            body: BTreeMap::new(),
+            method: method.to_owned(),
+            path: path.to_owned(),
+            query: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_reply_to() {
+        let response = ApiResponse::reply_to(&request("GET", "/foo"))
+            .unwrap()
+            .status(201)
+            .content(json!("bar"))
+            .content_type("text/plain");
+        assert_eq!(
+            response,
+            ApiResponse {
+                content: json!("bar"),
+                content_type: json!("text/plain"),
+                status: 201,
+            }
+        );
+    }
+
+    #[test]
+    fn test_reply_to_defaults() {
+        let response = ApiResponse::reply_to(&request("GET", "/foo")).unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.content_type, json!("application/json"));
+    }
+
+    #[test]
+    fn test_reply_to_incomplete_request() {
+        let err = ApiResponse::reply_to(&request("", "/foo")).unwrap_err();
+        assert_eq!(err.status, 400);
+        assert!(err.content["error"].as_str().unwrap().contains("method"));
+        let err = ApiResponse::reply_to(&request("GET", "")).unwrap_err();
+        assert!(err.content["error"].as_str().unwrap().contains("path"));
+    }
+}
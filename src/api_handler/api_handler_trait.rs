@@ -4,7 +4,7 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
  */
 
-use crate::api_handler::{ApiHandlerHandle, ApiRequest, ApiResponse};
+use crate::api_handler::{ApiHandlerError, ApiHandlerHandle, ApiRequest, ApiResponse};
 use as_any::{AsAny, Downcast};
 use async_trait::async_trait;
 
@@ -16,7 +16,8 @@ use async_trait::async_trait;
 /// ```no_run
 /// # use gateway_addon_rust::{
 /// #     prelude::*, plugin::connect,
-/// #     api_handler::{api_handler, ApiHandler, ApiRequest, ApiResponse}, error::WebthingsError
+/// #     api_handler::{api_handler, ApiHandler, ApiHandlerError, ApiRequest, ApiResponse},
+/// #     error::WebthingsError
 /// # };
 /// # use async_trait::async_trait;
 /// # use serde_json::json;
@@ -27,14 +28,14 @@ use async_trait::async_trait;
 ///
 /// #[async_trait]
 /// impl ApiHandler for BuiltExampleApiHandler {
-///     async fn handle_request(&mut self, request: ApiRequest) -> Result<ApiResponse, String> {
+///     async fn handle_request(&mut self, request: ApiRequest) -> Result<ApiResponse, ApiHandlerError> {
 ///         match request.path.as_ref() {
 ///             "/example-route" => Ok(ApiResponse {
 ///                 content: serde_json::to_value(self.foo).unwrap(),
 ///                 content_type: json!("text/plain"),
 ///                 status: 200,
 ///             }),
-///             _ => Err("unknown route".to_owned()),
+///             _ => Err(ApiHandlerError::not_found(json!({ "error": "unknown route" }))),
 ///         }
 ///     }
 /// }
@@ -63,7 +64,10 @@ pub trait ApiHandler: BuiltApiHandler + Send + Sync + AsAny + 'static {
     }
 
     /// Called when a route at `/extensions/<plugin-id>/api/` was requested.
-    async fn handle_request(&mut self, request: ApiRequest) -> Result<ApiResponse, String>;
+    ///
+    /// An [error][ApiHandlerError] is answered with its status and content.
+    async fn handle_request(&mut self, request: ApiRequest)
+        -> Result<ApiResponse, ApiHandlerError>;
 }
 
 impl Downcast for dyn ApiHandler {}
@@ -103,7 +107,7 @@ pub trait BuiltApiHandler {
 ///
 /// # Examples
 /// ```
-/// # use gateway_addon_rust::{prelude::*, api_handler::{BuiltApiHandler, ApiHandlerBuilder, ApiHandler, ApiHandlerHandle, ApiRequest, ApiResponse}};
+/// # use gateway_addon_rust::{prelude::*, api_handler::{BuiltApiHandler, ApiHandlerBuilder, ApiHandler, ApiHandlerError, ApiHandlerHandle, ApiRequest, ApiResponse}};
 /// # use async_trait::async_trait;
 /// struct ExampleApiHandler {
 ///     foo: i32,
@@ -127,8 +131,8 @@ pub trait BuiltApiHandler {
 /// #[async_trait]
 /// impl ApiHandler for BuiltExampleApiHandler {
 ///     // ...
-///     # async fn handle_request(&mut self, _: ApiRequest) -> Result<ApiResponse, String> {
-///     #   Err("".to_owned())
+///     # async fn handle_request(&mut self, _: ApiRequest) -> Result<ApiResponse, ApiHandlerError> {
+///     #   Err("".into())
 ///     # }
 /// }
 ///
@@ -174,15 +178,19 @@ impl BuiltApiHandler for BuiltNoopApiHandler {
 
 #[async_trait]
 impl ApiHandler for BuiltNoopApiHandler {
-    async fn handle_request(&mut self, _request: ApiRequest) -> Result<ApiResponse, String> {
-        Err("No Api Handler registered".to_owned())
+    async fn handle_request(
+        &mut self,
+        _request: ApiRequest,
+    ) -> Result<ApiResponse, ApiHandlerError> {
+        Err("No Api Handler registered".into())
     }
 }
 
 #[cfg(test)]
 pub(crate) mod tests {
     use crate::api_handler::{
-        ApiHandler, ApiHandlerBuilder, ApiHandlerHandle, ApiRequest, ApiResponse, BuiltApiHandler,
+        ApiHandler, ApiHandlerBuilder, ApiHandlerError, ApiHandlerHandle, ApiRequest, ApiResponse,
+        BuiltApiHandler,
     };
     use async_trait::async_trait;
     use mockall::mock;
@@ -190,7 +198,7 @@ pub(crate) mod tests {
     mock! {
         pub ApiHandler{
             pub async fn on_unload(&mut self) -> Result<(), String>;
-            pub async fn handle_request(&mut self, request: ApiRequest) -> Result<ApiResponse, String>;
+            pub async fn handle_request(&mut self, request: ApiRequest) -> Result<ApiResponse, ApiHandlerError>;
         }
     }
 
@@ -238,7 +246,10 @@ pub(crate) mod tests {
             self.data.on_unload().await
         }
 
-        async fn handle_request(&mut self, request: ApiRequest) -> Result<ApiResponse, String> {
+        async fn handle_request(
+            &mut self,
+            request: ApiRequest,
+        ) -> Result<ApiResponse, ApiHandlerError> {
             self.data.handle_request(request).await
         }
     }
@@ -0,0 +1,91 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+
+//! Binary data for properties and events.
+
+use crate::{event::SimpleData, property::SimpleValue, type_::Type};
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+/// A byte buffer which serializes to a base64 encoded string.
+///
+/// Use this instead of `Vec<u8>`, which serializes to a JSON array of numbers.
+///
+/// # Examples
+/// ```
+/// # use gateway_addon_rust::{bytes::Bytes, property::Value};
+/// # use serde_json::json;
+/// let value = Value::serialize(Bytes::from(vec![1, 2, 3])).unwrap();
+/// assert_eq!(value, Some(json!("AQID")));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Bytes(pub Vec<u8>);
+
+impl From<Vec<u8>> for Bytes {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self(bytes)
+    }
+}
+
+impl From<Bytes> for Vec<u8> {
+    fn from(bytes: Bytes) -> Self {
+        bytes.0
+    }
+}
+
+impl Serialize for Bytes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&base64::encode(&self.0))
+    }
+}
+
+impl<'de> Deserialize<'de> for Bytes {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let encoded = <String as Deserialize>::deserialize(deserializer)?;
+        base64::decode(encoded).map(Self).map_err(D::Error::custom)
+    }
+}
+
+impl SimpleValue for Bytes {
+    fn type_() -> Type {
+        Type::String
+    }
+}
+
+impl SimpleData for Bytes {
+    fn type_() -> Option<Type> {
+        Some(Type::String)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{bytes::Bytes, event::Data, property::Value};
+    use rstest::rstest;
+    use serde_json::json;
+
+    #[rstest]
+    #[case(vec![], "")]
+    #[case(vec![1, 2, 3], "AQID")]
+    #[case(vec![0xff; 4], "/////w==")]
+    fn test_roundtrip(#[case] bytes: Vec<u8>, #[case] encoded: &str) {
+        let value = Value::serialize(Bytes(bytes.clone())).unwrap();
+        assert_eq!(value, Some(json!(encoded)));
+        assert_eq!(<Bytes as Value>::deserialize(value).unwrap(), Bytes(bytes));
+    }
+
+    #[test]
+    fn test_data() {
+        assert_eq!(
+            Data::serialize(Bytes(vec![1, 2, 3])).unwrap(),
+            Some(json!("AQID"))
+        );
+    }
+
+    #[test]
+    fn test_invalid_base64() {
+        assert!(<Bytes as Value>::deserialize(Some(json!("not base64!"))).is_err());
+    }
+}
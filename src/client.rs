@@ -4,10 +4,22 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
  */
 
-use crate::error::WebthingsError;
-use futures::{prelude::*, stream::SplitSink};
+use crate::{error::WebthingsError, plugin::HealthTracker, runtime::spawn};
+use futures::{future::BoxFuture, prelude::*, stream::SplitSink};
 use mockall_double::double;
-use tokio::net::TcpStream;
+use std::{
+    collections::VecDeque,
+    io::ErrorKind,
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::Arc,
+    time::Duration,
+};
+use tokio::{
+    net::TcpStream,
+    runtime::Handle,
+    sync::{oneshot, Mutex, Notify},
+    time::sleep,
+};
 use tokio_tungstenite::{tungstenite::protocol::Message, MaybeTlsStream, WebSocketStream};
 use webthings_gateway_ipc_types::Message as IPCMessage;
 
@@ -15,33 +27,1138 @@ use webthings_gateway_ipc_types::Message as IPCMessage;
 mockall::mock! {
     pub WebsocketClient {
         pub async fn send_message(&mut self, msg: &IPCMessage) -> Result<(), WebthingsError>;
+        pub async fn send_message_no_flush(&mut self, msg: &IPCMessage) -> Result<(), WebthingsError>;
+        pub fn try_send_message(&mut self, msg: &IPCMessage) -> Result<(), WebthingsError>;
+        pub async fn flush(&mut self) -> Result<(), WebthingsError>;
+        pub async fn close(&mut self) -> Result<(), WebthingsError>;
+        pub fn set_log_prefix(&mut self, log_prefix: String);
+        pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy);
+        pub fn set_queue_config(&mut self, queue_config: QueueConfig);
+        pub fn set_message_tap(&mut self, message_tap: Option<MessageTap>);
     }
 }
 
+/// The mock only records whole sends, so enqueueing sends right away and returns the result.
+#[cfg(test)]
+impl MockWebsocketClient {
+    pub async fn enqueue_message(
+        &mut self,
+        msg: &IPCMessage,
+    ) -> Result<WriteCompletion, WebthingsError> {
+        let result = self.send_message(msg).await;
+        Ok(Box::pin(future::ready(result)))
+    }
+
+    pub async fn enqueue_message_no_flush(
+        &mut self,
+        msg: &IPCMessage,
+    ) -> Result<WriteCompletion, WebthingsError> {
+        let result = self.send_message_no_flush(msg).await;
+        Ok(Box::pin(future::ready(result)))
+    }
+
+    pub async fn enqueue_flush(&mut self) -> Result<WriteCompletion, WebthingsError> {
+        let result = self.flush().await;
+        Ok(Box::pin(future::ready(result)))
+    }
+}
+
+/// A callback which gets invoked with every [message][IPCMessage] exchanged with the gateway.
+///
+/// See [Plugin::set_message_tap][crate::Plugin::set_message_tap].
+pub type MessageTap = Arc<dyn Fn(&IPCMessage) + Send + Sync>;
+
+/// Invoke the given [tap][MessageTap], if any, without letting a panic escape.
+pub(crate) fn tap_message(message_tap: &Option<MessageTap>, log_prefix: &str, msg: &IPCMessage) {
+    if let Some(message_tap) = message_tap {
+        if catch_unwind(AssertUnwindSafe(|| message_tap(msg))).is_err() {
+            log::error!("{}Message tap panicked on message {:?}", log_prefix, msg);
+        }
+    }
+}
+
+/// A policy for retrying messages which could not be sent due to a transient error.
+///
+/// After the `n`th failed attempt, sending is retried after `backoff * 2^(n - 1)`.
+///
+/// # Examples
+/// ```
+/// # use gateway_addon_rust::client::RetryPolicy;
+/// # use std::time::Duration;
+/// # let _ =
+/// RetryPolicy {
+///     retries: 3,
+///     backoff: Duration::from_millis(50),
+/// }
+/// # ;
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    /// Maximum number of retries per message.
+    pub retries: u32,
+    /// Delay before the first retry.
+    pub backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            retries: 0,
+            backoff: Duration::from_millis(100),
+        }
+    }
+}
+
+impl RetryPolicy {
+    fn delay(&self, attempt: u32) -> Duration {
+        self.backoff * 2u32.saturating_pow(attempt.saturating_sub(1))
+    }
+}
+
+/// What to do with a message which does not fit into the full outgoing queue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Wait until there is space in the queue.
+    ///
+    /// [try_send_message][WebsocketClient::try_send_message] fails with [WouldBlock][WebthingsError::WouldBlock] instead.
+    Block,
+    /// Drop the oldest queued message of the same [kind][MessageKind] to make space.
+    ///
+    /// The sender of the dropped message receives [MessageDropped][WebthingsError::MessageDropped].
+    /// Waits like [Block][OverflowPolicy::Block] if no message of the same kind is queued.
+    DropOldest,
+    /// Fail with [QueueFull][WebthingsError::QueueFull].
+    Error,
+}
+
+/// Kinds of outgoing messages which can have different [overflow policies][OverflowPolicy].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageKind {
+    /// Notifications about changed property values.
+    Property,
+    /// Notifications about raised events.
+    Event,
+    /// Replies to requests of the gateway, e.g. action responses.
+    Response,
+    /// All other messages.
+    Other,
+}
+
+impl MessageKind {
+    fn of(msg: &IPCMessage) -> Self {
+        match msg {
+            IPCMessage::DevicePropertyChangedNotification(_) => Self::Property,
+            IPCMessage::DeviceEventNotification(_) => Self::Event,
+            msg if !is_resendable(msg) => Self::Response,
+            _ => Self::Other,
+        }
+    }
+}
+
+/// Capacity of the outgoing queue and what to do when it is full.
+///
+/// Flush and close requests do not count towards the capacity and are never dropped.
+///
+/// # Examples
+/// ```
+/// # use gateway_addon_rust::client::{OverflowPolicy, QueueConfig};
+/// # let _ =
+/// QueueConfig {
+///     capacity: Some(256),
+///     property: OverflowPolicy::DropOldest,
+///     ..QueueConfig::default()
+/// }
+/// # ;
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueueConfig {
+    /// Maximum number of queued messages, `None` for no limit.
+    pub capacity: Option<usize>,
+    /// Policy for [property notifications][MessageKind::Property].
+    ///
+    /// Note that dropping the oldest notification may drop the value of another property.
+    pub property: OverflowPolicy,
+    /// Policy for [event notifications][MessageKind::Event].
+    pub event: OverflowPolicy,
+    /// Policy for [responses][MessageKind::Response].
+    pub response: OverflowPolicy,
+    /// Policy for [other messages][MessageKind::Other].
+    pub other: OverflowPolicy,
+}
+
+impl Default for QueueConfig {
+    fn default() -> Self {
+        Self {
+            capacity: None,
+            property: OverflowPolicy::Block,
+            event: OverflowPolicy::Block,
+            response: OverflowPolicy::Block,
+            other: OverflowPolicy::Block,
+        }
+    }
+}
+
+impl QueueConfig {
+    fn policy(&self, kind: MessageKind) -> OverflowPolicy {
+        match kind {
+            MessageKind::Property => self.property,
+            MessageKind::Event => self.event,
+            MessageKind::Response => self.response,
+            MessageKind::Other => self.other,
+        }
+    }
+}
+
+type WebsocketSink = SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, Message>;
+
+type Completion = oneshot::Sender<Result<(), WebthingsError>>;
+
+/// A future which resolves to the result of an enqueued write, see [WebsocketClient::enqueue_message].
+pub type WriteCompletion = BoxFuture<'static, Result<(), WebthingsError>>;
+
+/// A request to the sender task, which owns the websocket sink.
+enum Outgoing {
+    Write {
+        msg: String,
+        kind: MessageKind,
+        flush: bool,
+        retry_policy: RetryPolicy,
+        log_prefix: String,
+        done: Done,
+    },
+    Flush(Completion),
+    Close(Completion),
+}
+
+/// How the sender task reports the result of a write.
+enum Done {
+    /// Send the result back to the waiting caller.
+    Reply(Completion),
+    /// Nobody waits for the result, log errors and record them in the health tracker.
+    Detached {
+        health: Option<Arc<HealthTracker>>,
+        bytes: usize,
+    },
+}
+
+impl Done {
+    fn finish(self, log_prefix: &str, result: Result<(), WebthingsError>) {
+        match self {
+            Done::Reply(done) => {
+                let _ = done.send(result);
+            }
+            Done::Detached { health, bytes } => {
+                if let Err(err) = &result {
+                    log::error!("{}Could not send queued message: {}", log_prefix, err);
+                }
+                if let Some(health) = health {
+                    match &result {
+                        Ok(()) => {
+                            health.record_message();
+                            health.record_sent(bytes);
+                        }
+                        Err(err) => health.record_error(err),
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Result of trying to enqueue a message.
+enum Push {
+    Queued,
+    /// The queue is full and the message has to wait for space.
+    Full(Outgoing),
+}
+
+#[derive(Default)]
+struct QueueState {
+    items: VecDeque<Outgoing>,
+    writes: usize,
+    config: QueueConfig,
+    closed: bool,
+}
+
+/// The queue between the client and the sender task.
+///
+/// Only writes count towards the [capacity][QueueConfig::capacity].
+#[derive(Default)]
+struct OutgoingQueue {
+    state: std::sync::Mutex<QueueState>,
+    readable: Notify,
+    writable: Notify,
+}
+
+impl OutgoingQueue {
+    fn set_config(&self, config: QueueConfig) {
+        self.state.lock().unwrap().config = config;
+        self.writable.notify_waiters();
+    }
+
+    fn try_push(&self, outgoing: Outgoing) -> Result<Push, WebthingsError> {
+        let mut state = self.state.lock().unwrap();
+        if state.closed {
+            return Err(WebthingsError::Send(tungstenite::Error::AlreadyClosed));
+        }
+        let kind = match &outgoing {
+            Outgoing::Write { kind, .. } => *kind,
+            _ => {
+                state.items.push_back(outgoing);
+                self.readable.notify_one();
+                return Ok(Push::Queued);
+            }
+        };
+        let full = match state.config.capacity {
+            Some(capacity) => state.writes >= capacity,
+            None => false,
+        };
+        if full {
+            match state.config.policy(kind) {
+                OverflowPolicy::Error => return Err(WebthingsError::QueueFull),
+                OverflowPolicy::Block => return Ok(Push::Full(outgoing)),
+                OverflowPolicy::DropOldest => {
+                    let oldest = state.items.iter().position(
+                        |item| matches!(item, Outgoing::Write { kind: k, .. } if *k == kind),
+                    );
+                    match oldest.and_then(|oldest| state.items.remove(oldest)) {
+                        Some(Outgoing::Write {
+                            done, log_prefix, ..
+                        }) => {
+                            state.writes -= 1;
+                            done.finish(&log_prefix, Err(WebthingsError::MessageDropped));
+                        }
+                        _ => return Ok(Push::Full(outgoing)),
+                    }
+                }
+            }
+        }
+        state.writes += 1;
+        state.items.push_back(outgoing);
+        self.readable.notify_one();
+        Ok(Push::Queued)
+    }
+
+    async fn push(&self, mut outgoing: Outgoing) -> Result<(), WebthingsError> {
+        loop {
+            let writable = self.writable.notified();
+            match self.try_push(outgoing)? {
+                Push::Queued => return Ok(()),
+                Push::Full(rejected) => outgoing = rejected,
+            }
+            writable.await;
+        }
+    }
+
+    async fn pop(&self) -> Option<Outgoing> {
+        loop {
+            {
+                let mut state = self.state.lock().unwrap();
+                if let Some(outgoing) = state.items.pop_front() {
+                    if let Outgoing::Write { .. } = outgoing {
+                        state.writes -= 1;
+                        self.writable.notify_waiters();
+                    }
+                    return Some(outgoing);
+                }
+                if state.closed {
+                    return None;
+                }
+            }
+            self.readable.notified().await;
+        }
+    }
+
+    /// Reject all further messages, the queued ones are still written.
+    fn finish(&self) {
+        self.state.lock().unwrap().closed = true;
+        self.readable.notify_one();
+    }
+
+    /// Reject all further messages and drop the queued ones.
+    fn close(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.closed = true;
+        state.items.clear();
+        state.writes = 0;
+        self.readable.notify_one();
+        self.writable.notify_waiters();
+    }
+}
+
+/// Write all [outgoing][Outgoing] messages to the sink, one at a time.
+///
+/// This is the only place which writes to the websocket.
+async fn run_sender<S>(mut sink: S, queue: Arc<OutgoingQueue>)
+where
+    S: Sink<Message, Error = tungstenite::Error> + Unpin,
+{
+    while let Some(outgoing) = queue.pop().await {
+        match outgoing {
+            Outgoing::Write {
+                msg,
+                flush,
+                retry_policy,
+                log_prefix,
+                done,
+                ..
+            } => {
+                let result = send_frame(&mut sink, msg, flush, retry_policy, &log_prefix).await;
+                done.finish(&log_prefix, result);
+            }
+            Outgoing::Flush(done) => {
+                let _ = done.send(sink.flush().await.map_err(WebthingsError::Send));
+            }
+            Outgoing::Close(done) => {
+                let _ = done.send(sink.close().await.map_err(WebthingsError::Send));
+                break;
+            }
+        }
+    }
+    queue.close();
+}
+
+/// Write a message to the sink, retrying transient errors according to the [policy][RetryPolicy].
+///
+/// Retries happen before the next message is taken from the queue, so the order is kept.
+async fn send_frame<S>(
+    sink: &mut S,
+    msg: String,
+    flush: bool,
+    retry_policy: RetryPolicy,
+    log_prefix: &str,
+) -> Result<(), WebthingsError>
+where
+    S: Sink<Message, Error = tungstenite::Error> + Unpin,
+{
+    let mut attempt = 0;
+    loop {
+        let frame = Message::Text(msg.clone());
+        let result = if flush {
+            sink.send(frame).await
+        } else {
+            sink.feed(frame).await
+        };
+        match result {
+            Err(err) if attempt < retry_policy.retries && is_transient(&err) => {
+                attempt += 1;
+                log::debug!(
+                    "{}Could not send message ({}), retrying ({}/{})",
+                    log_prefix,
+                    err,
+                    attempt,
+                    retry_policy.retries
+                );
+                sleep(retry_policy.delay(attempt)).await;
+            }
+            result => return result.map_err(WebthingsError::Send),
+        }
+    }
+}
+
+/// A client which sends messages to the gateway.
+///
+/// A background task owns the websocket and writes all messages in the order they were enqueued.
+/// The `enqueue_*` methods only need the client to enqueue a message and return a future which
+/// resolves to the result of the write, so the client can be unlocked before awaiting it.
 pub struct WebsocketClient {
-    sink: SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, Message>,
+    queue: Arc<OutgoingQueue>,
+    log_prefix: String,
+    retry_policy: RetryPolicy,
+    message_tap: Option<MessageTap>,
+    health: Option<Arc<HealthTracker>>,
 }
 
 impl WebsocketClient {
-    pub fn new(sink: SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, Message>) -> Self {
-        Self { sink }
+    /// Create a new client and spawn the sender task which owns the given sink.
+    ///
+    /// The task is spawned on the given runtime, or on the current one if `None`.
+    pub fn new(sink: WebsocketSink, runtime: Option<&Handle>) -> Self {
+        let queue = Arc::new(OutgoingQueue::default());
+        spawn(runtime, run_sender(sink, queue.clone()));
+
+        Self {
+            queue,
+            log_prefix: String::new(),
+            retry_policy: RetryPolicy::default(),
+            message_tap: None,
+            health: None,
+        }
     }
 
-    pub async fn send(&mut self, msg: String) -> Result<(), WebthingsError> {
-        log::trace!("Sending message {}", msg);
+    pub fn set_log_prefix(&mut self, log_prefix: String) {
+        self.log_prefix = log_prefix;
+    }
 
-        self.sink
-            .send(Message::Text(msg))
-            .await
-            .map_err(WebthingsError::Send)
+    pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+        self.retry_policy = retry_policy;
+    }
+
+    pub fn set_message_tap(&mut self, message_tap: Option<MessageTap>) {
+        self.message_tap = message_tap;
     }
 
+    pub fn set_queue_config(&mut self, queue_config: QueueConfig) {
+        self.queue.set_config(queue_config);
+    }
+
+    #[cfg_attr(test, allow(dead_code))]
+    pub(crate) fn set_health_tracker(&mut self, health: Arc<HealthTracker>) {
+        self.health = Some(health);
+    }
+
+    pub async fn send(&mut self, msg: String) -> Result<(), WebthingsError> {
+        write(
+            &self.queue,
+            &self.log_prefix,
+            msg,
+            MessageKind::Other,
+            true,
+            RetryPolicy::default(),
+        )
+        .await?
+        .await
+    }
+
+    /// Send a message and flush it to the gateway immediately.
     pub async fn send_message(&mut self, msg: &IPCMessage) -> Result<(), WebthingsError> {
+        self.enqueue_message(msg).await?.await
+    }
+
+    /// Send a message without flushing it.
+    ///
+    /// Use this to send many messages at once, followed by a single [flush][WebsocketClient::flush].
+    pub async fn send_message_no_flush(&mut self, msg: &IPCMessage) -> Result<(), WebthingsError> {
+        self.enqueue_message_no_flush(msg).await?.await
+    }
+
+    /// Enqueue a message which is flushed to the gateway immediately.
+    ///
+    /// Returns a future which resolves once the message was written, including [retries][RetryPolicy].
+    /// The message keeps its position among all other messages, so the client does not have to
+    /// stay locked while awaiting the future.
+    pub async fn enqueue_message(
+        &mut self,
+        msg: &IPCMessage,
+    ) -> Result<WriteCompletion, WebthingsError> {
+        self.enqueue_message_with(msg, true).await
+    }
+
+    /// Like [enqueue_message][WebsocketClient::enqueue_message], but without flushing the message.
+    pub async fn enqueue_message_no_flush(
+        &mut self,
+        msg: &IPCMessage,
+    ) -> Result<WriteCompletion, WebthingsError> {
+        self.enqueue_message_with(msg, false).await
+    }
+
+    /// Enqueue a message without waiting for it to be written.
+    ///
+    /// The message is written to the gateway by the sender task in order with all other messages.
+    /// Neither [retries][RetryPolicy] nor errors are reported back to the caller, send errors are
+    /// logged instead. Fails with [WouldBlock][WebthingsError::WouldBlock] if the message would
+    /// have to wait for space in the [queue][QueueConfig].
+    pub fn try_send_message(&mut self, msg: &IPCMessage) -> Result<(), WebthingsError> {
+        let json = serde_json::to_string(msg).map_err(WebthingsError::Serialization)?;
+
+        tap_message(&self.message_tap, &self.log_prefix, msg);
+        log::trace!("{}Enqueueing message {}", self.log_prefix, json);
+
+        let bytes = json.len();
+        match self.queue.try_push(Outgoing::Write {
+            msg: json,
+            kind: MessageKind::of(msg),
+            flush: true,
+            retry_policy: RetryPolicy::default(),
+            log_prefix: self.log_prefix.clone(),
+            done: Done::Detached {
+                health: self.health.clone(),
+                bytes,
+            },
+        })? {
+            Push::Queued => Ok(()),
+            Push::Full(_) => Err(WebthingsError::WouldBlock),
+        }
+    }
+
+    /// Flush all messages which were sent without flushing.
+    pub async fn flush(&mut self) -> Result<(), WebthingsError> {
+        self.enqueue_flush().await?.await
+    }
+
+    /// Enqueue a [flush][WebsocketClient::flush] and return a future which resolves once it is done.
+    pub async fn enqueue_flush(&mut self) -> Result<WriteCompletion, WebthingsError> {
+        request(&self.queue, Outgoing::Flush).await
+    }
+
+    async fn enqueue_message_with(
+        &mut self,
+        msg: &IPCMessage,
+        flush: bool,
+    ) -> Result<WriteCompletion, WebthingsError> {
         let json = serde_json::to_string(msg).map_err(WebthingsError::Serialization)?;
+        let kind = MessageKind::of(msg);
+
+        tap_message(&self.message_tap, &self.log_prefix, msg);
 
-        self.send(json).await
+        let retry_policy = if is_resendable(msg) {
+            self.retry_policy
+        } else {
+            RetryPolicy::default()
+        };
+        let bytes = json.len();
+        let completion = write(
+            &self.queue,
+            &self.log_prefix,
+            json,
+            kind,
+            flush,
+            retry_policy,
+        )
+        .await?;
+        let health = self.health.clone();
+
+        Ok(Box::pin(async move {
+            let result = completion.await;
+            if let Some(health) = &health {
+                match &result {
+                    Ok(()) => {
+                        health.record_message();
+                        health.record_sent(bytes);
+                    }
+                    Err(err) => health.record_error(err),
+                }
+            }
+            result
+        }))
+    }
+
+    /// Flush all pending messages and close the connection.
+    pub async fn close(&mut self) -> Result<(), WebthingsError> {
+        request(&self.queue, Outgoing::Close).await?.await
+    }
+}
+
+/// Enqueue a request to the sender task and return a future which resolves to its result.
+async fn request(
+    queue: &OutgoingQueue,
+    outgoing: impl FnOnce(Completion) -> Outgoing,
+) -> Result<WriteCompletion, WebthingsError> {
+    let (done, result) = oneshot::channel();
+    queue.push(outgoing(done)).await?;
+    Ok(Box::pin(async move {
+        result
+            .await
+            .unwrap_or(Err(WebthingsError::Send(tungstenite::Error::AlreadyClosed)))
+    }))
+}
+
+async fn write(
+    queue: &OutgoingQueue,
+    log_prefix: &str,
+    msg: String,
+    kind: MessageKind,
+    flush: bool,
+    retry_policy: RetryPolicy,
+) -> Result<WriteCompletion, WebthingsError> {
+    log::trace!("{}Sending message {}", log_prefix, msg);
+
+    request(queue, |done| Outgoing::Write {
+        msg,
+        kind,
+        flush,
+        retry_policy,
+        log_prefix: log_prefix.to_owned(),
+        done: Done::Reply(done),
+    })
+    .await
+}
+
+impl Drop for WebsocketClient {
+    fn drop(&mut self) {
+        self.queue.finish();
+    }
+}
+
+/// Whether sending may succeed when retried after this error.
+fn is_transient(err: &tungstenite::Error) -> bool {
+    match err {
+        tungstenite::Error::SendQueueFull(_) => true,
+        tungstenite::Error::Io(err) => matches!(
+            err.kind(),
+            ErrorKind::Interrupted | ErrorKind::TimedOut | ErrorKind::WouldBlock
+        ),
+        _ => false,
     }
 }
 
+/// Whether the gateway tolerates receiving this message more than once.
+///
+/// Replies to requests are matched by the gateway and must not be duplicated.
+fn is_resendable(msg: &IPCMessage) -> bool {
+    !matches!(
+        msg,
+        IPCMessage::PluginRegisterRequest(_)
+            | IPCMessage::PluginUnloadResponse(_)
+            | IPCMessage::AdapterUnloadResponse(_)
+            | IPCMessage::AdapterRemoveDeviceResponse(_)
+            | IPCMessage::DeviceRequestActionResponse(_)
+            | IPCMessage::DeviceRemoveActionResponse(_)
+            | IPCMessage::ApiHandlerUnloadResponse(_)
+            | IPCMessage::ApiHandlerApiResponse(_)
+    )
+}
+
 #[double]
 pub use WebsocketClient as Client;
+
+/// Send a message, keeping the client locked only until the message is enqueued.
+pub(crate) async fn send_message(
+    client: &Mutex<Client>,
+    msg: &IPCMessage,
+) -> Result<(), WebthingsError> {
+    let completion = client.lock().await.enqueue_message(msg).await?;
+    completion.await
+}
+
+/// Send several messages in a row, keeping the client locked only until all of them are enqueued,
+/// so they are not interleaved with other messages.
+///
+/// Returns the result of every message.
+pub(crate) async fn send_each(
+    client: &Mutex<Client>,
+    msgs: &[IPCMessage],
+) -> Vec<Result<(), WebthingsError>> {
+    let mut completions = Vec::with_capacity(msgs.len());
+    {
+        let mut client = client.lock().await;
+        for msg in msgs {
+            completions.push(client.enqueue_message(msg).await);
+        }
+    }
+    let mut results = Vec::with_capacity(completions.len());
+    for completion in completions {
+        results.push(match completion {
+            Ok(completion) => completion.await,
+            Err(err) => Err(err),
+        });
+    }
+    results
+}
+
+/// Send several messages in a row and flush them once, keeping the client locked only until all
+/// of them are enqueued, so they are not interleaved with other messages.
+pub(crate) async fn send_messages(
+    client: &Mutex<Client>,
+    msgs: &[IPCMessage],
+) -> Result<(), WebthingsError> {
+    let mut completions = Vec::with_capacity(msgs.len() + 1);
+    {
+        let mut client = client.lock().await;
+        for msg in msgs {
+            completions.push(client.enqueue_message_no_flush(msg).await?);
+        }
+        completions.push(client.enqueue_flush().await?);
+    }
+    for completion in completions {
+        completion.await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        is_resendable, is_transient, request, run_sender, tap_message, Done, MessageKind,
+        MessageTap, Outgoing, OutgoingQueue, OverflowPolicy, Push, QueueConfig, RetryPolicy,
+    };
+    use crate::error::WebthingsError;
+    use futures::{channel::mpsc as futures_mpsc, Sink, SinkExt, StreamExt};
+    use std::{
+        io::{Error, ErrorKind},
+        pin::Pin,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        task::{Context, Poll},
+        time::Duration,
+    };
+    use tokio::{sync::oneshot, time::sleep};
+    use tokio_tungstenite::tungstenite::protocol::Message as WebsocketMessage;
+    use webthings_gateway_ipc_types::{
+        DevicePropertyChangedNotificationMessageData, Message, PluginUnloadResponseMessageData,
+        Property,
+    };
+
+    #[test]
+    fn test_retry_delay() {
+        let retry_policy = RetryPolicy {
+            retries: 3,
+            backoff: Duration::from_millis(10),
+        };
+        assert_eq!(retry_policy.delay(1), Duration::from_millis(10));
+        assert_eq!(retry_policy.delay(2), Duration::from_millis(20));
+        assert_eq!(retry_policy.delay(3), Duration::from_millis(40));
+    }
+
+    #[test]
+    fn test_is_transient() {
+        assert!(is_transient(&tungstenite::Error::Io(Error::from(
+            ErrorKind::TimedOut
+        ))));
+        assert!(!is_transient(&tungstenite::Error::Io(Error::from(
+            ErrorKind::BrokenPipe
+        ))));
+        assert!(!is_transient(&tungstenite::Error::ConnectionClosed));
+    }
+
+    #[test]
+    fn test_is_resendable() {
+        let notification: Message = DevicePropertyChangedNotificationMessageData {
+            plugin_id: "plugin_id".to_owned(),
+            adapter_id: "adapter_id".to_owned(),
+            device_id: "device_id".to_owned(),
+            property: Property {
+                at_type: None,
+                description: None,
+                enum_: None,
+                links: None,
+                maximum: None,
+                minimum: None,
+                multiple_of: None,
+                name: Some("property_name".to_owned()),
+                read_only: None,
+                title: None,
+                type_: "integer".to_owned(),
+                unit: None,
+                value: None,
+                visible: None,
+            },
+        }
+        .into();
+        assert!(is_resendable(&notification));
+
+        let response: Message = PluginUnloadResponseMessageData {
+            plugin_id: "plugin_id".to_owned(),
+        }
+        .into();
+        assert!(!is_resendable(&response));
+    }
+
+    fn message() -> Message {
+        PluginUnloadResponseMessageData {
+            plugin_id: "plugin_id".to_owned(),
+        }
+        .into()
+    }
+
+    #[test]
+    fn test_tap_message() {
+        let count = Arc::new(AtomicUsize::new(0));
+        let count_clone = count.clone();
+        let message_tap: MessageTap = Arc::new(move |_| {
+            count_clone.fetch_add(1, Ordering::SeqCst);
+        });
+        tap_message(&Some(message_tap), "", &message());
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_tap_message_panic() {
+        let message_tap: MessageTap = Arc::new(|_| panic!("Tap failed"));
+        tap_message(&Some(message_tap), "", &message());
+    }
+
+    fn outgoing(
+        msg: &str,
+        kind: MessageKind,
+    ) -> (Outgoing, oneshot::Receiver<Result<(), WebthingsError>>) {
+        retrying(msg, kind, RetryPolicy::default())
+    }
+
+    fn retrying(
+        msg: &str,
+        kind: MessageKind,
+        retry_policy: RetryPolicy,
+    ) -> (Outgoing, oneshot::Receiver<Result<(), WebthingsError>>) {
+        let (done, result) = oneshot::channel();
+        let outgoing = Outgoing::Write {
+            msg: msg.to_owned(),
+            kind,
+            flush: true,
+            retry_policy,
+            log_prefix: String::new(),
+            done: Done::Reply(done),
+        };
+        (outgoing, result)
+    }
+
+    async fn write(queue: &OutgoingQueue, msg: &str) -> Result<(), WebthingsError> {
+        let (outgoing, result) = outgoing(msg, MessageKind::Other);
+        queue.push(outgoing).await.unwrap();
+        result.await.unwrap()
+    }
+
+    async fn popped(queue: &OutgoingQueue) -> String {
+        match queue.pop().await {
+            Some(Outgoing::Write { msg, .. }) => msg,
+            _ => panic!("Expected a write"),
+        }
+    }
+
+    fn bounded(policy: OverflowPolicy) -> OutgoingQueue {
+        let queue = OutgoingQueue::default();
+        queue.set_config(QueueConfig {
+            capacity: Some(2),
+            property: policy,
+            event: policy,
+            response: policy,
+            other: policy,
+        });
+        queue
+    }
+
+    #[tokio::test]
+    async fn test_run_sender() {
+        let (sink, stream) = futures_mpsc::unbounded();
+        let sink = sink.sink_map_err(|_| tungstenite::Error::ConnectionClosed);
+        let queue = Arc::new(OutgoingQueue::default());
+        tokio::spawn(run_sender(sink, queue.clone()));
+
+        write(&queue, "foo").await.unwrap();
+        write(&queue, "bar").await.unwrap();
+        queue.finish();
+
+        assert_eq!(
+            stream.collect::<Vec<_>>().await,
+            vec![
+                WebsocketMessage::Text("foo".to_owned()),
+                WebsocketMessage::Text("bar".to_owned())
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_sender_error() {
+        let (sink, stream) = futures_mpsc::unbounded();
+        let sink = sink.sink_map_err(|_| tungstenite::Error::ConnectionClosed);
+        let queue = Arc::new(OutgoingQueue::default());
+        tokio::spawn(run_sender(sink, queue.clone()));
+        drop(stream);
+
+        assert!(matches!(
+            write(&queue, "foo").await,
+            Err(WebthingsError::Send(tungstenite::Error::ConnectionClosed))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_request_completes_after_write() {
+        let queue = OutgoingQueue::default();
+        let mut completion = request(&queue, Outgoing::Flush).await.unwrap();
+        assert!(futures::poll!(&mut completion).is_pending());
+
+        match queue.pop().await {
+            Some(Outgoing::Flush(done)) => done.send(Ok(())).unwrap(),
+            _ => panic!("Expected a flush"),
+        }
+        assert!(completion.await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_request_sender_gone() {
+        let queue = OutgoingQueue::default();
+        let completion = request(&queue, Outgoing::Flush).await.unwrap();
+        drop(queue.pop().await);
+        assert!(matches!(
+            completion.await,
+            Err(WebthingsError::Send(tungstenite::Error::AlreadyClosed))
+        ));
+    }
+
+    /// A sink which fails to accept the first `failures` messages with a transient error.
+    struct FlakySink<S> {
+        sink: S,
+        failures: usize,
+    }
+
+    impl<S: Sink<WebsocketMessage, Error = tungstenite::Error> + Unpin> Sink<WebsocketMessage>
+        for FlakySink<S>
+    {
+        type Error = tungstenite::Error;
+
+        fn poll_ready(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+        ) -> Poll<Result<(), Self::Error>> {
+            Pin::new(&mut self.sink).poll_ready(cx)
+        }
+
+        fn start_send(mut self: Pin<&mut Self>, item: WebsocketMessage) -> Result<(), Self::Error> {
+            if self.failures > 0 {
+                self.failures -= 1;
+                return Err(tungstenite::Error::Io(Error::from(ErrorKind::WouldBlock)));
+            }
+            Pin::new(&mut self.sink).start_send(item)
+        }
+
+        fn poll_flush(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+        ) -> Poll<Result<(), Self::Error>> {
+            Pin::new(&mut self.sink).poll_flush(cx)
+        }
+
+        fn poll_close(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+        ) -> Poll<Result<(), Self::Error>> {
+            Pin::new(&mut self.sink).poll_close(cx)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_sender_retry_keeps_order() {
+        let (sink, stream) = futures_mpsc::unbounded();
+        let sink = FlakySink {
+            sink: sink.sink_map_err(|_| tungstenite::Error::ConnectionClosed),
+            failures: 1,
+        };
+        let queue = Arc::new(OutgoingQueue::default());
+        let retry_policy = RetryPolicy {
+            retries: 1,
+            backoff: Duration::from_millis(1),
+        };
+        let (first, first_result) = retrying("first", MessageKind::Property, retry_policy);
+        let (second, second_result) = retrying("second", MessageKind::Property, retry_policy);
+        queue.push(first).await.unwrap();
+        queue.push(second).await.unwrap();
+        tokio::spawn(run_sender(sink, queue.clone()));
+
+        assert!(first_result.await.unwrap().is_ok());
+        assert!(second_result.await.unwrap().is_ok());
+        queue.finish();
+        assert_eq!(
+            stream.collect::<Vec<_>>().await,
+            vec![
+                WebsocketMessage::Text("first".to_owned()),
+                WebsocketMessage::Text("second".to_owned())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_message_kind() {
+        assert_eq!(MessageKind::of(&message()), MessageKind::Response);
+    }
+
+    #[tokio::test]
+    async fn test_queue_drop_oldest() {
+        let queue = bounded(OverflowPolicy::DropOldest);
+        let (first, first_result) = outgoing("first", MessageKind::Property);
+        let (second, _second_result) = outgoing("second", MessageKind::Response);
+        let (third, _third_result) = outgoing("third", MessageKind::Property);
+        queue.push(first).await.unwrap();
+        queue.push(second).await.unwrap();
+        queue.push(third).await.unwrap();
+
+        assert!(matches!(
+            first_result.await.unwrap(),
+            Err(WebthingsError::MessageDropped)
+        ));
+        assert_eq!(popped(&queue).await, "second");
+        assert_eq!(popped(&queue).await, "third");
+    }
+
+    #[tokio::test]
+    async fn test_queue_drop_oldest_other_kind() {
+        let queue = bounded(OverflowPolicy::DropOldest);
+        queue
+            .push(outgoing("first", MessageKind::Response).0)
+            .await
+            .unwrap();
+        queue
+            .push(outgoing("second", MessageKind::Response).0)
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            queue.try_push(outgoing("third", MessageKind::Property).0),
+            Ok(Push::Full(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_queue_error() {
+        let queue = bounded(OverflowPolicy::Error);
+        queue
+            .push(outgoing("first", MessageKind::Event).0)
+            .await
+            .unwrap();
+        queue
+            .push(outgoing("second", MessageKind::Event).0)
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            queue.push(outgoing("third", MessageKind::Event).0).await,
+            Err(WebthingsError::QueueFull)
+        ));
+        assert_eq!(popped(&queue).await, "first");
+        assert_eq!(popped(&queue).await, "second");
+    }
+
+    #[tokio::test]
+    async fn test_queue_block() {
+        let queue = Arc::new(bounded(OverflowPolicy::Block));
+        queue
+            .push(outgoing("first", MessageKind::Response).0)
+            .await
+            .unwrap();
+        queue
+            .push(outgoing("second", MessageKind::Response).0)
+            .await
+            .unwrap();
+
+        let blocked = {
+            let queue = queue.clone();
+            tokio::spawn(async move {
+                queue
+                    .push(outgoing("third", MessageKind::Response).0)
+                    .await
+                    .unwrap();
+            })
+        };
+        sleep(Duration::from_millis(20)).await;
+        assert!(!blocked.is_finished());
+
+        assert_eq!(popped(&queue).await, "first");
+        blocked.await.unwrap();
+        assert_eq!(popped(&queue).await, "second");
+        assert_eq!(popped(&queue).await, "third");
+    }
+
+    #[tokio::test]
+    async fn test_queue_unbounded() {
+        let queue = OutgoingQueue::default();
+        for i in 0..100 {
+            queue
+                .push(outgoing(&i.to_string(), MessageKind::Property).0)
+                .await
+                .unwrap();
+        }
+        assert_eq!(popped(&queue).await, "0");
+    }
+
+    #[tokio::test]
+    async fn test_queue_closed() {
+        let queue = OutgoingQueue::default();
+        queue.close();
+        assert!(matches!(
+            queue.push(outgoing("first", MessageKind::Other).0).await,
+            Err(WebthingsError::Send(tungstenite::Error::AlreadyClosed))
+        ));
+        assert!(queue.pop().await.is_none());
+    }
+}
@@ -0,0 +1,77 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+
+//! A module for converting WoT descriptions into their IPC form.
+
+use crate::error::WebthingsError;
+use serde::{de::DeserializeOwned, Serialize};
+
+/// A trait which converts a WoT description into the form sent to the gateway.
+///
+/// Implemented for [PropertyDescription][crate::PropertyDescription],
+/// [ActionDescription][crate::ActionDescription] and [EventDescription][crate::EventDescription],
+/// so tooling can handle any of them uniformly, e.g. to dump the descriptions of an addon.
+///
+/// # Examples
+/// ```
+/// # use gateway_addon_rust::{description::IntoFullDescription, prelude::*};
+/// fn dump<D: IntoFullDescription>(name: &str, description: D) -> String {
+///     let description = description.into_full_description(name.to_owned()).unwrap();
+///     serde_json::to_string(&description).unwrap()
+/// }
+///
+/// dump("level", PropertyDescription::<u8>::default());
+/// dump("fade", ActionDescription::<u8>::default());
+/// dump("overheated", EventDescription::<f32>::default());
+/// ```
+pub trait IntoFullDescription {
+    /// The IPC form of the description.
+    type FullDescription: Serialize;
+
+    /// Convert the description into its IPC form, using the given name of the property, action or event.
+    fn into_full_description(self, name: String) -> Result<Self::FullDescription, WebthingsError>;
+}
+
+/// Parse a `@type`, keeping values unknown to this crate in the `other` variant.
+pub(crate) fn parse_at_type<T: DeserializeOwned>(
+    at_type: String,
+    other: impl FnOnce(String) -> T,
+) -> T {
+    serde_json::from_value(serde_json::Value::String(at_type.clone()))
+        .unwrap_or_else(|_| other(at_type))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IntoFullDescription;
+    use crate::{ActionDescription, EventDescription, PropertyDescription};
+
+    #[test]
+    fn test_into_full_description() {
+        let property = IntoFullDescription::into_full_description(
+            PropertyDescription::<i32>::default().title("foo"),
+            "property".to_owned(),
+        )
+        .unwrap();
+        assert_eq!(property.name, Some("property".to_owned()));
+        assert_eq!(property.title, Some("foo".to_owned()));
+
+        let action = IntoFullDescription::into_full_description(
+            ActionDescription::<i32>::default().title("foo"),
+            "action".to_owned(),
+        )
+        .unwrap();
+        assert_eq!(action.title, Some("foo".to_owned()));
+
+        let event = IntoFullDescription::into_full_description(
+            EventDescription::<i32>::default().title("foo"),
+            "event".to_owned(),
+        )
+        .unwrap();
+        assert_eq!(event.name, Some("event".to_owned()));
+        assert_eq!(event.title, Some("foo".to_owned()));
+    }
+}
@@ -5,10 +5,11 @@
  */
 
 use crate::{
-    actions, error::WebthingsError, events, properties, Actions, Device, DeviceDescription,
-    DeviceHandle, Events, Properties,
+    action::check_input_schema, actions, error::WebthingsError, events, properties, Actions,
+    Device, DeviceDescription, DeviceHandle, Events, Properties,
 };
-use std::collections::BTreeMap;
+use async_trait::async_trait;
+use std::collections::{BTreeMap, HashSet};
 use webthings_gateway_ipc_types::Device as FullDeviceDescription;
 
 /// A trait used to specify the structure of a WoT device.
@@ -74,31 +75,47 @@ pub trait DeviceStructure: Send + Sync + 'static {
 
     #[doc(hidden)]
     fn full_description(&self) -> Result<FullDeviceDescription, WebthingsError> {
-        let mut property_descriptions = BTreeMap::new();
-        for property_builder in self.properties() {
-            property_descriptions.insert(
-                property_builder.name(),
-                property_builder.full_description()?,
-            );
-        }
+        full_device_description(
+            self.id(),
+            self.description(),
+            &self.properties(),
+            &self.actions(),
+            &self.events(),
+        )
+    }
+}
 
-        let mut action_descriptions = BTreeMap::new();
-        for action in self.actions() {
-            action_descriptions.insert(action.name(), action.full_description());
-        }
+pub(crate) fn full_device_description(
+    id: String,
+    description: DeviceDescription,
+    properties: &Properties,
+    actions: &Actions,
+    events: &Events,
+) -> Result<FullDeviceDescription, WebthingsError> {
+    let mut property_descriptions = BTreeMap::new();
+    for property_builder in properties {
+        property_descriptions.insert(
+            property_builder.name(),
+            property_builder.full_description()?,
+        );
+    }
 
-        let mut event_descriptions = BTreeMap::new();
-        for event in self.events() {
-            event_descriptions.insert(event.name(), event.full_description()?);
-        }
+    let mut action_descriptions = BTreeMap::new();
+    for action in actions {
+        action_descriptions.insert(action.name(), action.full_description());
+    }
 
-        Ok(self.description().into_full_description(
-            self.id(),
-            property_descriptions,
-            action_descriptions,
-            event_descriptions,
-        ))
+    let mut event_descriptions = BTreeMap::new();
+    for event in events {
+        event_descriptions.insert(event.name(), event.full_description()?);
     }
+
+    Ok(description.into_full_description(
+        id,
+        property_descriptions,
+        action_descriptions,
+        event_descriptions,
+    ))
 }
 
 /// A trait used to build a [Device] around a data struct and a [device handle][DeviceHandle].
@@ -157,6 +174,178 @@ pub trait DeviceBuilder: DeviceStructure {
 
     /// Build the [device][Device] from a data struct and an [device handle][DeviceHandle].
     fn build(data: Self, device_handle: DeviceHandle) -> Self::BuiltDevice;
+
+    /// Check the structure of this device before it is added.
+    ///
+    /// Fails if the id or an affordance name is empty or contains control characters, an affordance name is used more than once
+    /// across properties, actions and events, or an affordance description is invalid,
+    /// including action input schemas which are not valid json schemas.
+    ///
+    /// This is run automatically by [add_device][crate::adapter::AdapterHandle::add_device].
+    fn validate(&self) -> Result<(), WebthingsError> {
+        validate_device(
+            &self.id(),
+            &self.properties(),
+            &self.actions(),
+            &self.events(),
+        )
+    }
+}
+
+/// An asynchronous variant of [DeviceStructure] + [DeviceBuilder].
+///
+/// Use this instead of [DeviceBuilder] if the structure of a device has to be determined
+/// using IO, e.g. by querying the physical device for its capabilities.
+/// Add it using [AdapterHandle::add_device_async][crate::AdapterHandle::add_device_async].
+///
+/// # Examples
+/// ```
+/// # use gateway_addon_rust::{prelude::*, device::{AsyncDeviceBuilder, BuiltDevice}, example::ExampleProperty};
+/// # use async_trait::async_trait;
+/// struct ExampleDevice {
+///     foo: i32,
+/// }
+///
+/// struct BuiltExampleDevice {
+///     data: ExampleDevice,
+///     device_handle: DeviceHandle,
+/// }
+///
+/// impl BuiltDevice for BuiltExampleDevice {
+///     // ...
+///   # fn device_handle(&self) -> &DeviceHandle {
+///   #     &self.device_handle
+///   # }
+///   # fn device_handle_mut(&mut self) -> &mut DeviceHandle {
+///   #     &mut self.device_handle
+///   # }
+/// }
+///
+/// #[async_trait]
+/// impl Device for BuiltExampleDevice {}
+///
+/// #[async_trait]
+/// impl AsyncDeviceBuilder for ExampleDevice {
+///     type BuiltDevice = BuiltExampleDevice;
+///
+///     fn id(&self) -> String {
+///         "example-device".to_owned()
+///     }
+///
+///     async fn description(&self) -> DeviceDescription {
+///         DeviceDescription::default()
+///     }
+///
+///     async fn properties(&self) -> Properties {
+///         // Query the device for its properties
+///         properties![ExampleProperty::new()]
+///     }
+///
+///     fn build(data: Self, device_handle: DeviceHandle) -> Self::BuiltDevice {
+///         BuiltExampleDevice {
+///             data,
+///             device_handle,
+///         }
+///     }
+/// }
+/// ```
+#[async_trait]
+pub trait AsyncDeviceBuilder: Send + Sync + 'static {
+    /// Type of [Device] to build.
+    type BuiltDevice: Device;
+
+    /// ID of the device.
+    fn id(&self) -> String;
+
+    /// [WoT description][DeviceDescription] of the device.
+    async fn description(&self) -> DeviceDescription;
+
+    /// A list of [properties][crate::property::PropertyBuilder] this device should own.
+    async fn properties(&self) -> Properties {
+        properties![]
+    }
+
+    /// A list of [actions][crate::Action] this device should own.
+    async fn actions(&self) -> Actions {
+        actions![]
+    }
+
+    /// A list of [events][crate::event::EventBuilder] this device should own.
+    async fn events(&self) -> Events {
+        events![]
+    }
+
+    /// Build the [device][Device] from a data struct and an [device handle][DeviceHandle].
+    fn build(data: Self, device_handle: DeviceHandle) -> Self::BuiltDevice;
+}
+
+/// Check whether the given id or affordance name is accepted by the gateway.
+///
+/// The gateway only rejects empty ids and ids containing control characters.
+/// Other characters, e.g. the `:` of MAC-derived ids, are escaped by the gateway where needed.
+pub(crate) fn validate_id(id: &str) -> Result<(), WebthingsError> {
+    if !id.is_empty() && !id.chars().any(char::is_control) {
+        Ok(())
+    } else {
+        Err(WebthingsError::InvalidId(id.to_owned()))
+    }
+}
+
+/// Check the structure of a device before its description is sent to the gateway.
+pub(crate) fn validate_device(
+    id: &str,
+    properties: &Properties,
+    actions: &Actions,
+    events: &Events,
+) -> Result<(), WebthingsError> {
+    validate_id(id)?;
+
+    let names = properties
+        .iter()
+        .map(|property| property.name())
+        .chain(actions.iter().map(|action| action.name()))
+        .chain(events.iter().map(|event| event.name()));
+    let mut seen = HashSet::new();
+    for name in names {
+        validate_id(&name)?;
+        if !seen.insert(name.clone()) {
+            return Err(WebthingsError::DuplicateName(name));
+        }
+    }
+
+    for property in properties {
+        property.full_description()?;
+    }
+    for action in actions {
+        if let Some(ref input) = action.full_description().input {
+            check_input_schema(&action.name(), input)?;
+        }
+    }
+    for event in events {
+        event.full_description()?;
+    }
+
+    Ok(())
+}
+
+/// Check the ids of a device and all of its affordances.
+pub(crate) fn validate_ids(description: &FullDeviceDescription) -> Result<(), WebthingsError> {
+    validate_id(&description.id)?;
+    let names = description
+        .properties
+        .iter()
+        .flat_map(|properties| properties.keys())
+        .chain(
+            description
+                .actions
+                .iter()
+                .flat_map(|actions| actions.keys()),
+        )
+        .chain(description.events.iter().flat_map(|events| events.keys()));
+    for name in names {
+        validate_id(name)?;
+    }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -164,20 +353,36 @@ pub(crate) mod tests {
     use crate::{
         action::{tests::MockAction, NoInput},
         actions,
-        device::{tests::BuiltMockDevice, DeviceBuilder},
+        device::{tests::BuiltMockDevice, validate_id, AsyncDeviceBuilder, DeviceBuilder},
+        error::WebthingsError,
         event::{tests::MockEvent, NoData},
         events, properties,
-        property::tests::MockProperty,
-        Actions, DeviceDescription, DeviceHandle, DeviceStructure, Events, Properties,
+        property::{
+            tests::{BuiltMockProperty, MockProperty},
+            PropertyBuilder, PropertyDescription, PropertyStructure,
+        },
+        ActionDescription, Actions, DeviceDescription, DeviceHandle, DeviceStructure, Events,
+        Properties, PropertyHandle,
     };
+    use async_trait::async_trait;
+    use rstest::rstest;
+    use serde_json::json;
 
     pub struct MockDevice {
         device_id: String,
+        /// Whether the weak self-reference was valid when `post_init` ran.
+        pub post_init_device_valid: Option<bool>,
+        /// The initial connected state of the device.
+        pub connected: bool,
     }
 
     impl MockDevice {
         pub fn new(device_id: String) -> Self {
-            Self { device_id }
+            Self {
+                device_id,
+                post_init_device_valid: None,
+                connected: true,
+            }
         }
 
         pub const PROPERTY_BOOL: &'static str = "property_bool";
@@ -202,7 +407,7 @@ pub(crate) mod tests {
         }
 
         fn description(&self) -> DeviceDescription {
-            DeviceDescription::default()
+            DeviceDescription::default().connected(self.connected)
         }
 
         fn properties(&self) -> Properties {
@@ -241,4 +446,197 @@ pub(crate) mod tests {
             BuiltMockDevice::new(data, device_handle)
         }
     }
+
+    pub struct MockAsyncDevice {
+        device_id: String,
+    }
+
+    impl MockAsyncDevice {
+        pub fn new(device_id: String) -> Self {
+            Self { device_id }
+        }
+    }
+
+    #[async_trait]
+    impl AsyncDeviceBuilder for MockAsyncDevice {
+        type BuiltDevice = BuiltMockDevice;
+
+        fn id(&self) -> String {
+            self.device_id.clone()
+        }
+
+        async fn description(&self) -> DeviceDescription {
+            DeviceDescription::default()
+        }
+
+        async fn properties(&self) -> Properties {
+            properties![MockProperty::<i32>::new(
+                MockDevice::PROPERTY_I32.to_owned()
+            )]
+        }
+
+        fn build(data: Self, device_handle: DeviceHandle) -> Self::BuiltDevice {
+            BuiltMockDevice::new(MockDevice::new(data.device_id), device_handle)
+        }
+    }
+
+    struct WriteOnlyReadOnlyProperty;
+
+    impl PropertyStructure for WriteOnlyReadOnlyProperty {
+        type Value = i32;
+
+        fn name(&self) -> String {
+            "conflicting".to_owned()
+        }
+
+        fn description(&self) -> PropertyDescription<Self::Value> {
+            PropertyDescription::default()
+                .write_only(true)
+                .read_only(true)
+        }
+    }
+
+    impl PropertyBuilder for WriteOnlyReadOnlyProperty {
+        type BuiltProperty = BuiltMockProperty<i32>;
+        fn build(_data: Self, _property_handle: PropertyHandle<i32>) -> Self::BuiltProperty {
+            unreachable!()
+        }
+    }
+
+    struct StructureDevice {
+        id: &'static str,
+        properties: fn() -> Properties,
+        actions: fn() -> Actions,
+    }
+
+    impl DeviceStructure for StructureDevice {
+        fn id(&self) -> String {
+            self.id.to_owned()
+        }
+
+        fn description(&self) -> DeviceDescription {
+            DeviceDescription::default()
+        }
+
+        fn properties(&self) -> Properties {
+            (self.properties)()
+        }
+
+        fn actions(&self) -> Actions {
+            (self.actions)()
+        }
+    }
+
+    impl DeviceBuilder for StructureDevice {
+        type BuiltDevice = BuiltMockDevice;
+        fn build(data: Self, device_handle: DeviceHandle) -> Self::BuiltDevice {
+            BuiltMockDevice::new(MockDevice::new(data.id.to_owned()), device_handle)
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(MockDevice::new("device".to_owned()).validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_empty_id() {
+        let device = StructureDevice {
+            id: "",
+            properties: || properties![],
+            actions: || actions![],
+        };
+        assert!(matches!(
+            device.validate(),
+            Err(WebthingsError::InvalidId(id)) if id.is_empty()
+        ));
+    }
+
+    #[test]
+    fn test_validate_duplicate_name() {
+        let device = StructureDevice {
+            id: "device",
+            properties: || properties![MockProperty::<i32>::new("toggle".to_owned())],
+            actions: || actions![MockAction::<NoInput>::new("toggle".to_owned())],
+        };
+        assert!(matches!(
+            device.validate(),
+            Err(WebthingsError::DuplicateName(name)) if name == "toggle"
+        ));
+    }
+
+    #[test]
+    fn test_validate_invalid_name() {
+        let device = StructureDevice {
+            id: "device",
+            properties: || properties![MockProperty::<i32>::new("foo\nbar".to_owned())],
+            actions: || actions![],
+        };
+        assert!(matches!(
+            device.validate(),
+            Err(WebthingsError::InvalidId(name)) if name == "foo\nbar"
+        ));
+    }
+
+    #[test]
+    fn test_validate_control_character_id() {
+        let device = StructureDevice {
+            id: "foo\u{0}",
+            properties: || properties![],
+            actions: || actions![],
+        };
+        assert!(matches!(
+            device.validate(),
+            Err(WebthingsError::InvalidId(id)) if id == "foo\u{0}"
+        ));
+    }
+
+    #[test]
+    fn test_validate_invalid_input_schema() {
+        let device = StructureDevice {
+            id: "device",
+            properties: || properties![],
+            actions: || {
+                let mut action = MockAction::<serde_json::Value>::new("fade".to_owned());
+                action.description = ActionDescription::default().input(json!({"type": 42}));
+                actions![action]
+            },
+        };
+        assert!(matches!(
+            device.validate(),
+            Err(WebthingsError::InvalidInputSchema(name, _)) if name == "fade"
+        ));
+    }
+
+    #[test]
+    fn test_validate_invalid_description() {
+        let device = StructureDevice {
+            id: "device",
+            properties: || properties![WriteOnlyReadOnlyProperty],
+            actions: || actions![],
+        };
+        assert!(matches!(
+            device.validate(),
+            Err(WebthingsError::InvalidPropertyAccess(name, _)) if name == "conflicting"
+        ));
+    }
+
+    #[rstest]
+    #[case("foo")]
+    #[case("foo-bar_1.2~3")]
+    #[case("aa:bb:cc:dd:ee:ff")]
+    #[case("dev/ttyUSB0")]
+    #[case("foo bar")]
+    #[case("föö")]
+    fn test_validate_id(#[case] id: &str) {
+        assert!(validate_id(id).is_ok());
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("foo\nbar")]
+    #[case("foo\u{7f}")]
+    fn test_validate_invalid_id(#[case] id: &str) {
+        assert!(validate_id(id).is_err());
+    }
 }
@@ -0,0 +1,77 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+
+use crate::device::{AtType, DeviceDescription};
+
+/// A capability of a [device][DeviceDescription], e.g. to route commands to matching devices only.
+///
+/// See [Device::capabilities][crate::Device::capabilities].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DeviceCapability {
+    /// A capability of the WebThings schema, i.e. a device `@type`.
+    Schema(AtType),
+    /// A capability which is not part of the WebThings schema, e.g. an [unknown `@type`][AtType::Other].
+    Custom(String),
+}
+
+impl From<AtType> for DeviceCapability {
+    fn from(at_type: AtType) -> Self {
+        match at_type {
+            AtType::Other(at_type) => DeviceCapability::Custom(at_type),
+            at_type => DeviceCapability::Schema(at_type),
+        }
+    }
+}
+
+impl DeviceDescription {
+    /// The [capabilities][DeviceCapability] of the device, derived from its `@type`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use gateway_addon_rust::{prelude::*, device::{AtType, DeviceCapability}};
+    /// let description = DeviceDescription::default().at_types(vec![AtType::Light, AtType::OnOffSwitch]);
+    /// assert!(description
+    ///     .capabilities()
+    ///     .contains(&DeviceCapability::Schema(AtType::Light)));
+    /// ```
+    pub fn capabilities(&self) -> Vec<DeviceCapability> {
+        self.at_type
+            .iter()
+            .flatten()
+            .cloned()
+            .map(DeviceCapability::from)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        device::{AtType, DeviceCapability},
+        DeviceDescription,
+    };
+
+    #[test]
+    fn test_capabilities() {
+        let description = DeviceDescription::default()
+            .at_type(AtType::Light)
+            .at_type(AtType::OnOffSwitch)
+            .at_type(AtType::ColorControl);
+        assert_eq!(
+            description.capabilities(),
+            vec![
+                DeviceCapability::Schema(AtType::Light),
+                DeviceCapability::Schema(AtType::OnOffSwitch),
+                DeviceCapability::Schema(AtType::ColorControl),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_no_capabilities() {
+        assert!(DeviceDescription::default().capabilities().is_empty());
+    }
+}
@@ -4,9 +4,14 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
  */
 
+use crate::{
+    description::parse_at_type, error::WebthingsError, ActionDescription, EventDescription,
+    PropertyDescription,
+};
+use serde::Deserialize;
 use std::collections::BTreeMap;
 use webthings_gateway_ipc_types::{
-    Action as FullActionDescription, Device as FullDeviceDescription, DevicePin,
+    Action as FullActionDescription, Device as FullDeviceDescription, DevicePin, DeviceWithoutId,
     Event as FullEventDescription, Link, Property as FullPropertyDescription,
 };
 
@@ -36,10 +41,12 @@ pub struct DeviceDescription {
     pub links: Option<Vec<Link>>,
     pub pin: Option<DevicePin>,
     pub title: Option<String>,
+    /// The connected state which is announced to the gateway when the device is added.
+    pub connected: bool,
 }
 
 /// Possible values of `@type` for a [device][DeviceDescription].
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
 pub enum AtType {
     Alarm,
     AirQualitySensor,
@@ -64,14 +71,87 @@ pub enum AtType {
     TemperatureSensor,
     Thermostat,
     VideoCamera,
+    /// A `@type` unknown to this crate, e.g. of a description parsed with [from_full][DeviceDescription::from_full].
+    Other(String),
 }
 
 impl ToString for AtType {
     fn to_string(&self) -> String {
-        format!("{:?}", self)
+        match self {
+            AtType::Other(at_type) => at_type.clone(),
+            at_type => format!("{:?}", at_type),
+        }
+    }
+}
+
+/// A [full device description][FullDeviceDescription] parsed by [DeviceDescription::from_full].
+///
+/// The affordances are kept with untyped values, so the description can be converted back
+/// using [into_full_description][ParsedDeviceDescription::into_full_description].
+#[derive(Clone)]
+pub struct ParsedDeviceDescription {
+    pub id: String,
+    pub description: DeviceDescription,
+    pub properties: Option<BTreeMap<String, PropertyDescription<serde_json::Value>>>,
+    pub actions: Option<BTreeMap<String, ActionDescription<serde_json::Value>>>,
+    pub events: Option<BTreeMap<String, EventDescription<serde_json::Value>>>,
+}
+
+impl ParsedDeviceDescription {
+    /// Convert the parsed description back into a [full device description][FullDeviceDescription].
+    pub fn into_full_description(self) -> Result<FullDeviceDescription, WebthingsError> {
+        let properties = match self.properties {
+            Some(properties) => Some(
+                properties
+                    .into_iter()
+                    .map(|(name, property)| {
+                        Ok((name.clone(), property.into_full_description(name)?))
+                    })
+                    .collect::<Result<BTreeMap<_, _>, WebthingsError>>()?,
+            ),
+            None => None,
+        };
+        let actions = self.actions.map(|actions| {
+            actions
+                .into_iter()
+                .map(|(name, action)| (name, action.into_full_description()))
+                .collect::<BTreeMap<_, _>>()
+        });
+        let events = match self.events {
+            Some(events) => Some(
+                events
+                    .into_iter()
+                    .map(|(name, event)| Ok((name.clone(), event.into_full_description(name)?)))
+                    .collect::<Result<BTreeMap<_, _>, WebthingsError>>()?,
+            ),
+            None => None,
+        };
+        let mut description = self.description.into_full_description(
+            self.id,
+            BTreeMap::new(),
+            BTreeMap::new(),
+            BTreeMap::new(),
+        );
+        description.properties = properties;
+        description.actions = actions;
+        description.events = events;
+        Ok(description)
     }
 }
 
+/// A device-level field of a [DeviceDescription], see [DeviceDescription::matches].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceField {
+    AtContext,
+    AtType,
+    BaseHref,
+    CredentialsRequired,
+    Description,
+    Links,
+    Pin,
+    Title,
+}
+
 /// # Builder methods
 impl DeviceDescription {
     /// Build an empty [DeviceDescription].
@@ -85,6 +165,7 @@ impl DeviceDescription {
             links: None,
             pin: None,
             title: None,
+            connected: true,
         }
     }
 
@@ -95,6 +176,31 @@ impl DeviceDescription {
         self
     }
 
+    /// Set `@context` from a list of context URIs, e.g. a base context plus vocabulary extensions.
+    ///
+    /// A single context is emitted as a plain string. As `@context` is a string in the IPC schema,
+    /// multiple contexts are emitted as the string of their JSON array, e.g. `["a","b"]`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use gateway_addon_rust::device::DeviceDescription;
+    /// # let _ =
+    /// DeviceDescription::default().contexts(vec![
+    ///     "https://webthings.io/schemas".to_owned(),
+    ///     "https://example.com/vocabulary".to_owned(),
+    /// ])
+    /// # ;
+    /// ```
+    #[must_use]
+    pub fn contexts(mut self, contexts: Vec<String>) -> Self {
+        self.at_context = match contexts.len() {
+            0 => None,
+            1 => contexts.into_iter().next(),
+            _ => Some(serde_json::Value::from(contexts).to_string()),
+        };
+        self
+    }
+
     /// Set `@type`.
     #[must_use]
     pub fn at_types(mut self, at_types: Vec<AtType>) -> Self {
@@ -155,19 +261,11 @@ impl DeviceDescription {
     /// # Examples
     /// ```
     /// # use gateway_addon_rust::device::DeviceDescription;
-    /// # use webthings_gateway_ipc_types::Link;
+    /// # use gateway_addon_rust::link::{Link, LinkBuilder};
     /// # let _ =
     /// DeviceDescription::default()
-    ///     .link(Link {
-    ///         href: "https://www.rust-lang.org/".to_owned(),
-    ///         media_type: None,
-    ///         rel: None,
-    ///     })
-    ///     .link(Link {
-    ///         href: "https://www.reddit.com/".to_owned(),
-    ///         media_type: None,
-    ///         rel: None,
-    ///     })
+    ///     .link(Link::new("https://www.rust-lang.org/"))
+    ///     .link(Link::new("https://www.reddit.com/"))
     /// # ;
     /// ```
     #[must_use]
@@ -193,6 +291,16 @@ impl DeviceDescription {
         self
     }
 
+    /// Set the initial connected state of the device.
+    ///
+    /// This is not part of the description sent to the gateway, but announced through a separate
+    /// connected state notification right after the device was added. Defaults to `true`.
+    #[must_use]
+    pub fn connected(mut self, connected: bool) -> Self {
+        self.connected = connected;
+        self
+    }
+
     #[doc(hidden)]
     pub fn into_full_description(
         self,
@@ -218,4 +326,264 @@ impl DeviceDescription {
             credentials_required: self.credentials_required,
         }
     }
+
+    /// Check whether a description saved by the gateway corresponds to this description,
+    /// e.g. in [Adapter::on_device_saved][crate::Adapter::on_device_saved].
+    ///
+    /// Only the device-level fields are compared, except for the `ignored` ones.
+    /// Ignore fields which users may edit within the gateway, e.g. [DeviceField::Title].
+    ///
+    /// # Examples
+    /// ```
+    /// # use gateway_addon_rust::device::{DeviceDescription, DeviceField};
+    /// # use webthings_gateway_ipc_types::DeviceWithoutId;
+    /// # let saved = DeviceWithoutId {
+    /// #     at_context: None,
+    /// #     at_type: None,
+    /// #     title: Some("My lamp".to_owned()),
+    /// #     description: None,
+    /// #     properties: None,
+    /// #     actions: None,
+    /// #     events: None,
+    /// #     links: None,
+    /// #     base_href: None,
+    /// #     pin: None,
+    /// #     credentials_required: None,
+    /// # };
+    /// let description = DeviceDescription::default().title("Lamp");
+    /// assert!(!description.matches(&saved, &[]));
+    /// assert!(description.matches(&saved, &[DeviceField::Title]));
+    /// ```
+    pub fn matches(&self, saved: &DeviceWithoutId, ignored: &[DeviceField]) -> bool {
+        let at_type = self
+            .at_type
+            .as_ref()
+            .map(|v| v.iter().map(|t| t.to_string()).collect::<Vec<_>>());
+        let checks = [
+            (DeviceField::AtContext, self.at_context == saved.at_context),
+            (DeviceField::AtType, at_type == saved.at_type),
+            (DeviceField::BaseHref, self.base_href == saved.base_href),
+            (
+                DeviceField::CredentialsRequired,
+                self.credentials_required == saved.credentials_required,
+            ),
+            (
+                DeviceField::Description,
+                self.description == saved.description,
+            ),
+            (DeviceField::Links, self.links == saved.links),
+            (DeviceField::Pin, self.pin == saved.pin),
+            (DeviceField::Title, self.title == saved.title),
+        ];
+        checks
+            .iter()
+            .all(|(field, equal)| *equal || ignored.contains(field))
+    }
+
+    /// Parse an existing [full device description][FullDeviceDescription],
+    /// e.g. one received from another WoT source.
+    ///
+    /// This is the inverse of building a device. The contained affordances are parsed using
+    /// [PropertyDescription::from_full][crate::PropertyDescription::from_full],
+    /// [ActionDescription::from_full][crate::ActionDescription::from_full] and
+    /// [EventDescription::from_full][crate::EventDescription::from_full].
+    /// Unknown `@type`s are kept as [AtType::Other].
+    pub fn from_full(
+        description: FullDeviceDescription,
+    ) -> Result<ParsedDeviceDescription, WebthingsError> {
+        let properties = match description.properties {
+            Some(properties) => Some(
+                properties
+                    .into_iter()
+                    .map(|(name, property)| Ok((name, PropertyDescription::from_full(property)?)))
+                    .collect::<Result<BTreeMap<_, _>, WebthingsError>>()?,
+            ),
+            None => None,
+        };
+        let actions = match description.actions {
+            Some(actions) => Some(
+                actions
+                    .into_iter()
+                    .map(|(name, action)| Ok((name, ActionDescription::from_full(action)?)))
+                    .collect::<Result<BTreeMap<_, _>, WebthingsError>>()?,
+            ),
+            None => None,
+        };
+        let events = match description.events {
+            Some(events) => Some(
+                events
+                    .into_iter()
+                    .map(|(name, event)| Ok((name, EventDescription::from_full(event)?)))
+                    .collect::<Result<BTreeMap<_, _>, WebthingsError>>()?,
+            ),
+            None => None,
+        };
+        let at_type = description.at_type.map(|at_types| {
+            at_types
+                .into_iter()
+                .map(|at_type| parse_at_type(at_type, AtType::Other))
+                .collect()
+        });
+        Ok(ParsedDeviceDescription {
+            id: description.id,
+            description: Self {
+                at_context: description.at_context,
+                at_type,
+                base_href: description.base_href,
+                credentials_required: description.credentials_required,
+                description: description.description,
+                links: description.links,
+                pin: description.pin,
+                title: description.title,
+                connected: true,
+            },
+            properties,
+            actions,
+            events,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        action::AtType as ActionAtType,
+        device::{AtType, DeviceDescription, DeviceField},
+        event::AtType as EventAtType,
+        property::AtType as PropertyAtType,
+        ActionDescription, EventDescription, PropertyDescription,
+    };
+    use serde_json::json;
+    use std::collections::BTreeMap;
+    use webthings_gateway_ipc_types::DeviceWithoutId;
+
+    fn saved(title: &str) -> DeviceWithoutId {
+        DeviceWithoutId {
+            at_context: None,
+            at_type: Some(vec!["Light".to_owned()]),
+            title: Some(title.to_owned()),
+            description: None,
+            properties: None,
+            actions: None,
+            events: None,
+            links: None,
+            base_href: None,
+            pin: None,
+            credentials_required: None,
+        }
+    }
+
+    #[test]
+    fn test_matches() {
+        let description = DeviceDescription::default()
+            .at_type(AtType::Light)
+            .title("Lamp");
+        assert!(description.matches(&saved("Lamp"), &[]));
+        assert!(!description.matches(&saved("My lamp"), &[]));
+        assert!(description.matches(&saved("My lamp"), &[DeviceField::Title]));
+        assert!(!description
+            .clone()
+            .at_type(AtType::OnOffSwitch)
+            .matches(&saved("Lamp"), &[DeviceField::Title]));
+    }
+
+    #[test]
+    fn test_from_full() {
+        let mut properties = BTreeMap::new();
+        properties.insert(
+            "level".to_owned(),
+            PropertyDescription::<u8>::default()
+                .at_type(PropertyAtType::LevelProperty)
+                .value(42)
+                .into_full_description("level".to_owned())
+                .unwrap(),
+        );
+        let mut actions = BTreeMap::new();
+        actions.insert(
+            "fade".to_owned(),
+            ActionDescription::<u8>::default()
+                .at_type(ActionAtType::FadeAction)
+                .into_full_description(),
+        );
+        let mut events = BTreeMap::new();
+        events.insert(
+            "overheated".to_owned(),
+            EventDescription::<f32>::default()
+                .at_type(EventAtType::OverheatedEvent)
+                .into_full_description("overheated".to_owned())
+                .unwrap(),
+        );
+        let full = DeviceDescription::default()
+            .at_context("https://webthings.io/schemas")
+            .at_types(vec![AtType::Light, AtType::OnOffSwitch])
+            .title("Foo")
+            .credentials_required(true)
+            .into_full_description("foo".to_owned(), properties, actions, events);
+        let parsed = DeviceDescription::from_full(full.clone()).unwrap();
+        assert_eq!(parsed.id, "foo");
+        assert_eq!(
+            parsed.properties.as_ref().unwrap()["level"].value,
+            json!(42)
+        );
+        assert_eq!(parsed.into_full_description().unwrap(), full);
+    }
+
+    #[test]
+    fn test_from_full_without_affordances() {
+        let mut full = DeviceDescription::default().into_full_description(
+            "foo".to_owned(),
+            BTreeMap::new(),
+            BTreeMap::new(),
+            BTreeMap::new(),
+        );
+        full.properties = None;
+        full.actions = None;
+        full.events = None;
+        let parsed = DeviceDescription::from_full(full.clone()).unwrap();
+        assert_eq!(parsed.into_full_description().unwrap(), full);
+    }
+
+    #[test]
+    fn test_from_full_unknown_at_type() {
+        let mut properties = BTreeMap::new();
+        let mut property = PropertyDescription::<u8>::default()
+            .into_full_description("level".to_owned())
+            .unwrap();
+        property.at_type = Some("FooProperty".to_owned());
+        properties.insert("level".to_owned(), property);
+        let mut full = DeviceDescription::default().into_full_description(
+            "foo".to_owned(),
+            properties,
+            BTreeMap::new(),
+            BTreeMap::new(),
+        );
+        full.at_type = Some(vec!["Light".to_owned(), "FooDevice".to_owned()]);
+        let parsed = DeviceDescription::from_full(full.clone()).unwrap();
+        assert_eq!(
+            parsed.description.at_type,
+            Some(vec![AtType::Light, AtType::Other("FooDevice".to_owned())])
+        );
+        assert_eq!(parsed.into_full_description().unwrap(), full);
+    }
+
+    #[test]
+    fn test_single_context() {
+        let description = DeviceDescription::default().contexts(vec!["foo".to_owned()]);
+        assert_eq!(description.at_context, Some("foo".to_owned()));
+    }
+
+    #[test]
+    fn test_multiple_contexts() {
+        let description =
+            DeviceDescription::default().contexts(vec!["foo".to_owned(), "bar".to_owned()]);
+        assert_eq!(description.at_context, Some(r#"["foo","bar"]"#.to_owned()));
+    }
+
+    #[test]
+    fn test_no_contexts() {
+        let description = DeviceDescription::default()
+            .at_context("foo")
+            .contexts(vec![]);
+        assert_eq!(description.at_context, None);
+    }
 }
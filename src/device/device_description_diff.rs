@@ -0,0 +1,203 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+
+use std::collections::BTreeMap;
+use webthings_gateway_ipc_types::{
+    Device as FullDeviceDescription, Property as FullPropertyDescription,
+};
+
+/// Names of the affordances of one kind which differ between two device descriptions.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AffordanceDiff {
+    /// Affordances which only exist in the new description.
+    pub added: Vec<String>,
+    /// Affordances which only exist in the old description.
+    pub removed: Vec<String>,
+    /// Affordances which exist in both descriptions but are described differently.
+    pub changed: Vec<String>,
+}
+
+impl AffordanceDiff {
+    fn new<V>(
+        old: &Option<BTreeMap<String, V>>,
+        new: &Option<BTreeMap<String, V>>,
+        eq: impl Fn(&V, &V) -> bool,
+    ) -> Self {
+        let empty = BTreeMap::new();
+        let old = old.as_ref().unwrap_or(&empty);
+        let new = new.as_ref().unwrap_or(&empty);
+
+        let mut diff = Self::default();
+        for (name, new_description) in new {
+            match old.get(name) {
+                Some(old_description) if eq(old_description, new_description) => {}
+                Some(_) => diff.changed.push(name.clone()),
+                None => diff.added.push(name.clone()),
+            }
+        }
+        for name in old.keys() {
+            if !new.contains_key(name) {
+                diff.removed.push(name.clone());
+            }
+        }
+        diff
+    }
+
+    /// Whether the affordances are described identically.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// The difference between two full descriptions of the same device.
+///
+/// Property values are ignored, only the structure of the device is compared.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeviceDescriptionDiff {
+    /// Whether the device level fields like `title` or `@type` changed.
+    pub device: bool,
+    pub properties: AffordanceDiff,
+    pub actions: AffordanceDiff,
+    pub events: AffordanceDiff,
+}
+
+impl DeviceDescriptionDiff {
+    /// Compare two full device descriptions.
+    pub fn new(old: &FullDeviceDescription, new: &FullDeviceDescription) -> Self {
+        Self {
+            device: without_affordances(old) != without_affordances(new),
+            properties: AffordanceDiff::new(&old.properties, &new.properties, |old, new| {
+                without_value(old) == without_value(new)
+            }),
+            actions: AffordanceDiff::new(&old.actions, &new.actions, PartialEq::eq),
+            events: AffordanceDiff::new(&old.events, &new.events, PartialEq::eq),
+        }
+    }
+
+    /// Whether the descriptions are identical apart from property values.
+    pub fn is_empty(&self) -> bool {
+        !self.device
+            && self.properties.is_empty()
+            && self.actions.is_empty()
+            && self.events.is_empty()
+    }
+
+    /// Whether only the descriptions of existing properties changed.
+    ///
+    /// Such changes can be advertised one property at a time instead of advertising the whole device again.
+    pub fn properties_only(&self) -> bool {
+        !self.device
+            && self.properties.added.is_empty()
+            && self.properties.removed.is_empty()
+            && self.actions.is_empty()
+            && self.events.is_empty()
+    }
+}
+
+fn without_affordances(description: &FullDeviceDescription) -> FullDeviceDescription {
+    FullDeviceDescription {
+        properties: None,
+        actions: None,
+        events: None,
+        ..description.clone()
+    }
+}
+
+fn without_value(description: &FullPropertyDescription) -> FullPropertyDescription {
+    FullPropertyDescription {
+        value: None,
+        ..description.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        device::{AffordanceDiff, DeviceDescriptionDiff},
+        ActionDescription, DeviceDescription, EventDescription, PropertyDescription,
+    };
+    use std::collections::BTreeMap;
+    use webthings_gateway_ipc_types::Device as FullDeviceDescription;
+
+    fn description(title: &str, property_title: &str, value: i32) -> FullDeviceDescription {
+        let mut properties = BTreeMap::new();
+        properties.insert(
+            "property".to_owned(),
+            PropertyDescription::<i32>::default()
+                .title(property_title)
+                .value(value)
+                .into_full_description("property".to_owned())
+                .unwrap(),
+        );
+        let mut actions = BTreeMap::new();
+        actions.insert(
+            "action".to_owned(),
+            ActionDescription::<serde_json::Value>::default().into_full_description(),
+        );
+        let mut events = BTreeMap::new();
+        events.insert(
+            "event".to_owned(),
+            EventDescription::<serde_json::Value>::default()
+                .into_full_description("event".to_owned())
+                .unwrap(),
+        );
+        DeviceDescription::default()
+            .title(title)
+            .into_full_description("device".to_owned(), properties, actions, events)
+    }
+
+    #[test]
+    fn test_diff_unchanged() {
+        let old = description("Device", "Property", 1);
+        let new = description("Device", "Property", 2);
+        let diff = DeviceDescriptionDiff::new(&old, &new);
+        assert!(diff.is_empty());
+        assert!(diff.properties_only());
+    }
+
+    #[test]
+    fn test_diff_property_changed() {
+        let old = description("Device", "Property", 1);
+        let new = description("Device", "Changed", 1);
+        let diff = DeviceDescriptionDiff::new(&old, &new);
+        assert!(!diff.is_empty());
+        assert!(diff.properties_only());
+        assert_eq!(diff.properties.changed, vec!["property".to_owned()]);
+    }
+
+    #[test]
+    fn test_diff_device_changed() {
+        let old = description("Device", "Property", 1);
+        let new = description("Changed", "Property", 1);
+        let diff = DeviceDescriptionDiff::new(&old, &new);
+        assert!(diff.device);
+        assert!(!diff.properties_only());
+    }
+
+    #[test]
+    fn test_diff_added_removed() {
+        let old = description("Device", "Property", 1);
+        let mut new = old.clone();
+        new.properties.as_mut().unwrap().remove("property");
+        new.actions = None;
+        let event = old.events.as_ref().unwrap()["event"].clone();
+        new.events
+            .as_mut()
+            .unwrap()
+            .insert("other".to_owned(), event);
+        let diff = DeviceDescriptionDiff::new(&old, &new);
+        assert_eq!(diff.properties.removed, vec!["property".to_owned()]);
+        assert_eq!(diff.actions.removed, vec!["action".to_owned()]);
+        assert_eq!(
+            diff.events,
+            AffordanceDiff {
+                added: vec!["other".to_owned()],
+                ..AffordanceDiff::default()
+            }
+        );
+        assert!(!diff.properties_only());
+    }
+}
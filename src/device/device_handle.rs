@@ -6,23 +6,44 @@
 
 use crate::{
     action::ActionBase,
-    client::Client,
-    error::WebthingsError,
-    event::{EventBase, EventBuilderBase},
-    property::{PropertyBase, PropertyBuilderBase},
-    ActionHandle, Adapter, Device, DeviceDescription,
+    client::{self, Client},
+    device::{DeviceUpdate, UpdateOperation},
+    error::{HandleError, WebthingsError},
+    event::{Data, EventBase, EventBuilderBase},
+    plugin::default_log_prefix,
+    property::{MissedPollBehavior, PropertyBase, PropertyBuilderBase, ValueSnapshot},
+    runtime::spawn,
+    ActionHandle, Adapter, Device, DeviceDescription, EventHandle, PropertyHandle,
 };
 
 use std::{
     collections::HashMap,
-    sync::{Arc, Weak},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Weak,
+    },
+    time::{Duration, Instant, SystemTime},
 };
-use tokio::sync::Mutex;
-use webthings_gateway_ipc_types::{DeviceConnectedStateNotificationMessageData, Message};
+use tokio::{
+    runtime::Handle,
+    sync::Mutex,
+    task::{yield_now, JoinHandle},
+    time::{interval_at, sleep, sleep_until, MissedTickBehavior},
+};
+use webthings_gateway_ipc_types::{
+    Device as FullDeviceDescription, DeviceConnectedStateNotificationMessageData, Message,
+};
+
+static LOCAL_ACTION_ID: AtomicU64 = AtomicU64::new(0);
+
+#[cfg(feature = "cancellation")]
+const CANCELLATION_POLL_INTERVAL: Duration = Duration::from_millis(10);
 
 /// A struct which represents an instance of a WoT device.
 ///
 /// Use it to notify the gateway.
+///
+/// Prefer the id getters over the public id fields, which are going to become private.
 #[derive(Clone)]
 pub struct DeviceHandle {
     pub(crate) client: Arc<Mutex<Client>>,
@@ -33,10 +54,63 @@ pub struct DeviceHandle {
     pub adapter_id: String,
     pub device_id: String,
     pub description: DeviceDescription,
-    pub connected: bool,
+    connected: bool,
+    pub(crate) connected_hook_running: bool,
     properties: HashMap<String, Arc<Mutex<Box<dyn PropertyBase>>>>,
     actions: HashMap<String, Arc<Mutex<Box<dyn ActionBase>>>>,
     events: HashMap<String, Arc<Mutex<Box<dyn EventBase>>>>,
+    poll_tasks: Vec<Arc<JoinHandle<()>>>,
+    pub(crate) runtime: Option<Handle>,
+    pub(crate) log_prefix: String,
+    /// The full description which was last sent to the gateway.
+    pub(crate) advertised: Option<FullDeviceDescription>,
+    saved_version: u64,
+    last_saved: Option<SystemTime>,
+    created: SystemTime,
+    liveness: Arc<std::sync::Mutex<Liveness>>,
+    liveness_task: Option<Arc<JoinHandle<()>>>,
+    #[cfg(feature = "cancellation")]
+    action_tokens: Arc<std::sync::Mutex<ActionTokens>>,
+}
+
+/// State of the liveness watchdog of a [device][DeviceHandle].
+///
+/// See [DeviceHandle::set_liveness_timeout].
+#[derive(Default)]
+struct Liveness {
+    timeout: Option<Duration>,
+    debounce: Duration,
+    last_heartbeat: Option<Instant>,
+    last_change: Option<Instant>,
+    /// Whether the watchdog disconnected the device, as opposed to [DeviceHandle::set_connected].
+    disconnected: bool,
+}
+
+impl Liveness {
+    /// Whether a connected state change is allowed, i.e. the last one is at least `debounce` ago.
+    fn settled(&self, now: Instant) -> bool {
+        !matches!(self.settled_at(), Some(settled_at) if now < settled_at)
+    }
+
+    /// When the next connected state change is allowed.
+    fn settled_at(&self) -> Option<Instant> {
+        self.last_change
+            .map(|last_change| last_change + self.debounce)
+    }
+}
+
+/// Running action instances by action id.
+///
+/// Entries are dropped once every clone of the respective [ActionHandle] is gone.
+#[cfg(feature = "cancellation")]
+type ActionTokens = HashMap<String, TrackedAction>;
+
+/// A running action instance.
+#[cfg(feature = "cancellation")]
+struct TrackedAction {
+    /// A copy of the handle, which does not keep the instance alive.
+    action_handle: ActionHandle<serde_json::Value>,
+    alive: Weak<()>,
 }
 
 impl DeviceHandle {
@@ -52,17 +126,56 @@ impl DeviceHandle {
             client,
             weak: Weak::new(),
             adapter,
+            log_prefix: default_log_prefix(&plugin_id),
             plugin_id,
             adapter_id,
+            connected: description.connected,
             description,
             device_id,
-            connected: true,
+            connected_hook_running: false,
             properties: HashMap::new(),
             actions: HashMap::new(),
             events: HashMap::new(),
+            poll_tasks: Vec::new(),
+            runtime: None,
+            advertised: None,
+            saved_version: 0,
+            last_saved: None,
+            created: SystemTime::now(),
+            liveness: Arc::new(std::sync::Mutex::new(Liveness::default())),
+            liveness_task: None,
+            #[cfg(feature = "cancellation")]
+            action_tokens: Arc::new(std::sync::Mutex::new(HashMap::new())),
         }
     }
 
+    /// ID of the plugin this device belongs to.
+    pub fn plugin_id(&self) -> &str {
+        &self.plugin_id
+    }
+
+    /// ID of the adapter this device belongs to.
+    pub fn adapter_id(&self) -> &str {
+        &self.adapter_id
+    }
+
+    /// ID of this device.
+    pub fn device_id(&self) -> &str {
+        &self.device_id
+    }
+
+    /// When this device was registered, which is kept across reconnects and [resyncs][DeviceHandle::resync].
+    pub fn created(&self) -> SystemTime {
+        self.created
+    }
+
+    /// A weak reference to the [device][crate::Device] which owns this handle.
+    ///
+    /// Only valid once the device was added, e.g. in [Device::post_init][crate::Device::post_init].
+    pub fn device(&self) -> Weak<Mutex<Box<dyn Device>>> {
+        self.weak.clone()
+    }
+
     pub(crate) async fn add_property(&mut self, property_builder: Box<dyn PropertyBuilderBase>) {
         let name = property_builder.name();
 
@@ -75,9 +188,42 @@ impl DeviceHandle {
         )));
 
         self.properties.insert(name, property.clone());
+
         property.lock().await.post_init();
     }
 
+    /// Start the poll tasks of the [properties][crate::Property] which have a
+    /// [poll interval][crate::Property::poll_interval].
+    ///
+    /// Called once the device was advertised, the first poll happens one interval later.
+    pub(crate) async fn start_polling(&mut self) {
+        if !self.poll_tasks.is_empty() {
+            return;
+        }
+        for (name, property) in &self.properties {
+            let property_lock = property.lock().await;
+            if let Some(poll_interval) = property_lock.poll_interval() {
+                self.poll_tasks.push(Arc::new(spawn_poll_task(
+                    self.runtime.as_ref(),
+                    self.log_prefix.clone(),
+                    name.clone(),
+                    Arc::downgrade(property),
+                    poll_interval,
+                    property_lock.missed_poll_behavior(),
+                )));
+            }
+        }
+    }
+
+    /// Stop all poll tasks of the [properties][crate::Property] which this device owns
+    /// and the [liveness watchdog][DeviceHandle::set_liveness_timeout].
+    pub(crate) fn stop_polling(&mut self) {
+        for poll_task in self.poll_tasks.drain(..) {
+            poll_task.abort();
+        }
+        self.stop_liveness();
+    }
+
     /// Get a reference to all the [properties][crate::Property] which this device owns.
     pub fn properties(&self) -> &HashMap<String, Arc<Mutex<Box<dyn PropertyBase>>>> {
         &self.properties
@@ -91,6 +237,16 @@ impl DeviceHandle {
         self.properties.get(&name.into()).cloned()
     }
 
+    /// Get a [property][crate::property::Property] which this device owns by ID, ignoring ASCII case.
+    ///
+    /// Returns `None` if several properties match, i.e. if their IDs only differ by case.
+    pub fn get_property_ci(
+        &self,
+        name: impl AsRef<str>,
+    ) -> Option<Arc<Mutex<Box<dyn PropertyBase>>>> {
+        get_ignore_ascii_case(&self.properties, name.as_ref())
+    }
+
     /// Helper method for setting the value of a [property][crate::Property] which this device owns by ID.
     ///
     /// Make sure that the type of the provided value is compatible with the respective property.
@@ -109,6 +265,26 @@ impl DeviceHandle {
         }
     }
 
+    /// Helper method for toggling a boolean [property][crate::Property] which this device owns by ID.
+    ///
+    /// Returns the new value. Fails with [WebthingsError::PropertyTypeMismatch] if the property is not boolean.
+    pub async fn toggle_property(&self, name: impl Into<String>) -> Result<bool, WebthingsError> {
+        let name = name.into();
+        let property = self
+            .properties
+            .get(&name)
+            .ok_or_else(|| WebthingsError::UnknownProperty(name.clone()))?;
+        let mut property = property.lock().await;
+        let property_handle = property
+            .property_handle_mut()
+            .as_any_mut()
+            .downcast_mut::<PropertyHandle<bool>>()
+            .ok_or_else(|| WebthingsError::PropertyTypeMismatch(name, "boolean"))?;
+        let value = !property_handle.description.value;
+        property_handle.set_value(value).await?;
+        Ok(value)
+    }
+
     pub(crate) async fn add_action(&mut self, action: Box<dyn ActionBase>) {
         let name = action.name();
 
@@ -128,17 +304,21 @@ impl DeviceHandle {
         self.actions.get(&name.into()).cloned()
     }
 
+    /// Get an [action][crate::Action] which this device owns by ID, ignoring ASCII case.
+    ///
+    /// Returns `None` if several actions match, i.e. if their IDs only differ by case.
+    pub fn get_action_ci(&self, name: impl AsRef<str>) -> Option<Arc<Mutex<Box<dyn ActionBase>>>> {
+        get_ignore_ascii_case(&self.actions, name.as_ref())
+    }
+
     pub(crate) async fn request_action(
         &self,
         action_name: String,
         action_id: String,
         input: serde_json::Value,
-    ) -> Result<(), String> {
+    ) -> Result<(), HandleError> {
         let action = self.get_action(&action_name).ok_or_else(|| {
-            format!(
-                "Failed to request action {} of {}: not found",
-                action_name, self.device_id,
-            )
+            HandleError::UnknownTarget(WebthingsError::UnknownAction(action_name.clone()))
         })?;
         let mut action = action.lock().await;
         let action_handle = ActionHandle::new(
@@ -152,22 +332,162 @@ impl DeviceHandle {
             input.clone(),
             input,
         );
-        action.check_and_perform(action_handle).await
+        #[cfg(feature = "cancellation")]
+        if let Ok(mut action_tokens) = self.action_tokens.lock() {
+            action_tokens.retain(|_, tracked| tracked.alive.strong_count() > 0);
+            let mut tracked_handle = action_handle.clone();
+            tracked_handle.alive = Arc::new(());
+            action_tokens.insert(
+                action_handle.id.clone(),
+                TrackedAction {
+                    action_handle: tracked_handle,
+                    alive: Arc::downgrade(&action_handle.alive),
+                },
+            );
+        }
+        action
+            .check_and_perform(action_handle)
+            .await
+            .map_err(|err| {
+                HandleError::Callback(
+                    format!(
+                        "Failed to request action {} for device {}",
+                        action_name, self.device_id
+                    ),
+                    err,
+                )
+            })
+    }
+
+    /// Invoke an [action][crate::Action] which this device owns from within the addon.
+    ///
+    /// The action runs exactly as if the gateway had requested it, but with a locally generated action id.
+    /// Status notifications sent through its [ActionHandle] are still forwarded to the gateway.
+    pub async fn invoke_action(
+        &self,
+        name: impl Into<String>,
+        input: serde_json::Value,
+    ) -> Result<(), WebthingsError> {
+        let name = name.into();
+        let action_id = format!(
+            "{}-local-{}",
+            self.plugin_id,
+            LOCAL_ACTION_ID.fetch_add(1, Ordering::Relaxed)
+        );
+        self.request_action(name.clone(), action_id, input)
+            .await
+            .map_err(|err| match err {
+                HandleError::UnknownTarget(err) => err,
+                HandleError::Callback(_, err) => WebthingsError::ActionFailed(name, err),
+                err => WebthingsError::ActionFailed(name, err.to_string()),
+            })
     }
 
     pub(crate) async fn remove_action(
         &self,
         action_name: String,
         action_id: String,
-    ) -> Result<(), String> {
+    ) -> Result<(), HandleError> {
+        #[cfg(feature = "cancellation")]
+        if let Ok(mut action_tokens) = self.action_tokens.lock() {
+            if let Some(tracked) = action_tokens.remove(&action_id) {
+                tracked.action_handle.cancellation_token.cancel();
+            }
+        }
         let action = self.get_action(&action_name).ok_or_else(|| {
-            format!(
-                "Failed to remove action {} ({}) of {}: not found",
-                action_name, action_id, self.device_id,
-            )
+            HandleError::UnknownTarget(WebthingsError::UnknownAction(action_name.clone()))
         })?;
         let mut action = action.lock().await;
-        action.cancel(action_id).await
+        action.cancel(action_id.clone()).await.map_err(|err| {
+            HandleError::Callback(
+                format!(
+                    "Failed to remove action {} ({}) for device {}",
+                    action_name, action_id, self.device_id
+                ),
+                err,
+            )
+        })
+    }
+
+    /// Get copies of the [handles][ActionHandle] of all running [action][crate::Action] instances
+    /// of this device, ordered by the time they were requested.
+    ///
+    /// The copies do not keep the instances running.
+    #[cfg(feature = "cancellation")]
+    pub fn running_actions(&self) -> Vec<ActionHandle<serde_json::Value>> {
+        let mut running_actions: Vec<ActionHandle<serde_json::Value>> =
+            match self.action_tokens.lock() {
+                Ok(action_tokens) => action_tokens
+                    .values()
+                    .filter(|tracked| tracked.alive.strong_count() > 0)
+                    .map(|tracked| tracked.action_handle.clone())
+                    .collect(),
+                Err(_) => Vec::new(),
+            };
+        running_actions.sort_by(|a, b| (a.time_requested, &a.id).cmp(&(b.time_requested, &b.id)));
+        running_actions
+    }
+
+    /// Cancel all running instances of the [actions][crate::Action] which this device owns,
+    /// e.g. when the device goes offline or is removed.
+    ///
+    /// Every instance is cancelled like on a removal through the gateway. Instances which did not
+    /// report their completion or failure within `grace_period` are reported to the gateway as failed.
+    /// Instances whose [ActionHandle] is still alive by then are also logged as ignoring the cancellation.
+    #[cfg(feature = "cancellation")]
+    pub async fn cancel_all_actions(&self, grace_period: Duration) -> Result<(), WebthingsError> {
+        let tracked: Vec<TrackedAction> = match self.action_tokens.lock() {
+            Ok(mut action_tokens) => action_tokens
+                .drain()
+                .map(|(_, tracked)| tracked)
+                .filter(|tracked| tracked.alive.strong_count() > 0)
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+
+        for TrackedAction { action_handle, .. } in &tracked {
+            action_handle.cancellation_token.cancel();
+            if let Some(action) = self.get_action(&action_handle.name) {
+                if let Err(err) = action.lock().await.cancel(action_handle.id.clone()).await {
+                    log::warn!(
+                        "{}Failed to cancel action {} ({}) of device {}: {}",
+                        self.log_prefix,
+                        action_handle.name,
+                        action_handle.id,
+                        self.device_id,
+                        err
+                    );
+                }
+            }
+        }
+
+        let deadline = Instant::now() + grace_period;
+        while tracked.iter().any(|t| t.alive.strong_count() > 0) && Instant::now() < deadline {
+            sleep(CANCELLATION_POLL_INTERVAL.min(deadline - Instant::now())).await;
+        }
+
+        let mut result = Ok(());
+        for TrackedAction {
+            mut action_handle,
+            alive,
+        } in tracked
+        {
+            if alive.strong_count() > 0 {
+                log::warn!(
+                    "{}Action {} ({}) of device {} ignored the cancellation",
+                    self.log_prefix,
+                    action_handle.name,
+                    action_handle.id,
+                    self.device_id
+                );
+            }
+            if !action_handle.terminated.load(Ordering::Relaxed) {
+                if let Err(err) = action_handle.fail().await {
+                    result = Err(err);
+                }
+            }
+        }
+        result
     }
 
     pub(crate) async fn add_event(&mut self, event_builder: Box<dyn EventBuilderBase>) {
@@ -196,6 +516,28 @@ impl DeviceHandle {
         self.events.get(&name.into()).cloned()
     }
 
+    /// Get an [event][crate::Event] which this device owns by ID, ignoring ASCII case.
+    ///
+    /// Returns `None` if several events match, i.e. if their IDs only differ by case.
+    pub fn get_event_ci(&self, name: impl AsRef<str>) -> Option<Arc<Mutex<Box<dyn EventBase>>>> {
+        get_ignore_ascii_case(&self.events, name.as_ref())
+    }
+
+    /// Get a clone of the typed [handle][EventHandle] of an [event][crate::Event] which this device owns by ID.
+    ///
+    /// The handle can be stored and used to [raise][EventHandle::raise] the event from anywhere,
+    /// e.g. from an [API handler][crate::api_handler::ApiHandler].
+    /// Returns [None] if there is no such event or its data is not of type `D`.
+    pub async fn event_handle<D: Data>(&self, name: impl Into<String>) -> Option<EventHandle<D>> {
+        let event = self.events.get(&name.into())?;
+        let event = event.lock().await;
+        event
+            .event_handle()
+            .as_any()
+            .downcast_ref::<EventHandle<D>>()
+            .cloned()
+    }
+
     /// Helper method for raising an [event][crate::event::Event] which this device owns by ID.
     ///
     /// Make sure that the type of the provided data is compatible with the respective event.
@@ -214,35 +556,411 @@ impl DeviceHandle {
         }
     }
 
+    /// Helper method for raising an [event][crate::event::Event] without data which this device owns by ID.
+    pub async fn raise_event_empty(&self, name: impl Into<String>) -> Result<(), WebthingsError> {
+        self.raise_event(name, None).await
+    }
+
+    /// Apply several operations as one logical change.
+    ///
+    /// All notifications are prepared first and then enqueued in order while holding the client lock once,
+    /// so they are not interleaved with traffic of other devices.
+    ///
+    /// Fails with [WebthingsError::UpdateFailed] which contains the index of the failed operation
+    /// and whether earlier operations were already sent.
+    /// If preparing an operation fails, nothing is sent and all property values are restored.
+    /// If sending fails, the values of the properties whose notification was not sent are restored.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use gateway_addon_rust::DeviceHandle;
+    /// # use serde_json::json;
+    /// # async fn example(device_handle: DeviceHandle) {
+    /// device_handle
+    ///     .update(|tx| {
+    ///         tx.set("temperature", Some(json!(21.5)));
+    ///         tx.set("humidity", Some(json!(40)));
+    ///         tx.event("measured", None);
+    ///     })
+    ///     .await
+    ///     .unwrap();
+    /// # }
+    /// ```
+    pub async fn update(&self, f: impl FnOnce(&mut DeviceUpdate)) -> Result<(), WebthingsError> {
+        let mut update = DeviceUpdate::default();
+        f(&mut update);
+
+        let mut prepared = Vec::new();
+        for (index, operation) in update.operations().iter().enumerate() {
+            match self.update_message(operation).await {
+                Ok((message, snapshot)) => prepared.push((index, message, snapshot)),
+                Err(err) => {
+                    restore_snapshots(prepared.into_iter().map(|(_, _, snapshot)| snapshot)).await;
+                    return Err(WebthingsError::UpdateFailed(index, false, Box::new(err)));
+                }
+            }
+        }
+
+        let (messages, pending): (Vec<_>, Vec<_>) = prepared
+            .into_iter()
+            .filter_map(|(index, message, snapshot)| message.map(|m| (m, (index, snapshot))))
+            .unzip();
+        let results = client::send_each(&self.client, &messages).await;
+
+        let mut sent = false;
+        let mut failure = None;
+        let mut unsent = Vec::new();
+        for ((index, snapshot), result) in pending.into_iter().zip(results) {
+            match result {
+                Ok(()) if failure.is_none() => sent = true,
+                Ok(()) => {}
+                Err(err) => {
+                    unsent.push(snapshot);
+                    failure.get_or_insert((index, sent, err));
+                }
+            }
+        }
+        restore_snapshots(unsent.into_iter()).await;
+        match failure {
+            Some((index, sent, err)) => {
+                Err(WebthingsError::UpdateFailed(index, sent, Box::new(err)))
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Prepare the notification of an operation.
+    ///
+    /// Returns the property together with a snapshot of its previous value if the operation sets a value.
+    async fn update_message(
+        &self,
+        operation: &UpdateOperation,
+    ) -> Result<(Option<Message>, Option<PropertySnapshot>), WebthingsError> {
+        match operation {
+            UpdateOperation::SetProperty(name, value) => {
+                let property = self
+                    .properties
+                    .get(name)
+                    .ok_or_else(|| WebthingsError::UnknownProperty(name.clone()))?;
+                let mut property_lock = property.lock().await;
+                let property_handle = property_lock.property_handle_mut();
+                let (message, snapshot) = property_handle.update_value(value.clone())?;
+                Ok((message, Some((property.clone(), snapshot))))
+            }
+            UpdateOperation::RaiseEvent(name, data) => {
+                let event = self
+                    .events
+                    .get(name)
+                    .ok_or_else(|| WebthingsError::UnknownEvent(name.clone()))?;
+                let event = event.lock().await;
+                Ok((Some(event.event_handle().event_message(data.clone())), None))
+            }
+        }
+    }
+
+    /// How often the gateway saved this device, e.g. because the user edited it.
+    ///
+    /// The gateway does not provide a modification timestamp or version,
+    /// so this counter is maintained by the addon and starts at `0` on every start.
+    pub fn saved_version(&self) -> u64 {
+        self.saved_version
+    }
+
+    /// When the gateway last saved this device since the addon started, if at all.
+    ///
+    /// See [saved_version][DeviceHandle::saved_version].
+    pub fn last_saved(&self) -> Option<SystemTime> {
+        self.last_saved
+    }
+
+    pub(crate) fn record_saved(&mut self) {
+        self.saved_version += 1;
+        self.last_saved = Some(SystemTime::now());
+    }
+
+    /// Get the connected state of this device.
+    pub fn connected(&self) -> bool {
+        self.connected
+    }
+
     /// Set the connected state of this device and notify the gateway.
+    ///
+    /// This does not call [Device::on_connected_changed][crate::Device::on_connected_changed],
+    /// use [Device::set_connected][crate::Device::set_connected] for that.
     pub async fn set_connected(&mut self, connected: bool) -> Result<(), WebthingsError> {
+        self.liveness.lock().unwrap().disconnected = false;
         self.connected = connected;
+        self.notify_connected().await
+    }
+
+    /// Mark this device as disconnected if [heartbeat][DeviceHandle::heartbeat] is not called
+    /// within the given timeout.
+    ///
+    /// The device is marked as connected again on the next heartbeat, unless it was disconnected
+    /// by [set_connected][DeviceHandle::set_connected] in the meantime. Both only update the state
+    /// and notify the gateway, like [set_connected][DeviceHandle::set_connected].
+    /// The watchdog starts once the device was added and stops when it is removed.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use gateway_addon_rust::prelude::*;
+    /// # use std::time::Duration;
+    /// # async fn example(device_handle: &mut DeviceHandle) {
+    /// device_handle.set_liveness_timeout(Duration::from_secs(60));
+    /// device_handle.set_liveness_debounce(Duration::from_secs(10));
+    /// // On every successful read from the hardware
+    /// device_handle.heartbeat().await.unwrap();
+    /// # }
+    /// ```
+    pub fn set_liveness_timeout(&mut self, timeout: Duration) {
+        {
+            let mut liveness = self.liveness.lock().unwrap();
+            liveness.timeout = Some(timeout);
+            liveness.last_heartbeat = Some(Instant::now());
+        }
+        self.stop_liveness();
+        self.start_liveness();
+    }
+
+    /// Stop the watchdog set by [set_liveness_timeout][DeviceHandle::set_liveness_timeout].
+    pub fn clear_liveness_timeout(&mut self) {
+        self.liveness.lock().unwrap().timeout = None;
+        self.stop_liveness();
+    }
+
+    /// Set the minimum time between two connected state changes caused by the
+    /// [liveness watchdog][DeviceHandle::set_liveness_timeout], to avoid flapping.
+    ///
+    /// Defaults to no debounce.
+    pub fn set_liveness_debounce(&mut self, debounce: Duration) {
+        self.liveness.lock().unwrap().debounce = debounce;
+    }
+
+    /// Record that the device is alive.
+    ///
+    /// Marks the device as connected again if the [liveness watchdog][DeviceHandle::set_liveness_timeout]
+    /// disconnected it, unless the last change is more recent than the
+    /// [debounce][DeviceHandle::set_liveness_debounce].
+    pub async fn heartbeat(&mut self) -> Result<(), WebthingsError> {
+        let now = Instant::now();
+        let reconnect = {
+            let mut liveness = self.liveness.lock().unwrap();
+            liveness.last_heartbeat = Some(now);
+            let reconnect = liveness.timeout.is_some()
+                && liveness.disconnected
+                && !self.connected
+                && liveness.settled(now);
+            if reconnect {
+                liveness.last_change = Some(now);
+            }
+            reconnect
+        };
+        if reconnect {
+            self.set_connected(true).await?;
+        }
+        Ok(())
+    }
+
+    /// Start the liveness watchdog if a timeout is set and the device was added.
+    pub(crate) fn start_liveness(&mut self) {
+        let timeout = match self.liveness.lock().unwrap().timeout {
+            Some(timeout) => timeout,
+            None => return,
+        };
+        if self.liveness_task.is_some() || self.weak.upgrade().is_none() {
+            return;
+        }
+        self.liveness_task = Some(Arc::new(spawn_liveness_task(
+            self.runtime.as_ref(),
+            self.weak.clone(),
+            self.liveness.clone(),
+            timeout,
+        )));
+    }
+
+    fn stop_liveness(&mut self) {
+        if let Some(liveness_task) = self.liveness_task.take() {
+            liveness_task.abort();
+        }
+    }
+
+    pub(crate) async fn notify_connected(&self) -> Result<(), WebthingsError> {
+        client::send_message(&self.client, &self.connected_message()).await
+    }
 
-        let message: Message = DeviceConnectedStateNotificationMessageData {
+    fn connected_message(&self) -> Message {
+        DeviceConnectedStateNotificationMessageData {
             plugin_id: self.plugin_id.clone(),
             adapter_id: self.adapter_id.clone(),
             device_id: self.device_id.clone(),
-            connected,
+            connected: self.connected,
+        }
+        .into()
+    }
+
+    /// Re-send the current value of every [property][crate::Property], the last instance of every
+    /// [retained][crate::event::EventDescription::retain_last] [event][crate::Event] and the connected state of this device.
+    /// [Write-only][crate::PropertyDescription::write_only] properties are skipped.
+    ///
+    /// Use this to bring the gateway up to date, e.g. after it restarted.
+    /// Yields to the runtime between properties, so resyncing large devices does not block other tasks.
+    /// The messages are sent in a row and flushed once at the end.
+    pub async fn resync(&self) -> Result<(), WebthingsError> {
+        let mut messages = Vec::new();
+        for property in self.properties.values() {
+            let property = property.lock().await;
+            if !property.property_handle().readable() {
+                continue;
+            }
+            messages.push(property.property_handle().value_message()?);
+            drop(property);
+            yield_now().await;
+        }
+        for event in self.events.values() {
+            if let Some(message) = event.lock().await.event_handle().last_message() {
+                messages.push(message);
+            }
+        }
+        messages.push(self.connected_message());
+        client::send_messages(&self.client, &messages).await
+    }
+}
+
+fn spawn_liveness_task(
+    runtime: Option<&Handle>,
+    device: Weak<Mutex<Box<dyn Device>>>,
+    liveness: Arc<std::sync::Mutex<Liveness>>,
+    timeout: Duration,
+) -> JoinHandle<()> {
+    spawn(runtime, async move {
+        loop {
+            let deadline = liveness
+                .lock()
+                .unwrap()
+                .last_heartbeat
+                .map_or_else(Instant::now, |last_heartbeat| last_heartbeat + timeout);
+            if Instant::now() < deadline {
+                sleep_until(deadline.into()).await;
+                continue;
+            }
+
+            let device = match device.upgrade() {
+                Some(device) => device,
+                None => break,
+            };
+            let mut device = device.lock().await;
+            let device_handle = device.device_handle_mut();
+            let (disconnect, blocked_until) = {
+                let mut liveness = liveness.lock().unwrap();
+                let now = Instant::now();
+                let expired = match liveness.last_heartbeat {
+                    Some(last_heartbeat) => now >= last_heartbeat + timeout,
+                    None => true,
+                };
+                let due = expired && device_handle.connected();
+                if !due {
+                    (false, None)
+                } else if liveness.settled(now) {
+                    liveness.last_change = Some(now);
+                    (true, None)
+                } else {
+                    (false, liveness.settled_at())
+                }
+            };
+            if disconnect {
+                if let Err(err) = device_handle.set_connected(false).await {
+                    log::warn!(
+                        "{}Could not disconnect device {}: {}",
+                        device_handle.log_prefix,
+                        device_handle.device_id(),
+                        err
+                    );
+                }
+                liveness.lock().unwrap().disconnected = true;
+            }
+            drop(device);
+            match blocked_until {
+                Some(settled_at) => sleep_until(settled_at.into()).await,
+                None => sleep(timeout).await,
+            }
         }
-        .into();
+    })
+}
+
+type PropertySnapshot = (Arc<Mutex<Box<dyn PropertyBase>>>, ValueSnapshot);
+
+/// Restore the values of a [batch update][DeviceHandle::update] which were not sent, latest first.
+///
+/// Values which were changed again in the meantime are kept.
+async fn restore_snapshots(snapshots: impl DoubleEndedIterator<Item = Option<PropertySnapshot>>) {
+    for (property, snapshot) in snapshots.rev().flatten() {
+        property
+            .lock()
+            .await
+            .property_handle_mut()
+            .restore(snapshot);
+    }
+}
 
-        self.client.lock().await.send_message(&message).await
+/// Look up the single entry whose key equals the given name ignoring ASCII case.
+fn get_ignore_ascii_case<T: Clone>(map: &HashMap<String, T>, name: &str) -> Option<T> {
+    let mut matches = map.iter().filter(|(key, _)| key.eq_ignore_ascii_case(name));
+    match (matches.next(), matches.next()) {
+        (Some((_, value)), None) => Some(value.clone()),
+        _ => None,
     }
 }
 
+fn spawn_poll_task(
+    runtime: Option<&Handle>,
+    log_prefix: String,
+    name: String,
+    property: Weak<Mutex<Box<dyn PropertyBase>>>,
+    poll_interval: Duration,
+    missed_poll_behavior: MissedPollBehavior,
+) -> JoinHandle<()> {
+    spawn(runtime, async move {
+        let mut interval = interval_at((Instant::now() + poll_interval).into(), poll_interval);
+        interval.set_missed_tick_behavior(match missed_poll_behavior {
+            MissedPollBehavior::Skip => MissedTickBehavior::Skip,
+            MissedPollBehavior::Queue => MissedTickBehavior::Burst,
+        });
+        loop {
+            interval.tick().await;
+            let property = match property.upgrade() {
+                Some(property) => property,
+                None => break,
+            };
+            let mut property = property.lock().await;
+            if let Err(err) = property.poll().await {
+                log::warn!("{}Could not poll property {}: {}", log_prefix, name, err);
+            }
+        }
+    })
+}
+
 #[cfg(test)]
 pub(crate) mod tests {
     use crate::{
         action::{tests::MockAction, NoInput},
+        adapter::tests::add_mock_device,
         client::Client,
+        device::tests::MockDevice,
+        error::WebthingsError,
         event::{tests::MockEvent, NoData},
-        property::tests::MockProperty,
-        DeviceDescription, DeviceHandle,
+        plugin::tests::{add_mock_adapter, plugin},
+        property::{tests::MockProperty, PropertyBase},
+        AdapterHandle, DeviceDescription, DeviceHandle, EventHandle, Plugin, PropertyHandle,
     };
+    use as_any::Downcast;
+    use mockall::Sequence;
     use rstest::{fixture, rstest};
     use serde_json::json;
-    use std::sync::{Arc, Weak};
-    use tokio::sync::Mutex;
+    use std::{
+        sync::{Arc, Weak},
+        time::{Duration, SystemTime},
+    };
+    use tokio::{sync::Mutex, time::sleep};
     use webthings_gateway_ipc_types::Message;
 
     const PLUGIN_ID: &str = "plugin_id";
@@ -303,11 +1021,77 @@ pub(crate) mod tests {
         assert!(device.get_event(EVENT_NAME).is_some())
     }
 
+    #[rstest]
+    #[tokio::test]
+    async fn test_event_handle(mut device: DeviceHandle) {
+        device
+            .add_event(Box::new(MockEvent::<i32>::new(EVENT_NAME.to_owned())))
+            .await;
+
+        device
+            .client
+            .lock()
+            .await
+            .expect_send_message()
+            .withf(|msg| match msg {
+                Message::DeviceEventNotification(msg) => {
+                    msg.data.event.name == EVENT_NAME && msg.data.event.data == Some(json!(42))
+                }
+                _ => false,
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let event_handle = device.event_handle::<i32>(EVENT_NAME).await.unwrap();
+        event_handle.raise(42).await.unwrap();
+
+        assert!(device.event_handle::<String>(EVENT_NAME).await.is_none());
+        assert!(device.event_handle::<i32>("unknown").await.is_none());
+    }
+
     #[rstest]
     fn test_get_unknown_event(device: DeviceHandle) {
         assert!(device.get_event(EVENT_NAME).is_none())
     }
 
+    #[rstest]
+    #[tokio::test]
+    async fn test_get_ci(mut device: DeviceHandle) {
+        device
+            .add_property(Box::new(MockProperty::<i32>::new(PROPERTY_NAME.to_owned())))
+            .await;
+        device
+            .add_action(Box::new(MockAction::<NoInput>::new(ACTION_NAME.to_owned())))
+            .await;
+        device
+            .add_event(Box::new(MockEvent::<NoData>::new(EVENT_NAME.to_owned())))
+            .await;
+
+        let property = device.get_property_ci("Property_Name").unwrap();
+        assert!(Arc::ptr_eq(
+            &property,
+            &device.get_property(PROPERTY_NAME).unwrap()
+        ));
+        assert!(device.get_action_ci("ACTION_NAME").is_some());
+        assert!(device.get_event_ci("Event_name").is_some());
+        assert!(device.get_property_ci("property").is_none());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_get_ci_ambiguous(mut device: DeviceHandle) {
+        device
+            .add_property(Box::new(MockProperty::<i32>::new("on".to_owned())))
+            .await;
+        device
+            .add_property(Box::new(MockProperty::<i32>::new("On".to_owned())))
+            .await;
+
+        assert!(device.get_property_ci("on").is_none());
+        assert!(device.get_property_ci("ON").is_none());
+        assert!(device.get_property("on").is_some());
+    }
+
     #[rstest]
     #[tokio::test]
     async fn test_set_property_value(mut device: DeviceHandle) {
@@ -342,9 +1126,11 @@ pub(crate) mod tests {
 
     #[rstest]
     #[tokio::test]
-    async fn test_raise_event(mut device: DeviceHandle) {
+    async fn test_toggle_property(mut device: DeviceHandle) {
         device
-            .add_event(Box::new(MockEvent::<NoData>::new(EVENT_NAME.to_owned())))
+            .add_property(Box::new(MockProperty::<bool>::new(
+                PROPERTY_NAME.to_owned(),
+            )))
             .await;
 
         device
@@ -352,55 +1138,634 @@ pub(crate) mod tests {
             .lock()
             .await
             .expect_send_message()
-            .times(1)
+            .withf(move |msg| matches!(msg, Message::DevicePropertyChangedNotification(_)))
+            .times(2)
             .returning(|_| Ok(()));
 
-        assert!(device.raise_event(EVENT_NAME, None).await.is_ok());
+        assert!(device.toggle_property(PROPERTY_NAME).await.unwrap());
+        assert!(!device.toggle_property(PROPERTY_NAME).await.unwrap());
     }
 
     #[rstest]
     #[tokio::test]
-    async fn test_raise_unknown_event(device: DeviceHandle) {
-        assert!(device.raise_event(EVENT_NAME, None).await.is_err());
+    async fn test_toggle_non_boolean_property(mut device: DeviceHandle) {
+        device
+            .add_property(Box::new(MockProperty::<i32>::new(PROPERTY_NAME.to_owned())))
+            .await;
+        assert!(matches!(
+            device.toggle_property(PROPERTY_NAME).await,
+            Err(WebthingsError::PropertyTypeMismatch(_, "boolean"))
+        ));
     }
 
     #[rstest]
-    #[case(true)]
-    #[case(false)]
     #[tokio::test]
-    async fn test_set_connected(mut device: DeviceHandle, #[case] connected: bool) {
+    async fn test_toggle_unknown_property(device: DeviceHandle) {
+        assert!(matches!(
+            device.toggle_property(PROPERTY_NAME).await,
+            Err(WebthingsError::UnknownProperty(_))
+        ));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_raise_event(mut device: DeviceHandle) {
+        device
+            .add_event(Box::new(MockEvent::<NoData>::new(EVENT_NAME.to_owned())))
+            .await;
+
         device
             .client
             .lock()
             .await
             .expect_send_message()
-            .withf(move |msg| match msg {
-                Message::DeviceConnectedStateNotification(msg) => {
-                    msg.data.plugin_id == PLUGIN_ID
-                        && msg.data.adapter_id == ADAPTER_ID
-                        && msg.data.device_id == DEVICE_ID
-                        && msg.data.connected == connected
-                }
-                _ => false,
-            })
             .times(1)
             .returning(|_| Ok(()));
 
-        assert!(device.set_connected(connected).await.is_ok());
-        assert_eq!(device.connected, connected);
+        assert!(device.raise_event(EVENT_NAME, None).await.is_ok());
     }
 
     #[rstest]
     #[tokio::test]
-    async fn test_event_post_init(mut device: DeviceHandle) {
-        let mut mock_event = MockEvent::<NoData>::new(EVENT_NAME.to_owned());
-        mock_event.expect_post_init = true;
-        mock_event.expect_post_init().times(1).returning(|| ());
-        device.add_event(Box::new(mock_event)).await;
-    }
+    async fn test_update(mut device: DeviceHandle) {
+        device
+            .add_property(Box::new(MockProperty::<i32>::new(PROPERTY_NAME.to_owned())))
+            .await;
+        device
+            .add_event(Box::new(MockEvent::<NoData>::new(EVENT_NAME.to_owned())))
+            .await;
 
-    #[rstest]
-    #[tokio::test]
+        let mut sequence = Sequence::new();
+        let mut client = device.client.lock().await;
+        client
+            .expect_send_message()
+            .withf(|msg| matches!(msg, Message::DevicePropertyChangedNotification(msg) if msg.data.property.value == Some(json!(42))))
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_| Ok(()));
+        client
+            .expect_send_message()
+            .withf(|msg| matches!(msg, Message::DeviceEventNotification(msg) if msg.data.event.name == EVENT_NAME))
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_| Ok(()));
+        drop(client);
+
+        device
+            .update(|tx| {
+                tx.set(PROPERTY_NAME, Some(json!(42)));
+                tx.event(EVENT_NAME, None);
+            })
+            .await
+            .unwrap();
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_update_unknown_event(mut device: DeviceHandle) {
+        device
+            .add_property(Box::new(MockProperty::<i32>::new(PROPERTY_NAME.to_owned())))
+            .await;
+
+        device.client.lock().await.expect_send_message().times(0);
+
+        let result = device
+            .update(|tx| {
+                tx.set(PROPERTY_NAME, Some(json!(42)));
+                tx.event(EVENT_NAME, None);
+            })
+            .await;
+        assert!(matches!(
+            result,
+            Err(WebthingsError::UpdateFailed(1, false, err)) if matches!(*err, WebthingsError::UnknownEvent(_))
+        ));
+        assert_eq!(property_value(&device).await, 0);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_update_send_failure(mut device: DeviceHandle) {
+        device
+            .add_property(Box::new(MockProperty::<i32>::new(PROPERTY_NAME.to_owned())))
+            .await;
+        device
+            .add_event(Box::new(MockEvent::<NoData>::new(EVENT_NAME.to_owned())))
+            .await;
+
+        let mut sequence = Sequence::new();
+        let mut client = device.client.lock().await;
+        client
+            .expect_send_message()
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_| Ok(()));
+        client
+            .expect_send_message()
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_| Err(WebthingsError::Send(tungstenite::Error::ConnectionClosed)));
+        drop(client);
+
+        let result = device
+            .update(|tx| {
+                tx.set(PROPERTY_NAME, Some(json!(42)));
+                tx.event(EVENT_NAME, None);
+            })
+            .await;
+        assert!(matches!(
+            result,
+            Err(WebthingsError::UpdateFailed(1, true, _))
+        ));
+    }
+
+    #[rstest]
+    #[case(0, 0)]
+    #[case(1, 42)]
+    #[tokio::test]
+    async fn test_update_send_failure_restores_values(
+        mut device: DeviceHandle,
+        #[case] failed_index: usize,
+        #[case] expected_value: i32,
+    ) {
+        device
+            .add_property(Box::new(MockProperty::<i32>::new(PROPERTY_NAME.to_owned())))
+            .await;
+
+        let mut sequence = Sequence::new();
+        let mut client = device.client.lock().await;
+        for index in 0..2 {
+            client
+                .expect_send_message()
+                .times(1)
+                .in_sequence(&mut sequence)
+                .returning(move |_| {
+                    if index >= failed_index {
+                        Err(WebthingsError::Send(tungstenite::Error::ConnectionClosed))
+                    } else {
+                        Ok(())
+                    }
+                });
+        }
+        drop(client);
+
+        let result = device
+            .update(|tx| {
+                tx.set(PROPERTY_NAME, Some(json!(42)));
+                tx.set(PROPERTY_NAME, Some(json!(43)));
+            })
+            .await;
+        assert!(matches!(
+            result,
+            Err(WebthingsError::UpdateFailed(index, _, _)) if index == failed_index
+        ));
+        assert_eq!(property_value(&device).await, expected_value);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_update_send_failure_keeps_interleaved_value(mut device: DeviceHandle) {
+        device
+            .add_property(Box::new(MockProperty::<i32>::new(PROPERTY_NAME.to_owned())))
+            .await;
+
+        let property = device.get_property(PROPERTY_NAME).unwrap();
+        device
+            .client
+            .lock()
+            .await
+            .expect_send_message()
+            .times(1)
+            .returning(move |_| {
+                // Another task sets the value while the notification of the batch is sent
+                property
+                    .try_lock()
+                    .unwrap()
+                    .property_handle_mut()
+                    .downcast_mut::<PropertyHandle<i32>>()
+                    .unwrap()
+                    .update_value(99)
+                    .unwrap();
+                Err(WebthingsError::Send(tungstenite::Error::ConnectionClosed))
+            });
+
+        let result = device
+            .update(|tx| {
+                tx.set(PROPERTY_NAME, Some(json!(42)));
+            })
+            .await;
+        assert!(matches!(
+            result,
+            Err(WebthingsError::UpdateFailed(0, false, _))
+        ));
+        assert_eq!(property_value(&device).await, 99);
+    }
+
+    async fn property_value(device: &DeviceHandle) -> i32 {
+        device
+            .get_property(PROPERTY_NAME)
+            .unwrap()
+            .lock()
+            .await
+            .property_handle()
+            .downcast_ref::<PropertyHandle<i32>>()
+            .unwrap()
+            .description
+            .value
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_raise_event_empty(mut device: DeviceHandle) {
+        device
+            .add_event(Box::new(MockEvent::<NoData>::new(EVENT_NAME.to_owned())))
+            .await;
+
+        device
+            .client
+            .lock()
+            .await
+            .expect_send_message()
+            .withf(move |msg| match msg {
+                Message::DeviceEventNotification(msg) => msg.data.event.data.is_none(),
+                _ => false,
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+
+        assert!(device.raise_event_empty(EVENT_NAME).await.is_ok());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_raise_unknown_event(device: DeviceHandle) {
+        assert!(device.raise_event(EVENT_NAME, None).await.is_err());
+    }
+
+    #[rstest]
+    #[case(true)]
+    #[case(false)]
+    #[tokio::test]
+    async fn test_set_connected(mut device: DeviceHandle, #[case] connected: bool) {
+        device
+            .client
+            .lock()
+            .await
+            .expect_send_message()
+            .withf(move |msg| match msg {
+                Message::DeviceConnectedStateNotification(msg) => {
+                    msg.data.plugin_id == PLUGIN_ID
+                        && msg.data.adapter_id == ADAPTER_ID
+                        && msg.data.device_id == DEVICE_ID
+                        && msg.data.connected == connected
+                }
+                _ => false,
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+
+        assert!(device.set_connected(connected).await.is_ok());
+        assert_eq!(device.connected(), connected);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_resync(mut device: DeviceHandle) {
+        device
+            .add_property(Box::new(MockProperty::<i32>::new("foo".to_owned())))
+            .await;
+        device
+            .add_property(Box::new(MockProperty::<bool>::new("bar".to_owned())))
+            .await;
+
+        let mut client = device.client.lock().await;
+        client
+            .expect_send_message_no_flush()
+            .withf(|msg| match msg {
+                Message::DevicePropertyChangedNotification(msg) => {
+                    msg.data.device_id == DEVICE_ID
+                        && (msg.data.property.name == Some("foo".to_owned())
+                            || msg.data.property.name == Some("bar".to_owned()))
+                }
+                _ => false,
+            })
+            .times(2)
+            .returning(|_| Ok(()));
+        client
+            .expect_send_message_no_flush()
+            .withf(|msg| match msg {
+                Message::DeviceConnectedStateNotification(msg) => {
+                    msg.data.device_id == DEVICE_ID && msg.data.connected
+                }
+                _ => false,
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+        client.expect_flush().times(1).returning(|| Ok(()));
+        client.expect_send_message().times(0);
+        drop(client);
+
+        device.resync().await.unwrap();
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_created_preserved_on_resync(mut device: DeviceHandle) {
+        device
+            .add_property(Box::new(MockProperty::<i32>::new(PROPERTY_NAME.to_owned())))
+            .await;
+        let property = device.get_property(PROPERTY_NAME).unwrap();
+        let property_created = |property: &dyn PropertyBase| {
+            property
+                .property_handle()
+                .downcast_ref::<PropertyHandle<i32>>()
+                .unwrap()
+                .created()
+        };
+
+        let created = device.created();
+        let property_created_before = property_created(property.lock().await.as_ref());
+        assert!(created <= SystemTime::now());
+
+        sleep(Duration::from_millis(10)).await;
+        {
+            let mut client = device.client.lock().await;
+            client.expect_send_message_no_flush().returning(|_| Ok(()));
+            client.expect_flush().returning(|| Ok(()));
+        }
+        device.resync().await.unwrap();
+
+        assert_eq!(device.created(), created);
+        assert_eq!(
+            property_created(property.lock().await.as_ref()),
+            property_created_before
+        );
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_resync_retained_event(mut device: DeviceHandle) {
+        device
+            .add_event(Box::new(MockEvent::<i32>::new(EVENT_NAME.to_owned())))
+            .await;
+        device
+            .add_event(Box::new(MockEvent::<i32>::new("other".to_owned())))
+            .await;
+
+        let event = device.get_event(EVENT_NAME).unwrap();
+        let mut event = event.lock().await;
+        let event_handle = event
+            .event_handle_mut()
+            .downcast_mut::<EventHandle<i32>>()
+            .unwrap();
+        event_handle.description.retain_last = true;
+
+        let mut client = device.client.lock().await;
+        client.expect_send_message().times(3).returning(|_| Ok(()));
+        drop(client);
+        event_handle.raise(1).await.unwrap();
+        event_handle.raise(2).await.unwrap();
+        let (timestamp, data) = event_handle.last().unwrap();
+        assert_eq!(data, 2);
+        drop(event);
+
+        device
+            .get_event("other")
+            .unwrap()
+            .lock()
+            .await
+            .event_handle()
+            .raise(Some(json!(3)))
+            .await
+            .unwrap();
+
+        let mut client = device.client.lock().await;
+        client
+            .expect_send_message_no_flush()
+            .withf(move |msg| match msg {
+                Message::DeviceEventNotification(msg) => {
+                    msg.data.event.name == EVENT_NAME
+                        && msg.data.event.data == Some(json!(2))
+                        && msg.data.event.timestamp == timestamp.to_rfc3339()
+                }
+                _ => false,
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+        client
+            .expect_send_message_no_flush()
+            .withf(|msg| matches!(msg, Message::DeviceConnectedStateNotification(_)))
+            .times(1)
+            .returning(|_| Ok(()));
+        client.expect_flush().times(1).returning(|| Ok(()));
+        drop(client);
+
+        device.resync().await.unwrap();
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_invoke_action(mut plugin: Plugin) {
+        let adapter = add_mock_adapter(&mut plugin, ADAPTER_ID).await;
+        let device = add_mock_device(adapter.lock().await.adapter_handle_mut(), DEVICE_ID).await;
+        let device = device.lock().await;
+        let device = device.device_handle();
+
+        let handles = Arc::new(std::sync::Mutex::new(Vec::new()));
+        {
+            let handles = handles.clone();
+            let action = device.get_action(MockDevice::ACTION_NOINPUT).unwrap();
+            let mut action = action.lock().await;
+            let action = action
+                .as_any_mut()
+                .downcast_mut::<MockAction<NoInput>>()
+                .unwrap();
+            action
+                .action_helper
+                .expect_perform()
+                .times(2)
+                .returning(move |action_handle| {
+                    handles.lock().unwrap().push(action_handle);
+                    Ok(())
+                });
+        }
+
+        device
+            .invoke_action(MockDevice::ACTION_NOINPUT, json!(null))
+            .await
+            .unwrap();
+        device
+            .invoke_action(MockDevice::ACTION_NOINPUT, json!(null))
+            .await
+            .unwrap();
+
+        let mut handles = handles.lock().unwrap().split_off(0);
+        assert_eq!(handles.len(), 2);
+        assert_ne!(handles[0].id, handles[1].id);
+
+        let action_id = handles[0].id.clone();
+        plugin
+            .client
+            .lock()
+            .await
+            .expect_send_message()
+            .withf(move |msg| match msg {
+                Message::DeviceActionStatusNotification(msg) => {
+                    msg.data.device_id == DEVICE_ID
+                        && msg.data.action.name == MockDevice::ACTION_NOINPUT
+                        && msg.data.action.id == action_id
+                }
+                _ => false,
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+
+        handles[0].start().await.unwrap();
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_invoke_action_failure(mut plugin: Plugin) {
+        let adapter = add_mock_adapter(&mut plugin, ADAPTER_ID).await;
+        let device = add_mock_device(adapter.lock().await.adapter_handle_mut(), DEVICE_ID).await;
+        let device = device.lock().await;
+        let device = device.device_handle();
+
+        {
+            let action = device.get_action(MockDevice::ACTION_NOINPUT).unwrap();
+            let mut action = action.lock().await;
+            let action = action
+                .as_any_mut()
+                .downcast_mut::<MockAction<NoInput>>()
+                .unwrap();
+            action
+                .action_helper
+                .expect_perform()
+                .times(1)
+                .returning(|_| Err("failed".to_owned()));
+        }
+
+        assert!(matches!(
+            device
+                .invoke_action(MockDevice::ACTION_NOINPUT, json!(null))
+                .await,
+            Err(WebthingsError::ActionFailed(_, _))
+        ));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_invoke_unknown_action(device: DeviceHandle) {
+        assert!(matches!(
+            device.invoke_action(ACTION_NAME, json!(null)).await,
+            Err(WebthingsError::UnknownAction(_))
+        ));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_event_post_init(mut device: DeviceHandle) {
+        let mut mock_event = MockEvent::<NoData>::new(EVENT_NAME.to_owned());
+        mock_event.expect_post_init = true;
+        mock_event.expect_post_init().times(1).returning(|| ());
+        device.add_event(Box::new(mock_event)).await;
+    }
+
+    #[cfg(feature = "cancellation")]
+    #[rstest]
+    #[tokio::test]
+    async fn test_cancel_all_actions(mut plugin: Plugin) {
+        let adapter = add_mock_adapter(&mut plugin, ADAPTER_ID).await;
+        let device = add_mock_device(adapter.lock().await.adapter_handle_mut(), DEVICE_ID).await;
+        let device = device.lock().await;
+        let device = device.device_handle();
+
+        let handles = Arc::new(std::sync::Mutex::new(Vec::new()));
+        {
+            let handles = handles.clone();
+            let action = device.get_action(MockDevice::ACTION_NOINPUT).unwrap();
+            let mut action = action.lock().await;
+            let action = action
+                .as_any_mut()
+                .downcast_mut::<MockAction<NoInput>>()
+                .unwrap();
+            action
+                .action_helper
+                .expect_perform()
+                .times(3)
+                .returning(move |action_handle| {
+                    handles.lock().unwrap().push(action_handle);
+                    Ok(())
+                });
+            action
+                .action_helper
+                .expect_cancel()
+                .times(3)
+                .returning(|_| Ok(()));
+        }
+
+        for action_id in ["cooperative", "finishing", "stubborn"] {
+            device
+                .request_action(
+                    MockDevice::ACTION_NOINPUT.to_owned(),
+                    action_id.to_owned(),
+                    json!(null),
+                )
+                .await
+                .unwrap();
+        }
+        let running_actions: Vec<String> = device
+            .running_actions()
+            .into_iter()
+            .map(|action_handle| action_handle.id)
+            .collect();
+        assert_eq!(running_actions, ["cooperative", "finishing", "stubborn"]);
+
+        let stubborn = handles.lock().unwrap().pop().unwrap();
+        let mut finishing = handles.lock().unwrap().pop().unwrap();
+        let cooperative = handles.lock().unwrap().pop().unwrap();
+        let cooperative_token = cooperative.cancellation_token.clone();
+        tokio::spawn(async move {
+            cooperative.cancellation_token.cancelled().await;
+            drop(cooperative);
+        });
+        tokio::spawn(async move {
+            finishing.cancellation_token.cancelled().await;
+            finishing.finish().await.unwrap();
+        });
+
+        {
+            let mut client = plugin.client.lock().await;
+            for (action_id, status) in [
+                ("cooperative", "error"),
+                ("finishing", "completed"),
+                ("stubborn", "error"),
+            ] {
+                client
+                    .expect_send_message()
+                    .withf(move |msg| match msg {
+                        Message::DeviceActionStatusNotification(msg) => {
+                            msg.data.action.id == action_id
+                                && msg.data.action.status == status
+                                && msg.data.action.time_completed.is_some()
+                        }
+                        _ => false,
+                    })
+                    .times(1)
+                    .returning(|_| Ok(()));
+            }
+        }
+
+        device
+            .cancel_all_actions(Duration::from_millis(50))
+            .await
+            .unwrap();
+        assert!(cooperative_token.is_cancelled());
+        assert!(stubborn.cancellation_token.is_cancelled());
+        assert!(device.running_actions().is_empty());
+    }
+
+    #[rstest]
+    #[tokio::test]
     async fn test_action_post_init(mut device: DeviceHandle) {
         let mut mock_action = MockAction::<NoInput>::new(ACTION_NAME.to_owned());
         mock_action.expect_post_init = true;
@@ -416,4 +1781,212 @@ pub(crate) mod tests {
         mock_property.expect_post_init().times(1).returning(|| ());
         device.add_property(Box::new(mock_property)).await;
     }
+
+    #[tokio::test]
+    async fn test_liveness_timeout() {
+        let client = Arc::new(Mutex::new(Client::new()));
+        let mut adapter =
+            AdapterHandle::new(client.clone(), PLUGIN_ID.to_owned(), ADAPTER_ID.to_owned());
+        let device = add_mock_device(&mut adapter, DEVICE_ID).await;
+
+        let mut seq = Sequence::new();
+        for connected in [false, true] {
+            client
+                .lock()
+                .await
+                .expect_send_message()
+                .withf(move |msg| match msg {
+                    Message::DeviceConnectedStateNotification(msg) => {
+                        msg.data.connected == connected
+                    }
+                    _ => false,
+                })
+                .times(1)
+                .in_sequence(&mut seq)
+                .returning(|_| Ok(()));
+        }
+
+        device
+            .lock()
+            .await
+            .device_handle_mut()
+            .set_liveness_timeout(Duration::from_millis(20));
+
+        sleep(Duration::from_millis(40)).await;
+        assert!(!device.lock().await.device_handle().connected());
+
+        device
+            .lock()
+            .await
+            .device_handle_mut()
+            .heartbeat()
+            .await
+            .unwrap();
+        assert!(device.lock().await.device_handle().connected());
+
+        device.lock().await.device_handle_mut().stop_polling();
+    }
+
+    #[tokio::test]
+    async fn test_liveness_debounce() {
+        let client = Arc::new(Mutex::new(Client::new()));
+        let mut adapter =
+            AdapterHandle::new(client.clone(), PLUGIN_ID.to_owned(), ADAPTER_ID.to_owned());
+        let device = add_mock_device(&mut adapter, DEVICE_ID).await;
+
+        client
+            .lock()
+            .await
+            .expect_send_message()
+            .withf(|msg| matches!(msg, Message::DeviceConnectedStateNotification(msg) if !msg.data.connected))
+            .times(1)
+            .returning(|_| Ok(()));
+
+        {
+            let mut device = device.lock().await;
+            let device_handle = device.device_handle_mut();
+            device_handle.set_liveness_debounce(Duration::from_secs(60));
+            device_handle.set_liveness_timeout(Duration::from_millis(20));
+        }
+
+        sleep(Duration::from_millis(40)).await;
+
+        let mut device = device.lock().await;
+        let device_handle = device.device_handle_mut();
+        device_handle.heartbeat().await.unwrap();
+        assert!(!device_handle.connected());
+        device_handle.stop_polling();
+    }
+
+    #[tokio::test]
+    async fn test_liveness_debounce_elapsed() {
+        let client = Arc::new(Mutex::new(Client::new()));
+        let mut adapter =
+            AdapterHandle::new(client.clone(), PLUGIN_ID.to_owned(), ADAPTER_ID.to_owned());
+        let device = add_mock_device(&mut adapter, DEVICE_ID).await;
+
+        let mut seq = Sequence::new();
+        for connected in [false, true, false] {
+            client
+                .lock()
+                .await
+                .expect_send_message()
+                .withf(move |msg| match msg {
+                    Message::DeviceConnectedStateNotification(msg) => {
+                        msg.data.connected == connected
+                    }
+                    _ => false,
+                })
+                .times(1)
+                .in_sequence(&mut seq)
+                .returning(|_| Ok(()));
+        }
+
+        {
+            let mut device = device.lock().await;
+            let device_handle = device.device_handle_mut();
+            device_handle.set_liveness_debounce(Duration::from_millis(150));
+            device_handle.set_liveness_timeout(Duration::from_millis(100));
+        }
+
+        sleep(Duration::from_millis(260)).await;
+        device
+            .lock()
+            .await
+            .device_handle_mut()
+            .heartbeat()
+            .await
+            .unwrap();
+        assert!(device.lock().await.device_handle().connected());
+
+        // Expired 100 ms after the heartbeat, but blocked by the debounce for another 50 ms
+        sleep(Duration::from_millis(175)).await;
+        assert!(!device.lock().await.device_handle().connected());
+
+        device.lock().await.device_handle_mut().stop_polling();
+    }
+
+    #[tokio::test]
+    async fn test_liveness_keeps_manual_disconnect() {
+        let client = Arc::new(Mutex::new(Client::new()));
+        let mut adapter =
+            AdapterHandle::new(client.clone(), PLUGIN_ID.to_owned(), ADAPTER_ID.to_owned());
+        let device = add_mock_device(&mut adapter, DEVICE_ID).await;
+
+        client
+            .lock()
+            .await
+            .expect_send_message()
+            .withf(|msg| matches!(msg, Message::DeviceConnectedStateNotification(msg) if !msg.data.connected))
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let mut device = device.lock().await;
+        let device_handle = device.device_handle_mut();
+        device_handle.set_liveness_timeout(Duration::from_secs(60));
+        device_handle.set_connected(false).await.unwrap();
+        device_handle.heartbeat().await.unwrap();
+        assert!(!device_handle.connected());
+        device_handle.stop_polling();
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_property_poll(mut device: DeviceHandle) {
+        let mut mock_property = MockProperty::<i32>::new(PROPERTY_NAME.to_owned());
+        mock_property.poll_interval = Some(Duration::from_millis(10));
+        mock_property.expect_poll().times(1..).returning(|| Ok(42));
+
+        device
+            .client
+            .lock()
+            .await
+            .expect_send_message()
+            .withf(move |msg| match msg {
+                Message::DevicePropertyChangedNotification(msg) => {
+                    msg.data.property.name == Some(PROPERTY_NAME.to_owned())
+                        && msg.data.property.value == Some(json!(42))
+                }
+                _ => false,
+            })
+            .times(1..)
+            .returning(|_| Ok(()));
+
+        device.add_property(Box::new(mock_property)).await;
+        device.start_polling().await;
+        sleep(Duration::from_millis(25)).await;
+        device.stop_polling();
+        sleep(Duration::from_millis(25)).await;
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_property_poll_not_started(mut device: DeviceHandle) {
+        let mut mock_property = MockProperty::<i32>::new(PROPERTY_NAME.to_owned());
+        mock_property.poll_interval = Some(Duration::from_millis(10));
+        mock_property.expect_poll().times(0);
+        device.add_property(Box::new(mock_property)).await;
+        sleep(Duration::from_millis(25)).await;
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_property_poll_first_tick_delayed(mut device: DeviceHandle) {
+        let mut mock_property = MockProperty::<i32>::new(PROPERTY_NAME.to_owned());
+        mock_property.poll_interval = Some(Duration::from_millis(100));
+        mock_property.expect_poll().times(0);
+        device.add_property(Box::new(mock_property)).await;
+        device.start_polling().await;
+        sleep(Duration::from_millis(25)).await;
+        device.stop_polling();
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_property_no_poll(mut device: DeviceHandle) {
+        let mut mock_property = MockProperty::<i32>::new(PROPERTY_NAME.to_owned());
+        mock_property.expect_poll().times(0);
+        device.add_property(Box::new(mock_property)).await;
+        sleep(Duration::from_millis(10)).await;
+    }
 }
@@ -5,6 +5,8 @@
  */
 
 use crate::{
+    client,
+    error::{HandleError, WebthingsError},
     message_handler::{MessageHandler, MessageResult},
     Device,
 };
@@ -16,32 +18,49 @@ use webthings_gateway_ipc_types::{
 
 #[async_trait]
 impl MessageHandler for dyn Device {
-    async fn handle_message(&mut self, message: IPCMessage) -> Result<MessageResult, String> {
+    async fn handle_message(&mut self, message: IPCMessage) -> Result<MessageResult, HandleError> {
         match message {
             IPCMessage::DeviceSetPropertyCommand(DeviceSetPropertyCommand { data, .. }) => {
                 let property = self
                     .device_handle()
                     .get_property(&data.property_name)
                     .ok_or_else(|| {
-                        format!(
-                            "Could not update property {} of {}: not found",
-                            data.property_name, data.device_id,
-                        )
+                        HandleError::UnknownTarget(WebthingsError::UnknownProperty(
+                            data.property_name.clone(),
+                        ))
                     })?;
                 let mut property = property.lock().await;
 
-                property.on_update(data.property_value.clone()).await?;
+                let context = || {
+                    format!(
+                        "Could not update property {} of {}",
+                        data.property_name, data.device_id
+                    )
+                };
+                if !property.property_handle().writable() {
+                    return Err(HandleError::InvalidValue(
+                        context(),
+                        WebthingsError::ReadOnlyProperty(data.property_name.clone()),
+                    ));
+                }
+                let value = if property.patchable() && data.property_value.is_object() {
+                    property
+                        .on_patch(data.property_value.clone())
+                        .await
+                        .map_err(|err| HandleError::Callback(context(), err))?
+                } else {
+                    property
+                        .on_update(data.property_value.clone())
+                        .await
+                        .map_err(|err| HandleError::Callback(context(), err))?;
+                    Some(data.property_value.clone())
+                };
 
                 property
                     .property_handle_mut()
-                    .set_value(Some(data.property_value.clone()))
+                    .set_value(value)
                     .await
-                    .map_err(|err| {
-                        format!(
-                            "Could not update property {} of {}: {}",
-                            data.property_name, data.device_id, err,
-                        )
-                    })?;
+                    .map_err(|err| HandleError::from_error(context(), err))?;
             }
             IPCMessage::DeviceRequestActionRequest(DeviceRequestActionRequest { data, .. }) => {
                 let result = self
@@ -53,6 +72,8 @@ impl MessageHandler for dyn Device {
                     )
                     .await;
 
+                // The IPC response has no field for an action output,
+                // so outputs recorded with `ActionHandle::finish_with` cannot be forwarded here.
                 let reply = DeviceRequestActionResponseMessageData {
                     plugin_id: data.plugin_id.clone(),
                     adapter_id: data.adapter_id.clone(),
@@ -63,20 +84,13 @@ impl MessageHandler for dyn Device {
                 }
                 .into();
 
-                self.device_handle()
-                    .client
-                    .lock()
-                    .await
-                    .send_message(&reply)
+                client::send_message(&self.device_handle().client, &reply)
                     .await
-                    .map_err(|err| format!("{:?}", err))?;
+                    .map_err(|err| {
+                        HandleError::from_error("Could not send request action response", err)
+                    })?;
 
-                result.map_err(|err| {
-                    format!(
-                        "Failed to request action {} for device {}: {:?}",
-                        data.action_name, data.device_id, err
-                    )
-                })?;
+                result?;
             }
             IPCMessage::DeviceRemoveActionRequest(DeviceRemoveActionRequest { data, .. }) => {
                 let result = self
@@ -95,22 +109,15 @@ impl MessageHandler for dyn Device {
                 }
                 .into();
 
-                self.device_handle()
-                    .client
-                    .lock()
+                client::send_message(&self.device_handle().client, &reply)
                     .await
-                    .send_message(&reply)
-                    .await
-                    .map_err(|err| format!("{:?}", err))?;
+                    .map_err(|err| {
+                        HandleError::from_error("Could not send remove action response", err)
+                    })?;
 
-                result.map_err(|err| {
-                    format!(
-                        "Failed to remove action {} ({}) for device {}: {:?}",
-                        data.action_name, data.action_id, data.device_id, err
-                    )
-                })?;
+                result?;
             }
-            msg => return Err(format!("Unexpected msg: {:?}", msg)),
+            msg => return Err(HandleError::UnexpectedMessage(format!("{:?}", msg))),
         }
         Ok(MessageResult::Continue)
     }
@@ -122,15 +129,19 @@ pub(crate) mod tests {
         action::{tests::MockAction, Input, NoInput},
         adapter::tests::add_mock_device,
         device::tests::MockDevice,
+        error::{HandleError, WebthingsError},
         event::{tests::BuiltMockEvent, BuiltEvent, NoData},
         message_handler::MessageHandler,
         plugin::tests::{add_mock_adapter, plugin},
         property::{self, tests::BuiltMockProperty},
-        Plugin, PropertyHandle,
+        Device, Plugin, PropertyHandle,
     };
     use as_any::Downcast;
     use rstest::rstest;
+    use serde::{Deserialize, Serialize};
     use serde_json::json;
+    use std::{any::type_name, sync::Arc};
+    use tokio::sync::Mutex;
     use webthings_gateway_ipc_types::{
         DeviceRemoveActionRequestMessageData, DeviceRequestActionRequestMessageData,
         DeviceSetPropertyCommandMessageData, Message,
@@ -141,6 +152,93 @@ pub(crate) mod tests {
     const DEVICE_ID: &str = "device_id";
     const ACTION_ID: &str = "action_id";
 
+    /// Request an action of a mock device with a typed input, as the gateway would.
+    ///
+    /// Expects the request action response and returns the result of handling the request.
+    /// Panics if the json schema type of `T` does not match the input of the action.
+    pub async fn invoke_action_typed<T: Input + Serialize>(
+        device: &Arc<Mutex<Box<dyn Device>>>,
+        action_name: &'static str,
+        input: T,
+    ) -> Result<(), HandleError> {
+        let mut device = device.lock().await;
+
+        let action = device
+            .device_handle()
+            .get_action(action_name)
+            .unwrap_or_else(|| panic!("Unknown action {:?}", action_name));
+        let expected_schema = action.lock().await.full_description().input;
+        let schema_type = |schema: &Option<serde_json::Value>| {
+            schema
+                .as_ref()
+                .and_then(|schema| schema.get("type"))
+                .cloned()
+        };
+        assert_eq!(
+            schema_type(&T::input()),
+            schema_type(&expected_schema),
+            "Input of type {} does not match the input of action {:?}",
+            type_name::<T>(),
+            action_name
+        );
+
+        let device_handle = device.device_handle();
+        let message: Message = DeviceRequestActionRequestMessageData {
+            plugin_id: device_handle.plugin_id.clone(),
+            adapter_id: device_handle.adapter_id.clone(),
+            device_id: device_handle.device_id.clone(),
+            action_name: action_name.to_owned(),
+            action_id: ACTION_ID.to_owned(),
+            input: serde_json::to_value(input).unwrap(),
+        }
+        .into();
+
+        device_handle
+            .client
+            .lock()
+            .await
+            .expect_send_message()
+            .withf(move |msg| match msg {
+                Message::DeviceRequestActionResponse(msg) => {
+                    msg.data.action_name == action_name && msg.data.action_id == ACTION_ID
+                }
+                _ => false,
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+
+        device.handle_message(message).await.map(|_| ())
+    }
+
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    struct FadeInput {
+        level: u8,
+        duration: u32,
+    }
+
+    impl Input for FadeInput {
+        fn input() -> Option<serde_json::Value> {
+            Some(json!({
+                "type": "object",
+                "properties": {
+                    "level": {
+                        "type": "integer",
+                        "minimum": 0,
+                        "maximum": 100,
+                    },
+                    "duration": {
+                        "type": "integer",
+                        "minimum": 0,
+                    }
+                }
+            }))
+        }
+
+        fn deserialize(value: serde_json::Value) -> Result<Self, WebthingsError> {
+            serde_json::from_value(value).map_err(WebthingsError::Serialization)
+        }
+    }
+
     #[rstest]
     #[case(MockDevice::ACTION_NOINPUT, json!(null), NoInput)]
     #[case(MockDevice::ACTION_BOOL, json!(true), true)]
@@ -264,6 +362,62 @@ pub(crate) mod tests {
         plugin.handle_message(message).await.unwrap();
     }
 
+    #[cfg(feature = "cancellation")]
+    #[rstest]
+    #[tokio::test]
+    async fn test_request_action_cancellation_token(mut plugin: Plugin) {
+        let token = std::sync::Arc::new(std::sync::Mutex::new(None));
+        let adapter = add_mock_adapter(&mut plugin, ADAPTER_ID).await;
+        let device = add_mock_device(adapter.lock().await.adapter_handle_mut(), DEVICE_ID).await;
+        let device = device.lock().await;
+
+        {
+            let action = device
+                .device_handle()
+                .get_action(MockDevice::ACTION_NOINPUT)
+                .unwrap();
+            let mut action = action.lock().await;
+            let action = action
+                .as_any_mut()
+                .downcast_mut::<MockAction<NoInput>>()
+                .unwrap();
+            let token = token.clone();
+            action
+                .action_helper
+                .expect_perform()
+                .times(1)
+                .returning(move |action_handle| {
+                    *token.lock().unwrap() = Some(action_handle.cancellation_token.clone());
+                    Ok(())
+                });
+            action
+                .action_helper
+                .expect_cancel()
+                .withf(move |action_id| action_id == ACTION_ID)
+                .times(1)
+                .returning(|_| Ok(()));
+        }
+
+        device
+            .device_handle()
+            .request_action(
+                MockDevice::ACTION_NOINPUT.to_owned(),
+                ACTION_ID.to_owned(),
+                json!(null),
+            )
+            .await
+            .unwrap();
+        let token = token.lock().unwrap().take().unwrap();
+        assert!(!token.is_cancelled());
+
+        device
+            .device_handle()
+            .remove_action(MockDevice::ACTION_NOINPUT.to_owned(), ACTION_ID.to_owned())
+            .await
+            .unwrap();
+        assert!(token.is_cancelled());
+    }
+
     #[rstest]
     #[case(MockDevice::PROPERTY_BOOL, json!(true), true)]
     #[case(MockDevice::PROPERTY_U8, json!(112_u8), 112_u8)]
@@ -327,6 +481,50 @@ pub(crate) mod tests {
         plugin.handle_message(message).await.unwrap();
     }
 
+    #[rstest]
+    #[tokio::test]
+    async fn test_request_property_update_read_only(mut plugin: Plugin) {
+        let adapter = add_mock_adapter(&mut plugin, ADAPTER_ID).await;
+        let device = add_mock_device(adapter.lock().await.adapter_handle_mut(), DEVICE_ID).await;
+
+        {
+            let device = device.lock().await;
+            let property = device
+                .device_handle()
+                .get_property(MockDevice::PROPERTY_I32)
+                .unwrap();
+            let mut property = property.lock().await;
+            property
+                .property_handle_mut()
+                .as_any_mut()
+                .downcast_mut::<PropertyHandle<i32>>()
+                .unwrap()
+                .description
+                .read_only = Some(true);
+            let property = property.downcast_mut::<BuiltMockProperty<i32>>().unwrap();
+            property.expect_on_update().times(0);
+        }
+
+        plugin.client.lock().await.expect_send_message().times(0);
+
+        let message: Message = DeviceSetPropertyCommandMessageData {
+            plugin_id: PLUGIN_ID.to_owned(),
+            adapter_id: ADAPTER_ID.to_owned(),
+            device_id: DEVICE_ID.to_owned(),
+            property_name: MockDevice::PROPERTY_I32.to_owned(),
+            property_value: json!(21),
+        }
+        .into();
+
+        assert!(matches!(
+            plugin.handle_message(message).await,
+            Err(HandleError::InvalidValue(
+                _,
+                WebthingsError::ReadOnlyProperty(_)
+            ))
+        ));
+    }
+
     #[rstest]
     #[tokio::test]
     async fn test_device_has_weak_adapter_ref(mut plugin: Plugin) {
@@ -387,4 +585,45 @@ pub(crate) mod tests {
             .upgrade()
             .is_some())
     }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_invoke_action_typed(mut plugin: Plugin) {
+        let adapter = add_mock_adapter(&mut plugin, ADAPTER_ID).await;
+        let device = add_mock_device(adapter.lock().await.adapter_handle_mut(), DEVICE_ID).await;
+
+        let expected_input = FadeInput {
+            level: 50,
+            duration: 3,
+        };
+        {
+            let mut device = device.lock().await;
+            let mut action = MockAction::<FadeInput>::new("fade".to_owned());
+            let input = expected_input.clone();
+            action
+                .action_helper
+                .expect_perform()
+                .withf(move |action_handle| action_handle.input == input)
+                .times(1)
+                .returning(|_| Ok(()));
+            device
+                .device_handle_mut()
+                .add_action(Box::new(action))
+                .await;
+        }
+
+        invoke_action_typed(&device, "fade", expected_input)
+            .await
+            .unwrap();
+    }
+
+    #[rstest]
+    #[tokio::test]
+    #[should_panic(expected = "does not match the input of action")]
+    async fn test_invoke_action_typed_mismatch(mut plugin: Plugin) {
+        let adapter = add_mock_adapter(&mut plugin, ADAPTER_ID).await;
+        let device = add_mock_device(adapter.lock().await.adapter_handle_mut(), DEVICE_ID).await;
+
+        let _ = invoke_action_typed(&device, MockDevice::ACTION_I32, "foo".to_owned()).await;
+    }
 }
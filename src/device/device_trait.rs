@@ -4,7 +4,7 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
  */
 
-use crate::DeviceHandle;
+use crate::{device::DeviceCapability, error::WebthingsError, DeviceHandle};
 use as_any::{AsAny, Downcast};
 use async_trait::async_trait;
 
@@ -35,7 +35,41 @@ use async_trait::async_trait;
 /// impl Device for BuiltExampleDevice {}
 /// ```
 #[async_trait]
-pub trait Device: BuiltDevice + Send + Sync + AsAny + 'static {}
+pub trait Device: BuiltDevice + Send + Sync + AsAny + 'static {
+    /// Called once after this device was added to its [adapter][crate::Adapter] and announced to the gateway.
+    ///
+    /// At this point [DeviceHandle::device] is valid, so it is safe to spawn tasks referencing this device.
+    fn post_init(&mut self) {}
+
+    /// Called when the connected state of this device changes through [set_connected][Device::set_connected].
+    async fn on_connected_changed(&mut self, _connected: bool) {}
+
+    /// The [capabilities][DeviceCapability] of this device, e.g. to route commands of an adapter
+    /// to matching devices only.
+    ///
+    /// Defaults to the [capabilities derived from the description][crate::DeviceDescription::capabilities].
+    /// Override it to add [custom capabilities][DeviceCapability::Custom].
+    fn capabilities(&self) -> Vec<DeviceCapability> {
+        self.device_handle().description.capabilities()
+    }
+
+    /// Set the connected state of this device, notify the gateway and call
+    /// [on_connected_changed][Device::on_connected_changed] if the state changed.
+    ///
+    /// Calls from within [on_connected_changed][Device::on_connected_changed] only update the
+    /// state and notify the gateway, they do not call the hook again.
+    async fn set_connected(&mut self, connected: bool) -> Result<(), WebthingsError> {
+        let device_handle = self.device_handle_mut();
+        let changed = device_handle.connected() != connected;
+        device_handle.set_connected(connected).await?;
+        if changed && !device_handle.connected_hook_running {
+            device_handle.connected_hook_running = true;
+            self.on_connected_changed(connected).await;
+            self.device_handle_mut().connected_hook_running = false;
+        }
+        Ok(())
+    }
+}
 
 impl Downcast for dyn Device {}
 
@@ -70,7 +104,19 @@ pub trait BuiltDevice {
 
 #[cfg(test)]
 pub(crate) mod tests {
-    use crate::device::{tests::MockDevice, BuiltDevice, Device, DeviceHandle};
+    use crate::{
+        client::Client,
+        device::{tests::MockDevice, AtType, BuiltDevice, Device, DeviceCapability, DeviceHandle},
+        DeviceDescription,
+    };
+    use async_trait::async_trait;
+    use rstest::{fixture, rstest};
+    use std::sync::{Arc, Weak};
+    use tokio::sync::Mutex;
+
+    const PLUGIN_ID: &str = "plugin_id";
+    const ADAPTER_ID: &str = "adapter_id";
+    const DEVICE_ID: &str = "device_id";
 
     pub struct BuiltMockDevice {
         data: MockDevice,
@@ -109,5 +155,119 @@ pub(crate) mod tests {
         }
     }
 
-    impl Device for BuiltMockDevice {}
+    impl Device for BuiltMockDevice {
+        fn post_init(&mut self) {
+            self.data.post_init_device_valid =
+                Some(self.device_handle.device().upgrade().is_some());
+        }
+    }
+
+    struct ConnectedDevice {
+        device_handle: DeviceHandle,
+        changes: Vec<bool>,
+    }
+
+    impl BuiltDevice for ConnectedDevice {
+        fn device_handle(&self) -> &DeviceHandle {
+            &self.device_handle
+        }
+
+        fn device_handle_mut(&mut self) -> &mut DeviceHandle {
+            &mut self.device_handle
+        }
+    }
+
+    #[async_trait]
+    impl Device for ConnectedDevice {
+        async fn on_connected_changed(&mut self, connected: bool) {
+            self.changes.push(connected);
+            if !connected {
+                self.set_connected(true).await.unwrap();
+            }
+        }
+    }
+
+    #[fixture]
+    fn device() -> ConnectedDevice {
+        let device_handle = DeviceHandle::new(
+            Arc::new(Mutex::new(Client::new())),
+            Weak::new(),
+            PLUGIN_ID.to_owned(),
+            ADAPTER_ID.to_owned(),
+            DEVICE_ID.to_owned(),
+            DeviceDescription::default(),
+        );
+        ConnectedDevice {
+            device_handle,
+            changes: Vec::new(),
+        }
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_on_connected_changed(mut device: ConnectedDevice) {
+        device
+            .device_handle
+            .client
+            .lock()
+            .await
+            .expect_send_message()
+            .times(3)
+            .returning(|_| Ok(()));
+
+        device.set_connected(true).await.unwrap();
+        assert!(device.changes.is_empty());
+
+        device.set_connected(false).await.unwrap();
+        assert_eq!(device.changes, vec![false]);
+        assert!(device.device_handle().connected());
+    }
+
+    struct CustomCapabilityDevice {
+        device_handle: DeviceHandle,
+    }
+
+    impl BuiltDevice for CustomCapabilityDevice {
+        fn device_handle(&self) -> &DeviceHandle {
+            &self.device_handle
+        }
+
+        fn device_handle_mut(&mut self) -> &mut DeviceHandle {
+            &mut self.device_handle
+        }
+    }
+
+    #[async_trait]
+    impl Device for CustomCapabilityDevice {
+        fn capabilities(&self) -> Vec<DeviceCapability> {
+            let mut capabilities = self.device_handle.description.capabilities();
+            capabilities.push(DeviceCapability::Custom("Dimmable".to_owned()));
+            capabilities
+        }
+    }
+
+    #[rstest]
+    fn test_capabilities(mut device: ConnectedDevice) {
+        device.device_handle.description =
+            DeviceDescription::default().at_types(vec![AtType::Light, AtType::OnOffSwitch]);
+        assert_eq!(
+            device.capabilities(),
+            vec![
+                DeviceCapability::Schema(AtType::Light),
+                DeviceCapability::Schema(AtType::OnOffSwitch),
+            ]
+        );
+
+        let device = CustomCapabilityDevice {
+            device_handle: device.device_handle,
+        };
+        assert_eq!(
+            device.capabilities(),
+            vec![
+                DeviceCapability::Schema(AtType::Light),
+                DeviceCapability::Schema(AtType::OnOffSwitch),
+                DeviceCapability::Custom("Dimmable".to_owned()),
+            ]
+        );
+    }
 }
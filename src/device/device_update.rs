@@ -0,0 +1,43 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+
+/// A single operation of a [DeviceUpdate].
+#[derive(Debug, Clone, PartialEq)]
+pub enum UpdateOperation {
+    /// Set the value of a [property][crate::Property] by name.
+    SetProperty(String, Option<serde_json::Value>),
+    /// Raise an [event][crate::Event] by name.
+    RaiseEvent(String, Option<serde_json::Value>),
+}
+
+/// A set of operations which are sent to the gateway together.
+///
+/// Use it with [DeviceHandle::update][crate::DeviceHandle::update].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DeviceUpdate {
+    operations: Vec<UpdateOperation>,
+}
+
+impl DeviceUpdate {
+    /// Set the value of a [property][crate::Property] which the device owns by name.
+    pub fn set(&mut self, name: impl Into<String>, value: Option<serde_json::Value>) -> &mut Self {
+        self.operations
+            .push(UpdateOperation::SetProperty(name.into(), value));
+        self
+    }
+
+    /// Raise an [event][crate::Event] which the device owns by name.
+    pub fn event(&mut self, name: impl Into<String>, data: Option<serde_json::Value>) -> &mut Self {
+        self.operations
+            .push(UpdateOperation::RaiseEvent(name.into(), data));
+        self
+    }
+
+    /// Get the collected operations in order.
+    pub fn operations(&self) -> &[UpdateOperation] {
+        &self.operations
+    }
+}
@@ -6,20 +6,37 @@
 
 //! A module for everything related to WoT devices aka things.
 
+#[cfg(feature = "runtime")]
 mod device_builder;
+mod device_capability;
 mod device_description;
+mod device_description_diff;
+#[cfg(feature = "runtime")]
 mod device_handle;
+#[cfg(feature = "runtime")]
 mod device_macro;
+#[cfg(feature = "runtime")]
 pub(crate) mod device_message_handler;
+#[cfg(feature = "runtime")]
 mod device_trait;
+#[cfg(feature = "runtime")]
+mod device_update;
 
+#[cfg(feature = "runtime")]
 pub use device_builder::*;
+pub use device_capability::*;
 pub use device_description::*;
+pub use device_description_diff::*;
+#[cfg(feature = "runtime")]
 pub use device_handle::*;
+#[cfg(feature = "runtime")]
 pub use device_macro::*;
+#[cfg(feature = "runtime")]
 pub use device_trait::*;
+#[cfg(feature = "runtime")]
+pub use device_update::*;
 
-#[cfg(test)]
+#[cfg(all(test, feature = "runtime"))]
 pub(crate) mod tests {
     pub use super::{device_builder::tests::*, device_trait::tests::*};
 }
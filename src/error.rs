@@ -6,24 +6,67 @@
 
 //! The set of possible errors when working with this crate.
 
+use crate::type_::Type;
 use thiserror::Error;
 
 /// The set of possible errors when working with this crate.
 #[derive(Error, Debug)]
 pub enum WebthingsError {
     /// Failed to connect to gateway
+    #[cfg(feature = "runtime")]
     #[error("Failed to connect to gateway")]
     Connect(#[source] tungstenite::Error),
 
+    /// Gateway did not answer the registration of the plugin in time
+    #[error("Gateway did not answer the registration in time")]
+    RegisterTimeout,
+
+    /// Connection closed before the gateway answered the registration of the plugin
+    #[error("Connection closed during registration")]
+    RegisterConnectionClosed,
+
+    /// Invalid TLS configuration
+    #[cfg(feature = "tls")]
+    #[error("Invalid TLS configuration")]
+    Tls(#[source] native_tls::Error),
+
+    /// Invalid gateway url
+    #[cfg(feature = "runtime")]
+    #[error("Invalid gateway url")]
+    InvalidUrl(#[source] url::ParseError),
+
+    /// Failed to read message
+    #[error("Failed to read message: {0}")]
+    Read(String),
+
     /// Failed to send message
+    #[cfg(feature = "runtime")]
     #[error("Failed to send message")]
     Send(#[source] tungstenite::Error),
 
+    /// Client is busy and the message was not enqueued
+    #[error("Client is busy")]
+    WouldBlock,
+
+    /// Outgoing queue is full and the message was rejected
+    #[error("Outgoing queue is full")]
+    QueueFull,
+
+    /// Message was dropped from the full outgoing queue in favor of a newer one
+    #[error("Message was dropped from the outgoing queue")]
+    MessageDropped,
+
+    /// No matching message was sent in time
+    #[cfg(feature = "testing")]
+    #[error("No matching message was sent in time")]
+    MessageTimeout,
+
     /// Failed to serialize message
     #[error("Failed to serialize message")]
     Serialization(#[source] serde_json::Error),
 
     /// Failed to access database
+    #[cfg(feature = "runtime")]
     #[error("Failed to access database")]
     Database(#[source] sqlite::Error),
 
@@ -35,6 +78,14 @@ pub enum WebthingsError {
     #[error("Unknown event")]
     UnknownEvent(String),
 
+    /// Unknown action
+    #[error("Unknown action")]
+    UnknownAction(String),
+
+    /// Action failed
+    #[error("Action {0:?} failed: {1}")]
+    ActionFailed(String, String),
+
     /// Unknown device
     #[error("Unknown device")]
     UnknownDevice(String),
@@ -42,4 +93,128 @@ pub enum WebthingsError {
     /// Unknown adapter
     #[error("Unknown adapter")]
     UnknownAdapter(String),
+
+    /// Invalid id
+    #[error("Invalid id")]
+    InvalidId(String),
+
+    /// Affordance name is used more than once within a device
+    #[error("Name {0:?} is used by more than one property, action or event")]
+    DuplicateName(String),
+
+    /// Normalized device id is already used by another device
+    #[error("Id {0:?} collides with another device after normalization to {1:?}")]
+    IdCollision(String, String),
+
+    /// Property has an unexpected type
+    #[error("Property {0:?} is not of type {1}")]
+    PropertyTypeMismatch(String, &'static str),
+
+    /// Value of a property is not one of its `enum` values
+    #[error("Value {1} of property {0:?} is not one of its enum values")]
+    ValueNotInEnum(String, serde_json::Value),
+
+    /// Value does not match the type of a property
+    #[error("Invalid value for property {0:?}, expected {}: {2}", .1.to_string())]
+    InvalidPropertyValue(String, Type, String),
+
+    /// Property is write-only and its value cannot be read
+    #[error("Property {0:?} is write-only")]
+    WriteOnlyProperty(String),
+
+    /// Property is read-only and its value cannot be written
+    #[error("Property {0:?} is read-only")]
+    ReadOnlyProperty(String),
+
+    /// Property is write-only but also declares conflicting access
+    #[error("Write-only property {0:?} cannot be {1}")]
+    InvalidPropertyAccess(String, &'static str),
+
+    /// Input schema of an action is not a valid json schema
+    #[error("Input schema of action {0:?} is invalid: {1}")]
+    InvalidInputSchema(String, String),
+
+    /// Unit of a property does not match its `@type`
+    #[error("Unit {2:?} of property {0:?} does not match its @type {1}")]
+    UnitMismatch(String, String, String),
+
+    /// An operation of a [device update][crate::device::DeviceUpdate] failed
+    #[error("Operation {0} of device update failed (earlier operations sent: {1})")]
+    UpdateFailed(usize, bool, #[source] Box<WebthingsError>),
+}
+
+/// Why a message received from the gateway could not be handled.
+///
+/// See [Plugin::dispatch][crate::Plugin::dispatch].
+#[cfg(feature = "runtime")]
+#[derive(Error, Debug)]
+pub enum HandleError {
+    /// Failed to send a reply or notification to the gateway
+    #[error("{0}: {1}")]
+    Transport(String, #[source] WebthingsError),
+
+    /// A callback of the addon failed
+    #[error("{0}: {1}")]
+    Callback(String, String),
+
+    /// The message refers to an unknown adapter, device, property or action
+    #[error("Unknown target: {0:?}")]
+    UnknownTarget(#[source] WebthingsError),
+
+    /// The message contains an invalid value
+    #[error("{0}: {1}")]
+    InvalidValue(String, #[source] WebthingsError),
+
+    /// The message is not handled by this crate
+    #[error("Unexpected message: {0}")]
+    UnexpectedMessage(String),
+}
+
+#[cfg(feature = "runtime")]
+impl HandleError {
+    /// Categorize an error of this crate which occurred while handling a message.
+    pub(crate) fn from_error(context: impl Into<String>, err: WebthingsError) -> Self {
+        match err {
+            WebthingsError::Send(_) => Self::Transport(context.into(), err),
+            WebthingsError::UnknownAction(_)
+            | WebthingsError::UnknownAdapter(_)
+            | WebthingsError::UnknownDevice(_)
+            | WebthingsError::UnknownEvent(_)
+            | WebthingsError::UnknownProperty(_) => Self::UnknownTarget(err),
+            err => Self::InvalidValue(context.into(), err),
+        }
+    }
+
+    /// Whether the connection to the gateway failed, as opposed to e.g. addon code.
+    pub fn is_transport(&self) -> bool {
+        matches!(self, Self::Transport(..))
+    }
+}
+
+#[cfg(all(test, feature = "runtime"))]
+mod tests {
+    use super::{HandleError, WebthingsError};
+    use serde_json::json;
+
+    #[test]
+    fn test_handle_error_from_error() {
+        assert!(matches!(
+            HandleError::from_error(
+                "",
+                WebthingsError::Send(tungstenite::Error::ConnectionClosed)
+            ),
+            HandleError::Transport(..)
+        ));
+        assert!(matches!(
+            HandleError::from_error("", WebthingsError::UnknownDevice("foo".to_owned())),
+            HandleError::UnknownTarget(WebthingsError::UnknownDevice(_))
+        ));
+        assert!(matches!(
+            HandleError::from_error(
+                "",
+                WebthingsError::ValueNotInEnum("foo".to_owned(), json!(42))
+            ),
+            HandleError::InvalidValue(..)
+        ));
+    }
 }
@@ -6,6 +6,7 @@
 
 use crate::{error::WebthingsError, type_::Type, EventDescription};
 use serde::{ser::Error, Serialize};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 
 /// A trait which converts Rust types to WoT [types][crate::type_::Type].
 ///
@@ -232,7 +233,9 @@ impl<T: Data> Data for Option<T> {
         description.minimum = t_description.minimum;
         description.multiple_of = t_description.multiple_of;
         description.title = t_description.title;
+        description.type_ = t_description.type_;
         description.unit = t_description.unit;
+        description.retain_last = t_description.retain_last;
         description
     }
 
@@ -249,10 +252,38 @@ impl<T: Data> Data for Option<T> {
     }
 }
 
+impl SimpleData for IpAddr {
+    fn type_() -> Option<Type> {
+        Some(Type::String)
+    }
+}
+
+impl SimpleData for Ipv4Addr {
+    fn type_() -> Option<Type> {
+        Some(Type::String)
+    }
+}
+
+impl SimpleData for Ipv6Addr {
+    fn type_() -> Option<Type> {
+        Some(Type::String)
+    }
+}
+
+impl SimpleData for SocketAddr {
+    fn type_() -> Option<Type> {
+        Some(Type::String)
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::event::{self, Data, NoData};
+    use crate::{
+        event::{self, AtType, Data, EventDescription, NoData},
+        type_::Type,
+    };
     use serde_json::json;
+    use std::net::{IpAddr, SocketAddr};
 
     #[test]
     fn test_serialize_nodata() {
@@ -352,4 +383,74 @@ mod tests {
             Some(json!({"i": 42, "s": "foo", "o": {"b": true}}))
         );
     }
+
+    #[derive(Clone, serde::Serialize)]
+    struct Temperature(f64);
+
+    impl event::SimpleData for Temperature {
+        fn type_() -> Option<Type> {
+            Some(Type::Integer)
+        }
+
+        fn description(description: EventDescription<Self>) -> EventDescription<Self> {
+            description
+                .at_type(AtType::OverheatedEvent)
+                .description("Temperature of the device")
+                .enum_(vec![Temperature(20.0), Temperature(80.0)])
+                .maximum(100)
+                .minimum(-20)
+                .multiple_of(0.5)
+                .title("Temperature")
+                .type_(Type::Number)
+                .unit("degree celsius")
+                .retain_last(true)
+        }
+    }
+
+    #[test]
+    fn test_option_description_forwarding() {
+        let description = EventDescription::<Option<Temperature>>::default();
+        assert!(description.retain_last);
+        let full = description
+            .into_full_description("temperature".to_owned())
+            .unwrap();
+        assert_eq!(full.at_type, Some("OverheatedEvent".to_owned()));
+        assert_eq!(
+            full.description,
+            Some("Temperature of the device".to_owned())
+        );
+        assert_eq!(full.enum_, Some(vec![json!(20.0), json!(80.0)]));
+        assert_eq!(full.maximum, Some(100.0));
+        assert_eq!(full.minimum, Some(-20.0));
+        assert_eq!(full.multiple_of, Some(0.5));
+        assert_eq!(full.title, Some("Temperature".to_owned()));
+        assert_eq!(full.type_, Some("number".to_owned()));
+        assert_eq!(full.unit, Some("degree celsius".to_owned()));
+    }
+
+    #[test]
+    fn test_option_description_builder() {
+        let full = EventDescription::<Option<f64>>::default()
+            .maximum(100)
+            .minimum(-20)
+            .unit("degree celsius")
+            .into_full_description("temperature".to_owned())
+            .unwrap();
+        assert_eq!(full.maximum, Some(100.0));
+        assert_eq!(full.minimum, Some(-20.0));
+        assert_eq!(full.type_, Some("number".to_owned()));
+        assert_eq!(full.unit, Some("degree celsius".to_owned()));
+    }
+
+    #[test]
+    fn test_serialize_addr() {
+        assert_eq!(
+            IpAddr::serialize("192.168.1.1".parse().unwrap()).unwrap(),
+            Some(json!("192.168.1.1"))
+        );
+        assert_eq!(
+            SocketAddr::serialize("[::1]:443".parse().unwrap()).unwrap(),
+            Some(json!("[::1]:443"))
+        );
+    }
 }
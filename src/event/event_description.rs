@@ -4,8 +4,18 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
  */
 
-use crate::{error::WebthingsError, event::Data, type_::Type};
-use std::marker::PhantomData;
+use crate::{
+    description::{parse_at_type, IntoFullDescription},
+    error::WebthingsError,
+    event::Data,
+    fingerprint::fingerprint,
+    type_::Type,
+};
+use serde::Deserialize;
+use std::{
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+};
 use webthings_gateway_ipc_types::{Event as FullEventDescription, Link};
 
 /// A struct which represents a WoT [event description][webthings_gateway_ipc_types::Event].
@@ -36,22 +46,31 @@ pub struct EventDescription<T: Data> {
     pub title: Option<String>,
     pub type_: Option<Type>,
     pub unit: Option<String>,
+    /// Keep the last raised event and re-send it on [resync][crate::DeviceHandle::resync].
+    ///
+    /// Not part of the WoT description, see [retain_last][EventDescription::retain_last].
+    pub retain_last: bool,
     _data: PhantomData<T>,
 }
 
 /// Possible values of `@type` for an [event][EventDescription].
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Deserialize)]
 pub enum AtType {
     AlarmEvent,
     DoublePressedEvent,
     LongPressedEvent,
     OverheatedEvent,
     PressedEvent,
+    /// A `@type` unknown to this crate, e.g. of a description parsed with `from_full`.
+    Other(String),
 }
 
 impl ToString for AtType {
     fn to_string(&self) -> String {
-        format!("{:?}", self)
+        match self {
+            AtType::Other(at_type) => at_type.clone(),
+            at_type => format!("{:?}", at_type),
+        }
     }
 }
 
@@ -70,6 +89,7 @@ impl<T: Data> EventDescription<T> {
             title: None,
             type_: T::type_(),
             unit: None,
+            retain_last: false,
             _data: PhantomData,
         };
         T::description(description)
@@ -108,19 +128,11 @@ impl<T: Data> EventDescription<T> {
     /// # Examples
     /// ```
     /// # use gateway_addon_rust::event::EventDescription;
-    /// # use webthings_gateway_ipc_types::Link;
+    /// # use gateway_addon_rust::link::{Link, LinkBuilder};
     /// # let _: EventDescription<i32> =
     /// EventDescription::default()
-    ///     .link(Link {
-    ///         href: "https://www.rust-lang.org/".to_owned(),
-    ///         media_type: None,
-    ///         rel: None,
-    ///     })
-    ///     .link(Link {
-    ///         href: "https://www.reddit.com/".to_owned(),
-    ///         media_type: None,
-    ///         rel: None,
-    ///     })
+    ///     .link(Link::new("https://www.rust-lang.org/"))
+    ///     .link(Link::new("https://www.reddit.com/"))
     /// # ;
     /// ```
     #[must_use]
@@ -133,6 +145,8 @@ impl<T: Data> EventDescription<T> {
     }
 
     /// Set `maximum`.
+    ///
+    /// Bounds are advertised as `f64`, so integer bounds beyond ±2^53 are rounded.
     #[must_use]
     pub fn maximum<F: Into<f64>>(mut self, maximum: F) -> Self {
         self.maximum = Some(maximum.into());
@@ -140,6 +154,8 @@ impl<T: Data> EventDescription<T> {
     }
 
     /// Set `minimum`.
+    ///
+    /// Bounds are advertised as `f64`, so integer bounds beyond ±2^53 are rounded.
     #[must_use]
     pub fn minimum<F: Into<f64>>(mut self, minimum: F) -> Self {
         self.minimum = Some(minimum.into());
@@ -181,11 +197,42 @@ impl<T: Data> EventDescription<T> {
         self
     }
 
+    /// Keep the last raised event, see [EventHandle::last][crate::EventHandle::last].
+    ///
+    /// Retained events are re-sent with their original timestamp on [resync][crate::DeviceHandle::resync],
+    /// similar to retained MQTT messages. By default, nothing is kept.
+    #[must_use]
+    pub fn retain_last(mut self, retain_last: bool) -> Self {
+        self.retain_last = retain_last;
+        self
+    }
+
+    /// A stable fingerprint of the serialized description.
+    ///
+    /// Use this to detect whether a description changed, e.g. across restarts.
+    /// Descriptions whose `enum` values cannot be serialized all share the same fingerprint.
+    pub fn fingerprint(&self) -> u64 {
+        fingerprint(&self.serialized())
+    }
+
+    fn serialized(&self) -> Option<serde_json::Value> {
+        let description = self.clone().into_full_description(String::new()).ok()?;
+        serde_json::to_value(description).ok()
+    }
+
     #[doc(hidden)]
     pub fn into_full_description(
         self,
         name: String,
     ) -> Result<FullEventDescription, WebthingsError> {
+        IntoFullDescription::into_full_description(self, name)
+    }
+}
+
+impl<T: Data> IntoFullDescription for EventDescription<T> {
+    type FullDescription = FullEventDescription;
+
+    fn into_full_description(self, name: String) -> Result<FullEventDescription, WebthingsError> {
         let enum_ = if let Some(enum_) = self.enum_ {
             let mut v = Vec::new();
             for e in enum_ {
@@ -214,3 +261,110 @@ impl<T: Data> EventDescription<T> {
         })
     }
 }
+
+impl EventDescription<serde_json::Value> {
+    /// Parse an existing [full event description][FullEventDescription],
+    /// e.g. one received from another WoT source.
+    ///
+    /// As [Data] can only be serialized, the data of the parsed event is kept as [serde_json::Value].
+    /// Unknown `@type`s are kept as [AtType::Other].
+    pub fn from_full(description: FullEventDescription) -> Result<Self, WebthingsError> {
+        let at_type = description
+            .at_type
+            .map(|at_type| parse_at_type(at_type, AtType::Other));
+        let type_ = if let Some(type_) = description.type_ {
+            Some(type_.parse()?)
+        } else {
+            None
+        };
+        Ok(Self {
+            at_type,
+            description: description.description,
+            enum_: description.enum_,
+            links: description.links,
+            maximum: description.maximum,
+            minimum: description.minimum,
+            multiple_of: description.multiple_of,
+            title: description.title,
+            type_,
+            unit: description.unit,
+            retain_last: false,
+            _data: PhantomData,
+        })
+    }
+}
+
+impl<T: Data> PartialEq for EventDescription<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.serialized() == other.serialized()
+    }
+}
+
+impl<T: Data> Eq for EventDescription<T> {}
+
+impl<T: Data> Hash for EventDescription<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.fingerprint());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{event::AtType, type_::Type, EventDescription};
+    use serde_json::json;
+
+    #[test]
+    fn test_from_full() {
+        let full = EventDescription::<serde_json::Value>::default()
+            .at_type(AtType::OverheatedEvent)
+            .type_(Type::Integer)
+            .enum_(vec![json!(1), json!(2)])
+            .unit("degree celsius")
+            .into_full_description("foo".to_owned())
+            .unwrap();
+        let description = EventDescription::from_full(full.clone()).unwrap();
+        assert_eq!(
+            description.into_full_description("foo".to_owned()).unwrap(),
+            full
+        );
+    }
+
+    #[test]
+    fn test_from_full_unknown_at_type() {
+        let mut full = EventDescription::<serde_json::Value>::default()
+            .into_full_description("foo".to_owned())
+            .unwrap();
+        full.at_type = Some("FooEvent".to_owned());
+        let description = EventDescription::from_full(full.clone()).unwrap();
+        assert!(
+            matches!(description.at_type, Some(AtType::Other(ref at_type)) if at_type == "FooEvent")
+        );
+        assert_eq!(
+            description.into_full_description("foo".to_owned()).unwrap(),
+            full
+        );
+    }
+
+    #[test]
+    fn test_from_full_unknown_type() {
+        let mut full = EventDescription::<serde_json::Value>::default()
+            .into_full_description("foo".to_owned())
+            .unwrap();
+        full.type_ = Some("foo".to_owned());
+        assert!(EventDescription::from_full(full).is_err());
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let description = EventDescription::<i32>::default().unit("percent");
+        assert_eq!(description.fingerprint(), description.clone().fingerprint());
+        assert!(description == EventDescription::<i32>::default().unit("percent"));
+        assert_ne!(
+            description.fingerprint(),
+            EventDescription::<i32>::default()
+                .unit("second")
+                .fingerprint()
+        );
+        assert!(description != EventDescription::<i32>::default().unit("second"));
+    }
+}
@@ -4,7 +4,12 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
  */
 
-use crate::{client::Client, error::WebthingsError, event::Data, Device, EventDescription};
+use crate::{
+    client::{self, Client},
+    error::WebthingsError,
+    event::{Data, NoData},
+    Device, EventDescription,
+};
 use as_any::{AsAny, Downcast};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
@@ -19,6 +24,8 @@ use webthings_gateway_ipc_types::{DeviceEventNotificationMessageData, Message};
 /// A struct which represents an instance of a WoT event.
 ///
 /// Use it to notify the gateway.
+///
+/// Prefer the id getters over the public id fields, which are going to become private.
 #[derive(Clone)]
 pub struct EventHandle<T: Data> {
     client: Arc<Mutex<Client>>,
@@ -29,9 +36,19 @@ pub struct EventHandle<T: Data> {
     pub device_id: String,
     pub name: String,
     pub description: EventDescription<T>,
+    last: Arc<std::sync::Mutex<Option<LastEvent<T>>>>,
+    created: SystemTime,
     _data: PhantomData<T>,
 }
 
+/// The last raised instance of a [retained][EventDescription::retain_last] event.
+struct LastEvent<T> {
+    timestamp: DateTime<Utc>,
+    /// `None` if the event was raised with serialized data.
+    data: Option<T>,
+    serialized: Option<serde_json::Value>,
+}
+
 impl<T: Data> EventHandle<T> {
     pub(crate) fn new(
         client: Arc<Mutex<Client>>,
@@ -50,14 +67,99 @@ impl<T: Data> EventHandle<T> {
             device_id,
             name,
             description,
+            last: Arc::new(std::sync::Mutex::new(None)),
+            created: SystemTime::now(),
             _data: PhantomData,
         }
     }
 
+    /// ID of the plugin this event belongs to.
+    pub fn plugin_id(&self) -> &str {
+        &self.plugin_id
+    }
+
+    /// ID of the adapter this event belongs to.
+    pub fn adapter_id(&self) -> &str {
+        &self.adapter_id
+    }
+
+    /// ID of the device this event belongs to.
+    pub fn device_id(&self) -> &str {
+        &self.device_id
+    }
+
+    /// Name of this event.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// When this event was registered.
+    pub fn created(&self) -> SystemTime {
+        self.created
+    }
+
     /// Raise a new event instance of this event.
     pub async fn raise(&self, data: T) -> Result<(), WebthingsError> {
-        let data = Data::serialize(data)?;
-        EventHandleBase::raise(self, data).await
+        let timestamp = SystemTime::now().into();
+        let serialized = Data::serialize(data.clone())?;
+        let message = self.message_at(serialized.clone(), timestamp);
+        self.retain(timestamp, Some(data), serialized);
+        client::send_message(&self.client, &message).await
+    }
+
+    /// The time and data of the last raised event instance.
+    ///
+    /// Only available if the event is [retained][EventDescription::retain_last]
+    /// and was raised with typed data, i.e. not via [EventHandleBase::raise].
+    pub fn last(&self) -> Option<(DateTime<Utc>, T)> {
+        let last = self.last.lock().unwrap();
+        let last = last.as_ref()?;
+        Some((last.timestamp, last.data.clone()?))
+    }
+
+    /// The time of the last raised event instance, if the event is [retained][EventDescription::retain_last].
+    pub fn last_raised(&self) -> Option<DateTime<Utc>> {
+        self.last
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|last| last.timestamp)
+    }
+
+    fn retain(
+        &self,
+        timestamp: DateTime<Utc>,
+        data: Option<T>,
+        serialized: Option<serde_json::Value>,
+    ) {
+        if self.description.retain_last {
+            *self.last.lock().unwrap() = Some(LastEvent {
+                timestamp,
+                data,
+                serialized,
+            });
+        }
+    }
+
+    fn message_at(&self, data: Option<serde_json::Value>, timestamp: DateTime<Utc>) -> Message {
+        DeviceEventNotificationMessageData {
+            plugin_id: self.plugin_id.clone(),
+            device_id: self.device_id.clone(),
+            adapter_id: self.adapter_id.clone(),
+            event: webthings_gateway_ipc_types::EventDescription {
+                data,
+                name: self.name.clone(),
+                timestamp: timestamp.to_rfc3339(),
+            },
+        }
+        .into()
+    }
+}
+
+impl EventHandle<NoData> {
+    /// Raise a new event instance of this event without any data.
+    pub async fn raise_empty(&self) -> Result<(), WebthingsError> {
+        self.raise(NoData).await
     }
 }
 
@@ -74,6 +176,12 @@ pub trait EventHandleBase: Send + Sync + AsAny + 'static {
     ///
     /// Make sure that the type of the provided data is compatible.
     async fn raise(&self, data: Option<serde_json::Value>) -> Result<(), WebthingsError>;
+
+    #[doc(hidden)]
+    fn event_message(&self, data: Option<serde_json::Value>) -> Message;
+
+    #[doc(hidden)]
+    fn last_message(&self) -> Option<Message>;
 }
 
 impl Downcast for dyn EventHandleBase {}
@@ -81,22 +189,22 @@ impl Downcast for dyn EventHandleBase {}
 #[async_trait]
 impl<D: Data> EventHandleBase for EventHandle<D> {
     async fn raise(&self, data: Option<serde_json::Value>) -> Result<(), WebthingsError> {
-        let time: DateTime<Utc> = SystemTime::now().into();
-        let message: Message = DeviceEventNotificationMessageData {
-            plugin_id: self.plugin_id.clone(),
-            device_id: self.device_id.clone(),
-            adapter_id: self.adapter_id.clone(),
-            event: webthings_gateway_ipc_types::EventDescription {
-                data,
-                name: self.name.clone(),
-                timestamp: time.to_rfc3339(),
-            },
-        }
-        .into();
-
-        self.client.lock().await.send_message(&message).await?;
+        let message = self.event_message(data);
+        client::send_message(&self.client, &message).await?;
         Ok(())
     }
+
+    fn event_message(&self, data: Option<serde_json::Value>) -> Message {
+        let timestamp = SystemTime::now().into();
+        self.retain(timestamp, None, data.clone());
+        self.message_at(data, timestamp)
+    }
+
+    fn last_message(&self) -> Option<Message> {
+        let last = self.last.lock().unwrap();
+        let last = last.as_ref()?;
+        Some(self.message_at(last.serialized.clone(), last.timestamp))
+    }
 }
 
 #[cfg(test)]
@@ -162,4 +270,64 @@ mod tests {
 
         event.raise(data).await.unwrap();
     }
+
+    #[tokio::test]
+    async fn test_raise_empty_event() {
+        let client = Arc::new(Mutex::new(Client::new()));
+
+        let event = EventHandle::<NoData>::new(
+            client.clone(),
+            Weak::new(),
+            PLUGIN_ID.to_owned(),
+            ADAPTER_ID.to_owned(),
+            DEVICE_ID.to_owned(),
+            EVENT_NAME.to_owned(),
+            EventDescription::default(),
+        );
+
+        client
+            .lock()
+            .await
+            .expect_send_message()
+            .withf(move |msg| match msg {
+                Message::DeviceEventNotification(msg) => {
+                    msg.data.event.name == EVENT_NAME && msg.data.event.data.is_none()
+                }
+                _ => false,
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+
+        event.raise_empty().await.unwrap();
+    }
+
+    #[rstest]
+    #[case(true)]
+    #[case(false)]
+    #[tokio::test]
+    async fn test_retain_last_no_data(#[case] retain_last: bool) {
+        let client = Arc::new(Mutex::new(Client::new()));
+
+        let event = EventHandle::<NoData>::new(
+            client.clone(),
+            Weak::new(),
+            PLUGIN_ID.to_owned(),
+            ADAPTER_ID.to_owned(),
+            DEVICE_ID.to_owned(),
+            EVENT_NAME.to_owned(),
+            EventDescription::default().retain_last(retain_last),
+        );
+
+        client
+            .lock()
+            .await
+            .expect_send_message()
+            .times(1)
+            .returning(|_| Ok(()));
+
+        assert!(event.last().is_none());
+        event.raise_empty().await.unwrap();
+        assert_eq!(event.last().is_some(), retain_last);
+        assert_eq!(event.last_raised().is_some(), retain_last);
+    }
 }
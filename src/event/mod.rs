@@ -6,21 +6,30 @@
 
 //! A module for everything related to WoT events.
 
+#[cfg(feature = "runtime")]
 mod event_builder;
 mod event_data;
 mod event_description;
+#[cfg(feature = "runtime")]
 mod event_handle;
+#[cfg(feature = "runtime")]
 mod event_macro;
+#[cfg(feature = "runtime")]
 mod event_trait;
 
+#[cfg(feature = "runtime")]
 pub use event_builder::*;
 pub use event_data::*;
 pub use event_description::*;
+#[cfg(feature = "runtime")]
 pub use event_handle::*;
+#[cfg(feature = "runtime")]
 pub use event_macro::*;
+#[cfg(feature = "runtime")]
 pub use event_trait::*;
 
 /// Convenience type for a collection of [EventBuilderBase].
+#[cfg(feature = "runtime")]
 pub type Events = Vec<Box<dyn EventBuilderBase>>;
 
 /// Convenience macro for building an [Events].
@@ -31,6 +40,7 @@ pub type Events = Vec<Box<dyn EventBuilderBase>>;
 /// events![ExampleEvent::new()]
 /// # ;
 /// ```
+#[cfg(feature = "runtime")]
 #[macro_export]
 macro_rules! events [
     ($($e:expr),*) => ({
@@ -39,7 +49,7 @@ macro_rules! events [
     })
 ];
 
-#[cfg(test)]
+#[cfg(all(test, feature = "runtime"))]
 pub(crate) mod tests {
     pub use super::{event_builder::tests::*, event_trait::tests::*};
 }
@@ -25,7 +25,7 @@ use crate::{
 use as_any::Downcast;
 use async_trait::async_trait;
 
-#[tokio::main]
+#[tokio::main(flavor = "current_thread")]
 pub async fn main() -> Result<(), WebthingsError> {
     let mut plugin = connect("example-addon").await?;
     let adapter = plugin.add_adapter(ExampleAdapter::new()).await?;
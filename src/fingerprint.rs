@@ -0,0 +1,48 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+
+//! A module for fingerprinting WoT descriptions.
+
+use serde::Serialize;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Compute a stable fingerprint of the json serialization of a value,
+/// e.g. of a [full device description][webthings_gateway_ipc_types::Device].
+///
+/// Unlike [std::hash::Hash] with the default hasher, the fingerprint does not change between
+/// runs or Rust versions, so it can be persisted to detect changed descriptions.
+///
+/// # Examples
+/// ```
+/// # use gateway_addon_rust::fingerprint::fingerprint;
+/// # use serde_json::json;
+/// assert_eq!(fingerprint(&json!({"a": 1})), fingerprint(&json!({"a": 1})));
+/// assert_ne!(fingerprint(&json!({"a": 1})), fingerprint(&json!({"a": 2})));
+/// ```
+pub fn fingerprint<T: Serialize>(value: &T) -> u64 {
+    let serialized = serde_json::to_vec(value).unwrap_or_default();
+    serialized.iter().fold(FNV_OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(FNV_PRIME)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fingerprint;
+    use serde_json::json;
+
+    #[test]
+    fn test_fingerprint_is_stable() {
+        assert_eq!(fingerprint(&json!(null)), 0x5b9b_c4ba_5281_08e4);
+    }
+
+    #[test]
+    fn test_fingerprint_differs() {
+        assert_ne!(fingerprint(&json!({"a": 1})), fingerprint(&json!({"a": 2})));
+    }
+}
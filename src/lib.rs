@@ -7,23 +7,45 @@
 //! This crate makes it possible to write addons for the WebthingsIO gateway in Rust.
 //!
 //! To get started, have a look at a [complete example](https://github.com/WebThingsIO/example-adapter-rust).
+//!
+//! # Features
+//! - `runtime` (default): Everything needed to connect to the gateway.
+//!   Disable the default features to only build the description types, i.e. the
+//!   `*Description` structs and the [Value][property::Value], [Data][event::Data] and
+//!   [Input][action::Input] traits, without tokio and the websocket stack.
+//! - `cancellation`: Cancellation tokens for [actions][action::Action].
+//! - `tls`: Connect to the gateway via `wss://`.
+//! - `testing`: Helpers for testing addons.
 
 pub mod action;
+#[cfg(feature = "runtime")]
 pub mod adapter;
+#[cfg(feature = "runtime")]
 pub mod api_handler;
+pub mod bytes;
+#[cfg(feature = "runtime")]
 #[doc(hidden)]
 pub mod client;
+#[cfg(feature = "runtime")]
 pub mod database;
+pub mod description;
 pub mod device;
 pub mod error;
 pub mod event;
 #[cfg(not(test))]
 #[cfg(debug_assertions)]
+#[cfg(feature = "runtime")]
 #[doc(hidden)]
 pub mod example;
+pub mod fingerprint;
+pub mod link;
+#[cfg(feature = "runtime")]
 pub(crate) mod message_handler;
+#[cfg(feature = "runtime")]
 pub mod plugin;
 pub mod property;
+#[cfg(feature = "runtime")]
+pub(crate) mod runtime;
 pub mod type_;
 
 /// The purpose of this module is to condense imports almost every addon requires.
@@ -35,21 +57,48 @@ pub mod type_;
 /// ```
 pub mod prelude {
     pub use crate::{
-        action::{self, Action, ActionDescription, ActionHandle, Actions},
+        action::{self, ActionDescription},
+        device::DeviceDescription,
+        event::{self, EventDescription},
+        property::{self, PropertyDescription},
+    };
+
+    #[cfg(feature = "runtime")]
+    pub use crate::{
+        action::{Action, ActionHandle, Actions},
         actions,
         adapter::{adapter, Adapter, AdapterHandle, AdapterStructure, BuiltAdapter},
-        device::{device, BuiltDevice, Device, DeviceDescription, DeviceHandle, DeviceStructure},
-        event::{
-            self, event, BuiltEvent, Event, EventDescription, EventHandle, EventStructure, Events,
-        },
+        device::{device, BuiltDevice, Device, DeviceHandle, DeviceStructure},
+        event::{event, BuiltEvent, Event, EventHandle, EventStructure, Events},
         events,
         plugin::Plugin,
         properties,
         property::{
-            self, property, BuiltProperty, Properties, Property, PropertyDescription,
-            PropertyHandle, PropertyStructure,
+            property, BuiltProperty, Properties, Property, PropertyHandle, PropertyStructure,
         },
     };
+
+    /// Mocks, fixtures and helpers for testing, so a test module needs a single `use`.
+    #[cfg(all(test, feature = "runtime"))]
+    #[allow(unused_imports)]
+    pub(crate) mod testing {
+        pub(crate) use crate::{
+            action::tests::*,
+            adapter::tests::*,
+            api_handler::tests::*,
+            client::Client,
+            device::{device_message_handler::tests::invoke_action_typed, tests::*},
+            event::tests::*,
+            message_handler::{MessageHandler, MessageResult},
+            plugin::tests::*,
+            property::tests::*,
+            Plugin,
+        };
+        pub(crate) use as_any::Downcast;
+        pub(crate) use rstest::{fixture, rstest};
+        pub(crate) use serde_json::json;
+        pub(crate) use webthings_gateway_ipc_types::Message;
+    }
 }
 
 pub use prelude::*;
@@ -0,0 +1,124 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+
+//! A module for building WoT links.
+
+use std::fmt;
+pub use webthings_gateway_ipc_types::Link;
+
+/// Builder methods for a WoT [link][Link].
+///
+/// Already implemented for [Link]. **You never have to implement this trait yourself.**
+///
+/// # Examples
+/// ```
+/// # use gateway_addon_rust::{prelude::*, link::{Link, LinkBuilder, Rel}};
+/// # let _ =
+/// DeviceDescription::default().link(
+///     Link::new("https://www.rust-lang.org/")
+///         .rel(Rel::Alternate)
+///         .media_type("text/html"),
+/// )
+/// # ;
+/// ```
+pub trait LinkBuilder {
+    /// Build a [Link] to the given `href`.
+    fn new(href: impl Into<String>) -> Self;
+
+    /// Set `rel`.
+    #[must_use]
+    fn rel(self, rel: impl Into<Rel>) -> Self;
+
+    /// Set `mediaType`.
+    #[must_use]
+    fn media_type(self, media_type: impl Into<String>) -> Self;
+}
+
+impl LinkBuilder for Link {
+    fn new(href: impl Into<String>) -> Self {
+        Self {
+            href: href.into(),
+            media_type: None,
+            rel: None,
+        }
+    }
+
+    fn rel(mut self, rel: impl Into<Rel>) -> Self {
+        self.rel = Some(rel.into().to_string());
+        self
+    }
+
+    fn media_type(mut self, media_type: impl Into<String>) -> Self {
+        self.media_type = Some(media_type.into());
+        self
+    }
+}
+
+/// Possible values of `rel` for a [link][Link].
+///
+/// Any other relation type can be used via [Rel::Custom] or by converting from a string.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Rel {
+    Alternate,
+    Collection,
+    Describedby,
+    Icon,
+    Item,
+    Type,
+    Custom(String),
+}
+
+impl fmt::Display for Rel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Rel::Alternate => "alternate",
+            Rel::Collection => "collection",
+            Rel::Describedby => "describedby",
+            Rel::Icon => "icon",
+            Rel::Item => "item",
+            Rel::Type => "type",
+            Rel::Custom(rel) => rel,
+        })
+    }
+}
+
+impl From<String> for Rel {
+    fn from(rel: String) -> Self {
+        Rel::Custom(rel)
+    }
+}
+
+impl From<&str> for Rel {
+    fn from(rel: &str) -> Self {
+        Rel::Custom(rel.to_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::link::{Link, LinkBuilder, Rel};
+
+    #[test]
+    fn test_link_builder() {
+        let link = Link::new("https://www.rust-lang.org/")
+            .rel(Rel::Alternate)
+            .media_type("text/html");
+        assert_eq!(
+            link,
+            Link {
+                href: "https://www.rust-lang.org/".to_owned(),
+                media_type: Some("text/html".to_owned()),
+                rel: Some("alternate".to_owned()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_custom_rel() {
+        let link = Link::new("https://www.rust-lang.org/").rel("license");
+        assert_eq!(link.rel, Some("license".to_owned()));
+    }
+}
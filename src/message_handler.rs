@@ -1,13 +1,17 @@
+use crate::error::HandleError;
 use async_trait::async_trait;
 use webthings_gateway_ipc_types::Message as IPCMessage;
 
-#[doc(hidden)]
-pub(crate) enum MessageResult {
+/// The outcome of handling a message, see [Plugin::dispatch][crate::Plugin::dispatch].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageResult {
+    /// Keep reading messages.
     Continue,
+    /// The gateway requested to unload the plugin, stop reading messages.
     Terminate,
 }
 
 #[async_trait]
 pub(crate) trait MessageHandler {
-    async fn handle_message(&mut self, message: IPCMessage) -> Result<MessageResult, String>;
+    async fn handle_message(&mut self, message: IPCMessage) -> Result<MessageResult, HandleError>;
 }
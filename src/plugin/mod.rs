@@ -6,12 +6,30 @@
 
 //! Connection to the WebthingsIO gateway.
 
+mod plugin_builder;
 mod plugin_connection;
+mod plugin_dispatcher;
+mod plugin_gateway_version;
+mod plugin_health;
 pub(crate) mod plugin_message_handler;
 mod plugin_struct;
+#[cfg(feature = "tls")]
+mod plugin_tls;
 
+pub use plugin_builder::*;
 pub use plugin_connection::*;
+pub(crate) use plugin_dispatcher::{mailbox_key, Mailboxes};
+pub use plugin_gateway_version::*;
+pub(crate) use plugin_health::HealthTracker;
+pub use plugin_health::{IoStats, PluginHealth};
 pub use plugin_struct::*;
+#[cfg(feature = "tls")]
+pub use plugin_tls::*;
+
+pub use crate::message_handler::MessageResult;
+
+/// Re-exported so that addons use the same version of `semver` as this crate.
+pub use semver::Version;
 
 #[cfg(test)]
 pub(crate) mod tests {
@@ -0,0 +1,339 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+
+use crate::{
+    client::{self, QueueConfig, RetryPolicy},
+    error::WebthingsError,
+    plugin::{connect_to, ExitStrategy, MessageFilter},
+    Plugin,
+};
+use std::time::Duration;
+use tokio::runtime::Handle;
+use webthings_gateway_ipc_types::Message;
+
+pub(crate) const GATEWAY_URL: &str = "ws://localhost:9500";
+pub(crate) const REGISTER_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Connector for `wss://` urls, which can only be configured with the `tls` feature.
+#[cfg(feature = "tls")]
+pub(crate) type Connector = tokio_tungstenite::Connector;
+#[cfg(not(feature = "tls"))]
+pub(crate) type Connector = std::convert::Infallible;
+
+type MessageTap = Box<dyn Fn(&Message) + Send + Sync>;
+
+/// Settings which are applied to the client before the plugin registers with the gateway.
+///
+/// The mocked connection of the tests ignores the retry policy, queue config and runtime.
+#[derive(Default)]
+#[cfg_attr(test, allow(dead_code))]
+pub(crate) struct ConnectSettings {
+    pub(crate) log_prefix: Option<String>,
+    pub(crate) retry_policy: Option<RetryPolicy>,
+    pub(crate) queue_config: Option<QueueConfig>,
+    pub(crate) message_tap: Option<client::MessageTap>,
+    pub(crate) runtime: Option<Handle>,
+}
+
+/// A builder which configures a [plugin][Plugin] before it connects to the gateway.
+///
+/// The log prefix, retry policy, queue config, message tap and runtime are applied before the plugin
+/// registers with the gateway, so they already cover the registration. Everything else is applied
+/// before the first message is handled.
+/// Use [connect][crate::plugin::connect] if the defaults are fine.
+///
+/// # Examples
+/// ```no_run
+/// # use gateway_addon_rust::{error::WebthingsError, Plugin};
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), WebthingsError> {
+/// let mut plugin = Plugin::builder("example-addon")
+///     .gateway_url("ws://localhost:9500")
+///     .concurrency_limit(4)
+///     .connect()
+///     .await?;
+/// plugin.event_loop().await;
+/// #   Ok(())
+/// # }
+/// ```
+pub struct PluginBuilder {
+    plugin_id: String,
+    package_name: Option<String>,
+    gateway_url: String,
+    register_timeout: Duration,
+    log_prefix: Option<String>,
+    retry_policy: Option<RetryPolicy>,
+    queue_config: Option<QueueConfig>,
+    message_tap: Option<MessageTap>,
+    concurrency_limit: Option<usize>,
+    runtime: Option<Handle>,
+    exit_strategy: Option<ExitStrategy>,
+    message_filter: Option<MessageFilter>,
+    #[cfg(feature = "tls")]
+    tls_config: Option<crate::plugin::TlsConfig>,
+}
+
+/// # Builder methods
+impl PluginBuilder {
+    /// Build a new [PluginBuilder] with the default settings.
+    pub fn new(plugin_id: impl Into<String>) -> Self {
+        Self {
+            plugin_id: plugin_id.into(),
+            package_name: None,
+            gateway_url: GATEWAY_URL.to_owned(),
+            register_timeout: REGISTER_TIMEOUT,
+            log_prefix: None,
+            retry_policy: None,
+            queue_config: None,
+            message_tap: None,
+            concurrency_limit: None,
+            runtime: None,
+            exit_strategy: None,
+            message_filter: None,
+            #[cfg(feature = "tls")]
+            tls_config: None,
+        }
+    }
+
+    /// Set the url of the gateway. Defaults to `ws://localhost:9500`.
+    #[must_use]
+    pub fn gateway_url(mut self, gateway_url: impl Into<String>) -> Self {
+        self.gateway_url = gateway_url.into();
+        self
+    }
+
+    /// Set how long to wait for the gateway to answer the registration of the plugin. Defaults to 30 seconds.
+    ///
+    /// [connect][PluginBuilder::connect] fails with [WebthingsError::RegisterTimeout] afterwards.
+    #[must_use]
+    pub fn register_timeout(mut self, register_timeout: Duration) -> Self {
+        self.register_timeout = register_timeout;
+        self
+    }
+
+    /// See [Plugin::set_package_name].
+    #[must_use]
+    pub fn package_name(mut self, package_name: impl Into<String>) -> Self {
+        self.package_name = Some(package_name.into());
+        self
+    }
+
+    /// See [Plugin::set_log_prefix].
+    #[must_use]
+    pub fn log_prefix(mut self, log_prefix: impl Into<String>) -> Self {
+        self.log_prefix = Some(log_prefix.into());
+        self
+    }
+
+    /// See [Plugin::set_retry_policy].
+    #[must_use]
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(retry_policy);
+        self
+    }
+
+    /// See [Plugin::set_queue_config].
+    #[must_use]
+    pub fn queue_config(mut self, queue_config: QueueConfig) -> Self {
+        self.queue_config = Some(queue_config);
+        self
+    }
+
+    /// See [Plugin::set_message_tap].
+    #[must_use]
+    pub fn message_tap(mut self, message_tap: MessageTap) -> Self {
+        self.message_tap = Some(message_tap);
+        self
+    }
+
+    /// See [Plugin::set_runtime].
+    #[must_use]
+    pub fn runtime(mut self, runtime: Handle) -> Self {
+        self.runtime = Some(runtime);
+        self
+    }
+
+    /// See [Plugin::set_message_filter].
+    #[must_use]
+    pub fn message_filter(mut self, message_filter: MessageFilter) -> Self {
+        self.message_filter = Some(message_filter);
+        self
+    }
+
+    /// See [Plugin::set_exit_strategy].
+    #[must_use]
+    pub fn exit_strategy(mut self, exit_strategy: ExitStrategy) -> Self {
+        self.exit_strategy = Some(exit_strategy);
+        self
+    }
+
+    /// See [Plugin::set_concurrency_limit].
+    #[must_use]
+    pub fn concurrency_limit(mut self, concurrency_limit: usize) -> Self {
+        self.concurrency_limit = Some(concurrency_limit);
+        self
+    }
+
+    /// Set the [TLS settings][crate::plugin::TlsConfig] used if the [gateway url][PluginBuilder::gateway_url] is a `wss://` url.
+    #[cfg(feature = "tls")]
+    #[must_use]
+    pub fn tls_config(mut self, tls_config: crate::plugin::TlsConfig) -> Self {
+        self.tls_config = Some(tls_config);
+        self
+    }
+}
+
+impl PluginBuilder {
+    /// Connect to the gateway and create the configured [plugin][Plugin].
+    pub async fn connect(self) -> Result<Plugin, WebthingsError> {
+        let connector = self.connector()?;
+        let settings = ConnectSettings {
+            log_prefix: self.log_prefix,
+            retry_policy: self.retry_policy,
+            queue_config: self.queue_config,
+            message_tap: self.message_tap.map(Into::into),
+            runtime: self.runtime.clone(),
+        };
+        let mut plugin = connect_to(
+            self.plugin_id,
+            &self.gateway_url,
+            self.register_timeout,
+            connector,
+            settings,
+        )
+        .await?;
+        if let Some(package_name) = self.package_name {
+            plugin.set_package_name(package_name).await;
+        }
+        if let Some(runtime) = self.runtime {
+            plugin.set_runtime(runtime);
+        }
+        if let Some(message_filter) = self.message_filter {
+            plugin.set_message_filter(message_filter);
+        }
+        if let Some(exit_strategy) = self.exit_strategy {
+            plugin.set_exit_strategy(exit_strategy);
+        }
+        plugin.set_concurrency_limit(self.concurrency_limit);
+        Ok(plugin)
+    }
+
+    #[cfg(feature = "tls")]
+    fn connector(&self) -> Result<Option<Connector>, WebthingsError> {
+        self.tls_config
+            .as_ref()
+            .map(|tls_config| tls_config.connector().map(Connector::NativeTls))
+            .transpose()
+    }
+
+    #[cfg(not(feature = "tls"))]
+    fn connector(&self) -> Result<Option<Connector>, WebthingsError> {
+        Ok(None)
+    }
+}
+
+impl Plugin {
+    /// Build a [plugin][Plugin] using a [PluginBuilder].
+    pub fn builder(plugin_id: impl Into<String>) -> PluginBuilder {
+        PluginBuilder::new(plugin_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        client::{QueueConfig, RetryPolicy},
+        error::WebthingsError,
+        plugin::{GATEWAY_URL, REGISTER_TIMEOUT},
+        Plugin,
+    };
+    use std::time::Duration;
+
+    const PLUGIN_ID: &str = "plugin_id";
+
+    #[test]
+    fn test_builder_defaults() {
+        let builder = Plugin::builder(PLUGIN_ID);
+        assert_eq!(builder.plugin_id, PLUGIN_ID);
+        assert_eq!(builder.gateway_url, GATEWAY_URL);
+        assert_eq!(builder.register_timeout, REGISTER_TIMEOUT);
+        assert!(builder.log_prefix.is_none());
+        assert!(builder.retry_policy.is_none());
+        assert!(builder.queue_config.is_none());
+        assert!(builder.message_tap.is_none());
+        assert!(builder.concurrency_limit.is_none());
+        assert!(builder.runtime.is_none());
+        assert!(builder.exit_strategy.is_none());
+        assert!(builder.message_filter.is_none());
+    }
+
+    #[test]
+    fn test_builder_methods() {
+        let retry_policy = RetryPolicy {
+            retries: 3,
+            backoff: Duration::from_millis(50),
+        };
+        let builder = Plugin::builder(PLUGIN_ID)
+            .gateway_url("ws://gateway:9500")
+            .register_timeout(Duration::from_secs(5))
+            .log_prefix("foo: ")
+            .retry_policy(retry_policy)
+            .queue_config(QueueConfig::default())
+            .message_tap(Box::new(|_| {}))
+            .exit_strategy(Box::new(|_| {}))
+            .message_filter(Box::new(|_| true))
+            .concurrency_limit(4);
+        assert_eq!(builder.gateway_url, "ws://gateway:9500");
+        assert_eq!(builder.register_timeout, Duration::from_secs(5));
+        assert_eq!(builder.log_prefix, Some("foo: ".to_owned()));
+        assert_eq!(builder.retry_policy, Some(retry_policy));
+        assert_eq!(builder.queue_config, Some(QueueConfig::default()));
+        assert!(builder.message_tap.is_some());
+        assert!(builder.exit_strategy.is_some());
+        assert!(builder.message_filter.is_some());
+        assert_eq!(builder.concurrency_limit, Some(4));
+    }
+
+    #[tokio::test]
+    async fn test_builder_connect() {
+        let plugin = Plugin::builder(PLUGIN_ID)
+            .concurrency_limit(4)
+            .connect()
+            .await
+            .unwrap();
+        assert_eq!(plugin.plugin_id(), PLUGIN_ID);
+        assert!(plugin.dispatcher.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_builder_connect_settings() {
+        let plugin = Plugin::builder(PLUGIN_ID)
+            .log_prefix("foo: ")
+            .message_tap(Box::new(|_| {}))
+            .connect()
+            .await
+            .unwrap();
+        assert_eq!(plugin.log_prefix(), "foo: ");
+        assert!(plugin.message_tap.is_some());
+        assert_eq!(
+            plugin
+                .api_handler
+                .lock()
+                .await
+                .api_handler_handle()
+                .log_prefix,
+            "foo: "
+        );
+    }
+
+    #[tokio::test]
+    async fn test_builder_connect_invalid_id() {
+        assert!(matches!(
+            Plugin::builder("foo\tbar").connect().await,
+            Err(WebthingsError::InvalidId(id)) if id == "foo\tbar"
+        ));
+    }
+}
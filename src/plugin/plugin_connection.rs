@@ -4,21 +4,37 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
  */
 
+use crate::plugin::RawFrameHook;
 use mockall_double::double;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+/// Invoke the given [raw frame hook][crate::Plugin::set_raw_frame_hook], if any, without letting a panic escape.
+pub(crate) fn inspect_frame(raw_frame_hook: Option<&RawFrameHook>, frame: &str) {
+    if let Some(raw_frame_hook) = raw_frame_hook {
+        if catch_unwind(AssertUnwindSafe(|| raw_frame_hook(frame))).is_err() {
+            log::error!("Raw frame hook panicked on frame {}", frame);
+        }
+    }
+}
 
 mod double {
     #[cfg(not(test))]
     pub mod plugin {
         use crate::{
             api_handler::{ApiHandlerBuilder, ApiHandlerHandle, NoopApiHandler},
-            client::Client,
+            client::{tap_message, Client},
+            device::validate_id,
             error::WebthingsError,
+            plugin::{
+                default_log_prefix, inspect_frame, ConnectSettings, Connector, HealthTracker,
+                Mailboxes, RawFrameHook, GATEWAY_URL, REGISTER_TIMEOUT,
+            },
             Plugin,
         };
         use futures::stream::{SplitStream, StreamExt};
-        use std::{collections::HashMap, str::FromStr, sync::Arc};
-        use tokio::{net::TcpStream, sync::Mutex};
-        use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+        use std::{collections::HashMap, str::FromStr, sync::Arc, time::Duration};
+        use tokio::{net::TcpStream, sync::Mutex, time::timeout};
+        use tokio_tungstenite::{connect_async, tungstenite, MaybeTlsStream, WebSocketStream};
         use url::Url;
         use webthings_gateway_ipc_types::{
             Message as IPCMessage, PluginRegisterRequestMessageData,
@@ -26,17 +42,56 @@ mod double {
         };
 
         pub(crate) type PluginStream = SplitStream<WebSocketStream<MaybeTlsStream<TcpStream>>>;
-        const GATEWAY_URL: &str = "ws://localhost:9500";
 
         /// Connect to a WebthingsIO gateway and create a new [plugin][Plugin].
+        ///
+        /// Use [Plugin::builder] to configure the plugin before connecting.
+        ///
+        /// Fails with [WebthingsError::InvalidId] if the plugin id is empty or contains control characters.
+        ///
+        /// Fails with [WebthingsError::RegisterTimeout] if the gateway does not answer the registration within 30 seconds.
         pub async fn connect(plugin_id: impl Into<String>) -> Result<Plugin, WebthingsError> {
-            let plugin_id = plugin_id.into();
-            let url = Url::parse(GATEWAY_URL).expect("Could not parse url");
+            connect_to(
+                plugin_id.into(),
+                GATEWAY_URL,
+                REGISTER_TIMEOUT,
+                None,
+                ConnectSettings::default(),
+            )
+            .await
+        }
 
-            let (socket, _) = connect_async(url).await.map_err(WebthingsError::Connect)?;
+        pub(crate) async fn connect_to(
+            plugin_id: String,
+            gateway_url: &str,
+            register_timeout: Duration,
+            connector: Option<Connector>,
+            settings: ConnectSettings,
+        ) -> Result<Plugin, WebthingsError> {
+            validate_id(&plugin_id)?;
+
+            let log_prefix = settings
+                .log_prefix
+                .unwrap_or_else(|| default_log_prefix(&plugin_id));
+            let url = Url::parse(gateway_url).map_err(WebthingsError::InvalidUrl)?;
+
+            let socket = connect_socket(url, connector)
+                .await
+                .map_err(WebthingsError::Connect)?;
 
             let (sink, mut stream) = socket.split();
-            let mut client = Client::new(sink);
+            let health = Arc::new(HealthTracker::default());
+            let mut client = Client::new(sink, settings.runtime.as_ref());
+            client.set_log_prefix(log_prefix.clone());
+            client.set_health_tracker(health.clone());
+            if let Some(retry_policy) = settings.retry_policy {
+                client.set_retry_policy(retry_policy);
+            }
+            if let Some(queue_config) = settings.queue_config {
+                client.set_queue_config(queue_config);
+            }
+            let message_tap = settings.message_tap;
+            client.set_message_tap(message_tap.clone());
 
             let message: IPCMessage = PluginRegisterRequestMessageData {
                 plugin_id: plugin_id.clone(),
@@ -46,50 +101,117 @@ mod double {
             client.send_message(&message).await?;
 
             let PluginRegisterResponseMessageData {
-                gateway_version: _,
+                gateway_version,
                 plugin_id: _,
                 preferences,
                 user_profile,
-            } = loop {
-                match read(&mut stream).await {
-                    None => {}
-                    Some(result) => match result {
-                        Ok(IPCMessage::PluginRegisterResponse(msg)) => {
-                            break msg.data;
+            } = timeout(register_timeout, async {
+                loop {
+                    match read(&mut stream, &log_prefix, &health, None).await {
+                        None => return Err(WebthingsError::RegisterConnectionClosed),
+                        Some(Ok(msg)) => {
+                            tap_message(&message_tap, &log_prefix, &msg);
+                            match msg {
+                                IPCMessage::PluginRegisterResponse(msg) => return Ok(msg.data),
+                                msg => log::warn!(
+                                    "{}Received unexpected message {:?}",
+                                    log_prefix,
+                                    msg
+                                ),
+                            }
                         }
-                        Ok(msg) => {
-                            log::warn!("Received unexpected message {:?}", msg);
+                        Some(Err(err)) => {
+                            log::error!("{}Could not read message: {}", log_prefix, err)
                         }
-                        Err(err) => log::error!("Could not read message: {}", err),
-                    },
+                    }
                 }
-            };
+            })
+            .await
+            .map_err(|_| WebthingsError::RegisterTimeout)??;
 
             let client = Arc::new(Mutex::new(client));
+            let mut api_handler_handle = ApiHandlerHandle::new(client.clone(), plugin_id.clone());
+            api_handler_handle.log_prefix = log_prefix.clone();
             let api_handler = Arc::new(Mutex::new(NoopApiHandler::build(
                 NoopApiHandler,
-                ApiHandlerHandle::new(client.clone(), plugin_id.clone()),
+                api_handler_handle,
             )));
 
             Ok(Plugin {
+                gateway_version,
+                package_name: plugin_id.clone(),
                 plugin_id,
                 preferences,
                 user_profile,
                 client,
-                stream,
+                stream: Arc::new(Mutex::new(stream)),
                 adapters: HashMap::new(),
                 api_handler,
+                log_prefix,
+                message_tap,
+                raw_frame_hook: None,
+                addressed_message_handler: None,
+                unknown_message_handler: None,
+                exit_strategy: None,
+                message_filter: None,
+                dispatcher: None,
+                mailboxes: Mailboxes::default(),
+                health,
+                runtime: None,
             })
         }
 
-        pub(crate) async fn read(stream: &mut PluginStream) -> Option<Result<IPCMessage, String>> {
+        /// Open the websocket, using the given connector for `wss://` urls.
+        async fn connect_socket(
+            url: Url,
+            connector: Option<Connector>,
+        ) -> Result<WebSocketStream<MaybeTlsStream<TcpStream>>, tungstenite::Error> {
+            #[cfg(feature = "tls")]
+            if let Some(connector) = connector {
+                return connect_socket_tls(url, connector).await;
+            }
+            #[cfg(not(feature = "tls"))]
+            let _ = connector;
+            connect_async(url).await.map(|(socket, _)| socket)
+        }
+
+        #[cfg(feature = "tls")]
+        async fn connect_socket_tls(
+            url: Url,
+            connector: Connector,
+        ) -> Result<WebSocketStream<MaybeTlsStream<TcpStream>>, tungstenite::Error> {
+            use tokio_tungstenite::{client_async_tls_with_config, tungstenite::error::UrlError};
+
+            let host = url
+                .host_str()
+                .ok_or(tungstenite::Error::Url(UrlError::NoHostName))?
+                .to_owned();
+            let port = url
+                .port_or_known_default()
+                .ok_or(tungstenite::Error::Url(UrlError::UnsupportedUrlScheme))?;
+            let stream = TcpStream::connect((host.as_str(), port))
+                .await
+                .map_err(tungstenite::Error::Io)?;
+            client_async_tls_with_config(url, stream, None, Some(connector))
+                .await
+                .map(|(socket, _)| socket)
+        }
+
+        pub(crate) async fn read(
+            stream: &mut PluginStream,
+            log_prefix: &str,
+            health: &HealthTracker,
+            raw_frame_hook: Option<&RawFrameHook>,
+        ) -> Option<Result<IPCMessage, String>> {
             stream.next().await.map(|result| match result {
                 Ok(msg) => {
                     let json = msg
                         .to_text()
                         .map_err(|err| format!("Could not get text message: {:?}", err))?;
 
-                    log::trace!("Received message {}", json);
+                    log::trace!("{}Received message {}", log_prefix, json);
+                    health.record_received(json.len());
+                    inspect_frame(raw_frame_hook, json);
 
                     IPCMessage::from_str(json)
                         .map_err(|err| format!("Could not parse message: {:?}", err))
@@ -104,13 +226,25 @@ mod double {
         use crate::{
             api_handler::{ApiHandlerBuilder, ApiHandlerHandle, NoopApiHandler},
             client::Client,
+            device::validate_id,
+            error::WebthingsError,
+            plugin::{
+                default_log_prefix, inspect_frame, ConnectSettings, Connector, HealthTracker,
+                Mailboxes, RawFrameHook,
+            },
             Plugin,
         };
-        use std::{collections::HashMap, sync::Arc};
+        use std::{
+            collections::{HashMap, VecDeque},
+            str::FromStr,
+            sync::Arc,
+            time::Duration,
+        };
         use tokio::sync::Mutex;
         use webthings_gateway_ipc_types::{Message as IPCMessage, Preferences, Units, UserProfile};
 
-        pub(crate) type PluginStream = ();
+        /// Raw text frames which the mocked gateway sends to the plugin.
+        pub(crate) type PluginStream = VecDeque<String>;
 
         pub fn connect(plugin_id: impl Into<String>) -> Plugin {
             let plugin_id = plugin_id.into();
@@ -130,23 +264,70 @@ mod double {
                 media_dir: "".to_owned(),
             };
             let client = Arc::new(Mutex::new(Client::new()));
+            let health = Arc::new(HealthTracker::default());
             let api_handler = Arc::new(Mutex::new(NoopApiHandler::build(
                 NoopApiHandler,
                 ApiHandlerHandle::new(client.clone(), plugin_id.clone()),
             )));
             Plugin {
+                log_prefix: default_log_prefix(&plugin_id),
+                gateway_version: "1.0.0".to_owned(),
+                package_name: plugin_id.clone(),
                 plugin_id,
                 preferences,
                 user_profile,
                 client,
-                stream: (),
+                stream: Arc::new(Mutex::new(VecDeque::new())),
                 adapters: HashMap::new(),
                 api_handler,
+                message_tap: None,
+                raw_frame_hook: None,
+                addressed_message_handler: None,
+                unknown_message_handler: None,
+                exit_strategy: None,
+                message_filter: None,
+                dispatcher: None,
+                mailboxes: Mailboxes::default(),
+                health,
+                runtime: None,
+            }
+        }
+
+        pub(crate) async fn connect_to(
+            plugin_id: String,
+            _gateway_url: &str,
+            _register_timeout: Duration,
+            _connector: Option<Connector>,
+            settings: ConnectSettings,
+        ) -> Result<Plugin, WebthingsError> {
+            validate_id(&plugin_id)?;
+            let mut plugin = connect(plugin_id);
+            if let Some(log_prefix) = settings.log_prefix {
+                plugin.log_prefix = log_prefix.clone();
+                plugin
+                    .api_handler
+                    .lock()
+                    .await
+                    .api_handler_handle_mut()
+                    .log_prefix = log_prefix;
             }
+            plugin.message_tap = settings.message_tap;
+            Ok(plugin)
         }
 
-        pub(crate) async fn read(_stream: &mut PluginStream) -> Option<Result<IPCMessage, String>> {
-            None
+        pub(crate) async fn read(
+            stream: &mut PluginStream,
+            _log_prefix: &str,
+            health: &HealthTracker,
+            raw_frame_hook: Option<&RawFrameHook>,
+        ) -> Option<Result<IPCMessage, String>> {
+            let json = stream.pop_front()?;
+            health.record_received(json.len());
+            inspect_frame(raw_frame_hook, &json);
+            Some(
+                IPCMessage::from_str(&json)
+                    .map_err(|err| format!("Could not parse message: {:?}", err)),
+            )
         }
     }
 }
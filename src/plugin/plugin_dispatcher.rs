@@ -0,0 +1,221 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+
+use crate::runtime::spawn;
+use futures::future::BoxFuture;
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{Arc, Mutex},
+};
+use tokio::{
+    runtime::Handle,
+    sync::{
+        mpsc::{unbounded_channel, UnboundedSender},
+        Semaphore,
+    },
+};
+
+/// The key of the mailbox of a device.
+pub(crate) fn mailbox_key(adapter_id: &str, device_id: &str) -> String {
+    format!("{}/{}", adapter_id, device_id)
+}
+
+/// The mailboxes of a [Dispatcher].
+///
+/// They are shared with the [adapters][crate::AdapterHandle], so the mailbox of a device is also
+/// closed when the adapter removes the device itself.
+#[derive(Clone, Default)]
+pub(crate) struct Mailboxes(Arc<Mutex<HashMap<String, UnboundedSender<BoxFuture<'static, ()>>>>>);
+
+impl Mailboxes {
+    /// Close the mailbox of the given key.
+    ///
+    /// Handlers which are already queued still run, afterwards the task of the mailbox exits.
+    pub(crate) fn close(&self, key: &str) {
+        self.0.lock().unwrap().remove(key);
+    }
+
+    /// Close the mailboxes of all keys starting with the given prefix.
+    pub(crate) fn close_prefixed(&self, prefix: &str) {
+        self.0
+            .lock()
+            .unwrap()
+            .retain(|key, _| !key.starts_with(prefix));
+    }
+
+    #[cfg(test)]
+    pub(crate) fn is_open(&self, key: &str) -> bool {
+        self.0.lock().unwrap().contains_key(key)
+    }
+}
+
+/// Runs message handlers on spawned tasks.
+///
+/// Every key, usually a device, gets its own mailbox which is worked off by a dedicated task, so
+/// handlers dispatched with the same key run in the order they were dispatched while handlers with
+/// different keys may run in parallel. At most `limit` handlers run at the same time.
+pub(crate) struct Dispatcher {
+    semaphore: Arc<Semaphore>,
+    mailboxes: Mailboxes,
+    pub(crate) runtime: Option<Handle>,
+}
+
+impl Dispatcher {
+    pub(crate) fn new(limit: usize, runtime: Option<Handle>, mailboxes: Mailboxes) -> Self {
+        Self {
+            semaphore: Arc::new(Semaphore::new(limit.max(1))),
+            mailboxes,
+            runtime,
+        }
+    }
+
+    pub(crate) fn dispatch(
+        &mut self,
+        key: String,
+        handler: impl Future<Output = ()> + Send + 'static,
+    ) {
+        let mut handler: BoxFuture<'static, ()> = Box::pin(handler);
+        let mut mailboxes = self.mailboxes.0.lock().unwrap();
+        if let Some(mailbox) = mailboxes.get(&key) {
+            match mailbox.send(handler) {
+                Ok(()) => return,
+                Err(err) => handler = err.0,
+            }
+        }
+
+        let (mailbox, mut handlers) = unbounded_channel::<BoxFuture<'static, ()>>();
+        let semaphore = self.semaphore.clone();
+        spawn(self.runtime.as_ref(), async move {
+            while let Some(handler) = handlers.recv().await {
+                if let Ok(_permit) = semaphore.clone().acquire_owned().await {
+                    handler.await;
+                }
+            }
+        });
+        // The receiver is alive until the mailbox is dropped.
+        let _ = mailbox.send(handler);
+        mailboxes.insert(key, mailbox);
+    }
+
+    /// Close the mailbox of the given key.
+    ///
+    /// Handlers which are already queued still run, afterwards the task of the mailbox exits.
+    pub(crate) fn close(&mut self, key: &str) {
+        self.mailboxes.close(key);
+    }
+
+    /// Close the mailboxes of all keys starting with the given prefix.
+    pub(crate) fn close_prefixed(&mut self, prefix: &str) {
+        self.mailboxes.close_prefixed(prefix);
+    }
+
+    #[cfg(test)]
+    pub(crate) fn is_open(&self, key: &str) -> bool {
+        self.mailboxes.is_open(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Dispatcher, Mailboxes};
+    use std::{sync::Arc, time::Duration};
+    use tokio::{
+        sync::{oneshot, Mutex},
+        time::sleep,
+    };
+
+    #[tokio::test]
+    async fn test_same_key_in_order() {
+        let mut dispatcher = Dispatcher::new(4, None, Mailboxes::default());
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        for i in 0..4 {
+            let order = order.clone();
+            dispatcher.dispatch("device".to_owned(), async move {
+                sleep(Duration::from_millis(20 - 5 * i)).await;
+                order.lock().await.push(i);
+            });
+        }
+
+        sleep(Duration::from_millis(100)).await;
+        assert_eq!(*order.lock().await, vec![0, 1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_different_keys_in_parallel() {
+        let mut dispatcher = Dispatcher::new(2, None, Mailboxes::default());
+        let (unblock, blocked) = oneshot::channel::<()>();
+        let (done, finished) = oneshot::channel();
+
+        dispatcher.dispatch("slow".to_owned(), async move {
+            let _ = blocked.await;
+        });
+        dispatcher.dispatch("fast".to_owned(), async move {
+            done.send(()).unwrap();
+        });
+
+        tokio::time::timeout(Duration::from_millis(100), finished)
+            .await
+            .unwrap()
+            .unwrap();
+        unblock.send(()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_limit() {
+        let mut dispatcher = Dispatcher::new(1, None, Mailboxes::default());
+        let (unblock, blocked) = oneshot::channel::<()>();
+        let (done, mut finished) = oneshot::channel();
+
+        dispatcher.dispatch("slow".to_owned(), async move {
+            let _ = blocked.await;
+        });
+        dispatcher.dispatch("fast".to_owned(), async move {
+            done.send(()).unwrap();
+        });
+
+        sleep(Duration::from_millis(20)).await;
+        assert!(finished.try_recv().is_err());
+        unblock.send(()).unwrap();
+        tokio::time::timeout(Duration::from_millis(100), finished)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_close_drains() {
+        let mut dispatcher = Dispatcher::new(2, None, Mailboxes::default());
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        for i in 0..3 {
+            let order = order.clone();
+            dispatcher.dispatch("device".to_owned(), async move {
+                sleep(Duration::from_millis(5)).await;
+                order.lock().await.push(i);
+            });
+        }
+        dispatcher.close("device");
+        assert!(!dispatcher.is_open("device"));
+
+        sleep(Duration::from_millis(50)).await;
+        assert_eq!(*order.lock().await, vec![0, 1, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_close_prefixed() {
+        let mut dispatcher = Dispatcher::new(2, None, Mailboxes::default());
+        dispatcher.dispatch("adapter/a".to_owned(), async {});
+        dispatcher.dispatch("adapter/b".to_owned(), async {});
+        dispatcher.dispatch("other/a".to_owned(), async {});
+
+        dispatcher.close_prefixed("adapter/");
+        assert!(!dispatcher.is_open("adapter/a"));
+        assert!(!dispatcher.is_open("adapter/b"));
+        assert!(dispatcher.is_open("other/a"));
+    }
+}
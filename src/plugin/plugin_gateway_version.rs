@@ -0,0 +1,94 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+
+use crate::Plugin;
+use semver::Version;
+
+/// A capability of the gateway which is only available from a certain version on.
+///
+/// See [Plugin::supports].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum GatewayFeature {
+    /// Handling of requests to `/extensions` by an [API handler][crate::api_handler::ApiHandler].
+    ApiHandlers,
+    /// Version 1 of the IPC message schema, as implemented by this crate.
+    IpcV1,
+}
+
+impl GatewayFeature {
+    /// The first gateway version which supports this feature.
+    pub fn min_version(self) -> Version {
+        match self {
+            GatewayFeature::ApiHandlers => Version::new(0, 10, 0),
+            GatewayFeature::IpcV1 => Version::new(1, 0, 0),
+        }
+    }
+}
+
+/// Parse a gateway version string like `1.0.0` or `v1.1.0-beta.1`.
+pub(crate) fn parse_gateway_version(version: &str) -> Option<Version> {
+    Version::parse(version.trim().trim_start_matches('v')).ok()
+}
+
+impl Plugin {
+    /// The version of the gateway as sent during registration, e.g. `1.0.0`.
+    pub fn gateway_version_raw(&self) -> &str {
+        &self.gateway_version
+    }
+
+    /// The parsed version of the gateway.
+    ///
+    /// `None` if the version sent by the gateway is not a valid semantic version,
+    /// see [gateway_version_raw][Plugin::gateway_version_raw] for the original string.
+    pub fn gateway_version(&self) -> Option<Version> {
+        parse_gateway_version(&self.gateway_version)
+    }
+
+    /// Whether the gateway is new enough to support the given [feature][GatewayFeature].
+    ///
+    /// Conservatively `false` if the [gateway version][Plugin::gateway_version] cannot be parsed.
+    /// Pre-releases of the minimum version are not considered to support the feature.
+    pub fn supports(&self, feature: GatewayFeature) -> bool {
+        self.gateway_version()
+            .is_some_and(|version| version >= feature.min_version())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::plugin::{parse_gateway_version, tests::plugin, GatewayFeature, Plugin, Version};
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("1.0.0", Some(Version::new(1, 0, 0)))]
+    #[case("v1.1.2", Some(Version::new(1, 1, 2)))]
+    #[case("1.1.0-beta.1", Some(Version::parse("1.1.0-beta.1").unwrap()))]
+    #[case("1.0", None)]
+    #[case("", None)]
+    fn test_parse_gateway_version(#[case] version: &str, #[case] expected: Option<Version>) {
+        assert_eq!(parse_gateway_version(version), expected);
+    }
+
+    #[rstest]
+    #[case("0.9.2", GatewayFeature::ApiHandlers, false)]
+    #[case("0.10.0", GatewayFeature::ApiHandlers, true)]
+    #[case("0.12.0", GatewayFeature::IpcV1, false)]
+    #[case("1.0.0-alpha.1", GatewayFeature::IpcV1, false)]
+    #[case("1.0.0", GatewayFeature::IpcV1, true)]
+    #[case("1.1.0", GatewayFeature::IpcV1, true)]
+    #[case("unknown", GatewayFeature::ApiHandlers, false)]
+    fn test_supports(
+        mut plugin: Plugin,
+        #[case] version: &str,
+        #[case] feature: GatewayFeature,
+        #[case] expected: bool,
+    ) {
+        plugin.gateway_version = version.to_owned();
+        assert_eq!(plugin.gateway_version_raw(), version);
+        assert_eq!(plugin.supports(feature), expected);
+    }
+}
@@ -0,0 +1,145 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+
+use chrono::{DateTime, TimeZone, Utc};
+use std::sync::{
+    atomic::{AtomicI64, AtomicU64, Ordering},
+    Mutex,
+};
+
+/// A snapshot of the health of a [plugin][crate::Plugin].
+///
+/// See [Plugin::health][crate::Plugin::health].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PluginHealth {
+    /// The last error which occurred while reading from or sending to the gateway.
+    pub last_error: Option<String>,
+    /// When the last message was successfully received from or sent to the gateway.
+    pub last_message: Option<DateTime<Utc>>,
+}
+
+/// Traffic counters of a [plugin][crate::Plugin].
+///
+/// See [Plugin::io_stats][crate::Plugin::io_stats].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IoStats {
+    /// The number of messages successfully sent to the gateway.
+    pub messages_sent: u64,
+    /// The total size of the serialized messages sent to the gateway.
+    pub bytes_sent: u64,
+    /// The number of messages received from the gateway.
+    pub messages_received: u64,
+    /// The total size of the messages received from the gateway.
+    pub bytes_received: u64,
+}
+
+/// Health status shared between the read and send paths.
+///
+/// Counters are atomics, so reading the status never waits for a message to be sent.
+#[derive(Debug, Default)]
+pub(crate) struct HealthTracker {
+    last_error: Mutex<Option<String>>,
+    last_message_millis: AtomicI64,
+    messages_sent: AtomicU64,
+    bytes_sent: AtomicU64,
+    messages_received: AtomicU64,
+    bytes_received: AtomicU64,
+}
+
+impl HealthTracker {
+    pub(crate) fn record_message(&self) {
+        self.last_message_millis
+            .store(Utc::now().timestamp_millis(), Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_error(&self, error: impl ToString) {
+        if let Ok(mut last_error) = self.last_error.lock() {
+            *last_error = Some(error.to_string());
+        }
+    }
+
+    #[cfg_attr(test, allow(dead_code))]
+    pub(crate) fn record_sent(&self, bytes: usize) {
+        self.messages_sent.fetch_add(1, Ordering::Relaxed);
+        self.bytes_sent.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    #[cfg_attr(test, allow(dead_code))]
+    pub(crate) fn record_received(&self, bytes: usize) {
+        self.messages_received.fetch_add(1, Ordering::Relaxed);
+        self.bytes_received
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> PluginHealth {
+        let last_message_millis = self.last_message_millis.load(Ordering::Relaxed);
+        PluginHealth {
+            last_error: self
+                .last_error
+                .lock()
+                .map(|last_error| last_error.clone())
+                .unwrap_or_default(),
+            last_message: if last_message_millis > 0 {
+                Utc.timestamp_millis_opt(last_message_millis).single()
+            } else {
+                None
+            },
+        }
+    }
+
+    pub(crate) fn io_stats(&self) -> IoStats {
+        IoStats {
+            messages_sent: self.messages_sent.load(Ordering::Relaxed),
+            bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
+            messages_received: self.messages_received.load(Ordering::Relaxed),
+            bytes_received: self.bytes_received.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HealthTracker, IoStats};
+
+    #[test]
+    fn test_default_health() {
+        let health = HealthTracker::default().snapshot();
+        assert!(health.last_error.is_none());
+        assert!(health.last_message.is_none());
+    }
+
+    #[test]
+    fn test_record() {
+        let tracker = HealthTracker::default();
+        tracker.record_message();
+        tracker.record_error("foo");
+        tracker.record_error("bar");
+
+        let health = tracker.snapshot();
+        assert_eq!(health.last_error, Some("bar".to_owned()));
+        assert!(health.last_message.is_some());
+    }
+
+    #[test]
+    fn test_io_stats() {
+        let tracker = HealthTracker::default();
+        assert_eq!(tracker.io_stats(), IoStats::default());
+
+        tracker.record_sent(10);
+        tracker.record_sent(5);
+        tracker.record_received(42);
+
+        assert_eq!(
+            tracker.io_stats(),
+            IoStats {
+                messages_sent: 2,
+                bytes_sent: 15,
+                messages_received: 1,
+                bytes_received: 42,
+            }
+        );
+    }
+}
@@ -5,6 +5,7 @@
  */
 
 use crate::{
+    error::HandleError,
     message_handler::{MessageHandler, MessageResult},
     Plugin,
 };
@@ -21,14 +22,20 @@ use webthings_gateway_ipc_types::{
 
 #[async_trait]
 impl MessageHandler for Plugin {
-    async fn handle_message(&mut self, message: IPCMessage) -> Result<MessageResult, String> {
+    async fn handle_message(&mut self, message: IPCMessage) -> Result<MessageResult, HandleError> {
         match &message {
             IPCMessage::PluginUnloadRequest(PluginUnloadRequest { data, .. }) => {
-                log::info!("Received request to unload plugin '{}'", data.plugin_id);
+                log::info!(
+                    "{}Received request to unload plugin '{}'",
+                    self.log_prefix,
+                    data.plugin_id
+                );
 
-                self.unload()
-                    .await
-                    .map_err(|err| format!("Could not send unload response: {}", err))?;
+                self.cancel_pairing().await;
+
+                self.unload().await.map_err(|err| {
+                    HandleError::from_error("Could not send unload response", err)
+                })?;
 
                 Ok(MessageResult::Terminate)
             }
@@ -65,7 +72,7 @@ impl MessageHandler for Plugin {
                 ..
             }) => {
                 self.borrow_adapter(adapter_id)
-                    .map_err(|e| format!("{:?}", e))?
+                    .map_err(HandleError::UnknownTarget)?
                     .lock()
                     .await
                     .handle_message(message)
@@ -74,16 +81,70 @@ impl MessageHandler for Plugin {
             IPCMessage::ApiHandlerUnloadRequest(_) | IPCMessage::ApiHandlerApiRequest(_) => {
                 self.api_handler.lock().await.handle_message(message).await
             }
-            msg => Err(format!("Unexpected msg: {:?}", msg)),
+            msg => {
+                if let Some(ref handler) = self.addressed_message_handler {
+                    if addressed_plugin_id(msg).as_ref() == Some(&self.plugin_id) {
+                        return handler(msg)
+                            .map(|_| MessageResult::Continue)
+                            .map_err(|err| {
+                                HandleError::Callback(
+                                    "Error during addressed message handler".to_owned(),
+                                    err,
+                                )
+                            });
+                    }
+                }
+
+                if let Some(ref mut handler) = self.unknown_message_handler {
+                    match handler(msg) {
+                        Ok(result) => return Ok(result),
+                        Err(err) => log::debug!(
+                            "{}Unknown message handler did not handle message: {}",
+                            self.log_prefix,
+                            err
+                        ),
+                    }
+                }
+
+                Err(HandleError::UnexpectedMessage(format!("{:?}", msg)))
+            }
         }
     }
 }
 
+fn addressed_plugin_id(message: &IPCMessage) -> Option<String> {
+    let message = serde_json::to_value(message).ok()?;
+    let data = message.get("data")?;
+    data.get("pluginId")
+        .or_else(|| data.get("plugin_id"))?
+        .as_str()
+        .map(|plugin_id| plugin_id.to_owned())
+}
+
 #[cfg(test)]
 pub(crate) mod tests {
-    use crate::{message_handler::MessageHandler, plugin::tests::plugin, Plugin};
+    use crate::{
+        adapter::tests::BuiltMockAdapter,
+        error::{HandleError, WebthingsError},
+        message_handler::{MessageHandler, MessageResult},
+        plugin::tests::{add_mock_adapter, plugin},
+        Plugin,
+    };
+    use as_any::Downcast;
     use rstest::rstest;
-    use webthings_gateway_ipc_types::{Message, PluginUnloadRequestMessageData};
+    use std::{
+        future::pending,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+    use tokio::{sync::oneshot, time::timeout};
+    use webthings_gateway_ipc_types::{
+        AdapterUnloadRequestMessageData, Message, PluginErrorNotificationMessageData,
+        PluginUnloadRequestMessageData,
+    };
 
     const PLUGIN_ID: &str = "plugin_id";
 
@@ -109,4 +170,196 @@ pub(crate) mod tests {
 
         plugin.handle_message(message).await.unwrap();
     }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_request_unload_while_pairing(mut plugin: Plugin) {
+        let adapter = add_mock_adapter(&mut plugin, "adapter_id").await;
+        let (discovery_alive, discovery_dropped) = oneshot::channel::<()>();
+        {
+            let mut adapter = adapter.lock().await;
+            let adapter_handle = adapter.adapter_handle_mut();
+            adapter_handle.start_pairing(Duration::from_secs(60));
+            adapter_handle.spawn_discovery(async move {
+                let _discovery_alive = discovery_alive;
+                pending::<()>().await;
+            });
+            adapter
+                .downcast_mut::<BuiltMockAdapter>()
+                .unwrap()
+                .adapter_helper
+                .expect_on_cancel_pairing()
+                .times(1)
+                .returning(|| Err("Scanner is gone".to_owned()));
+        }
+
+        plugin
+            .client
+            .lock()
+            .await
+            .expect_send_message()
+            .withf(|msg| matches!(msg, Message::PluginUnloadResponse(_)))
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let message: Message = PluginUnloadRequestMessageData {
+            plugin_id: PLUGIN_ID.to_owned(),
+        }
+        .into();
+        plugin.handle_message(message).await.unwrap();
+
+        assert!(!adapter.lock().await.adapter_handle().pairing());
+        assert!(timeout(Duration::from_secs(1), discovery_dropped)
+            .await
+            .unwrap()
+            .is_err());
+    }
+
+    #[rstest]
+    #[case(PLUGIN_ID, true)]
+    #[case("other_plugin_id", false)]
+    #[tokio::test]
+    async fn test_addressed_message(
+        mut plugin: Plugin,
+        #[case] plugin_id: &'static str,
+        #[case] expect_handled: bool,
+    ) {
+        let handled = Arc::new(AtomicBool::new(false));
+        let handled_clone = handled.clone();
+        plugin.set_addressed_message_handler(Box::new(move |_| {
+            handled_clone.store(true, Ordering::SeqCst);
+            Ok(())
+        }));
+
+        let message: Message = PluginErrorNotificationMessageData {
+            plugin_id: plugin_id.to_owned(),
+            message: "night mode".to_owned(),
+        }
+        .into();
+
+        assert_eq!(plugin.handle_message(message).await.is_ok(), expect_handled);
+        assert_eq!(handled.load(Ordering::SeqCst), expect_handled);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_unhandled_message(mut plugin: Plugin) {
+        let message: Message = PluginErrorNotificationMessageData {
+            plugin_id: PLUGIN_ID.to_owned(),
+            message: "night mode".to_owned(),
+        }
+        .into();
+
+        assert!(matches!(
+            plugin.handle_message(message).await,
+            Err(HandleError::UnexpectedMessage(_))
+        ));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_request_unload_send_failure(mut plugin: Plugin) {
+        let message: Message = PluginUnloadRequestMessageData {
+            plugin_id: PLUGIN_ID.to_owned(),
+        }
+        .into();
+
+        plugin
+            .client
+            .lock()
+            .await
+            .expect_send_message()
+            .times(1)
+            .returning(|_| Err(WebthingsError::Send(tungstenite::Error::ConnectionClosed)));
+
+        let err = plugin.handle_message(message).await.unwrap_err();
+        assert!(err.is_transport());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_unknown_adapter(mut plugin: Plugin) {
+        let message: Message = AdapterUnloadRequestMessageData {
+            plugin_id: PLUGIN_ID.to_owned(),
+            adapter_id: "unknown".to_owned(),
+        }
+        .into();
+
+        assert!(matches!(
+            plugin.handle_message(message).await,
+            Err(HandleError::UnknownTarget(WebthingsError::UnknownAdapter(adapter_id))) if adapter_id == "unknown"
+        ));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_addressed_message_failure(mut plugin: Plugin) {
+        plugin.set_addressed_message_handler(Box::new(|_| Err("night mode".to_owned())));
+
+        let message: Message = PluginErrorNotificationMessageData {
+            plugin_id: PLUGIN_ID.to_owned(),
+            message: "night mode".to_owned(),
+        }
+        .into();
+
+        assert!(matches!(
+            plugin.handle_message(message).await,
+            Err(HandleError::Callback(_, err)) if err == "night mode"
+        ));
+    }
+
+    #[rstest]
+    #[case(Ok(MessageResult::Terminate))]
+    #[case(Ok(MessageResult::Continue))]
+    #[tokio::test]
+    async fn test_unknown_message(
+        mut plugin: Plugin,
+        #[case] result: Result<MessageResult, String>,
+    ) {
+        let expected = result.clone();
+        plugin.set_unknown_message_handler(Box::new(move |message| {
+            assert!(matches!(message, Message::PluginErrorNotification(_)));
+            result.clone()
+        }));
+
+        let message: Message = PluginErrorNotificationMessageData {
+            plugin_id: PLUGIN_ID.to_owned(),
+            message: "night mode".to_owned(),
+        }
+        .into();
+
+        assert_eq!(plugin.handle_message(message).await.ok(), expected.ok());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_unknown_message_unrecognized(mut plugin: Plugin) {
+        plugin.set_unknown_message_handler(Box::new(|_| Err("unknown".to_owned())));
+
+        let message: Message = PluginErrorNotificationMessageData {
+            plugin_id: PLUGIN_ID.to_owned(),
+            message: "night mode".to_owned(),
+        }
+        .into();
+
+        assert!(matches!(
+            plugin.handle_message(message).await,
+            Err(HandleError::UnexpectedMessage(_))
+        ));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_unknown_message_addressed_first(mut plugin: Plugin) {
+        plugin.set_addressed_message_handler(Box::new(|_| Ok(())));
+        plugin.set_unknown_message_handler(Box::new(|_| panic!("Unexpected call")));
+
+        let message: Message = PluginErrorNotificationMessageData {
+            plugin_id: PLUGIN_ID.to_owned(),
+            message: "night mode".to_owned(),
+        }
+        .into();
+
+        assert!(plugin.handle_message(message).await.is_ok());
+    }
 }
@@ -7,22 +7,31 @@
 use crate::{
     adapter::AdapterBuilder,
     api_handler::{ApiHandler, ApiHandlerBuilder, ApiHandlerHandle},
-    client::Client,
+    client::{self, tap_message, Client, MessageTap, QueueConfig, RetryPolicy},
     database::Database,
-    error::WebthingsError,
+    error::{HandleError, WebthingsError},
     message_handler::{MessageHandler, MessageResult},
-    plugin::{plugin_connection, PluginStream},
+    plugin::{
+        mailbox_key, plugin_connection, plugin_dispatcher::Dispatcher, HealthTracker, IoStats,
+        Mailboxes, PluginHealth, PluginStream,
+    },
     Adapter, AdapterHandle,
 };
+use futures::stream::{self, Stream, StreamExt};
 use serde::{de::DeserializeOwned, Serialize};
+#[cfg(feature = "testing")]
+use std::future::Future;
 use std::{collections::HashMap, path::PathBuf, process, sync::Arc, time::Duration};
-use tokio::{sync::Mutex, time::sleep};
+#[cfg(feature = "testing")]
+use tokio::sync::mpsc;
+use tokio::{runtime::Handle, sync::Mutex, time::timeout};
 use webthings_gateway_ipc_types::{
     AdapterAddedNotificationMessageData, ApiHandlerAddedNotificationMessageData, Message,
     PluginErrorNotificationMessageData, PluginUnloadResponseMessageData, Preferences, UserProfile,
 };
 
 const DONT_RESTART_EXIT_CODE: i32 = 100;
+const FAIL_CLOSE_TIMEOUT: Duration = Duration::from_secs(2);
 
 /// A struct which represents a successfully established connection to a WebthingsIO gateway.
 ///
@@ -38,37 +47,638 @@ const DONT_RESTART_EXIT_CODE: i32 = 100;
 /// }
 /// ```
 pub struct Plugin {
-    pub plugin_id: String,
+    pub(crate) plugin_id: String,
+    pub(crate) package_name: String,
+    pub(crate) gateway_version: String,
     pub preferences: Preferences,
     pub user_profile: UserProfile,
     pub(crate) client: Arc<Mutex<Client>>,
     pub(crate) api_handler: Arc<Mutex<dyn ApiHandler>>,
-    pub(crate) stream: PluginStream,
+    pub(crate) stream: Arc<Mutex<PluginStream>>,
     pub(crate) adapters: HashMap<String, Arc<Mutex<Box<dyn Adapter>>>>,
+    pub(crate) log_prefix: String,
+    pub(crate) message_tap: Option<MessageTap>,
+    pub(crate) raw_frame_hook: Option<RawFrameHook>,
+    pub(crate) addressed_message_handler: Option<AddressedMessageHandler>,
+    pub(crate) unknown_message_handler: Option<UnknownMessageHandler>,
+    pub(crate) exit_strategy: Option<ExitStrategy>,
+    pub(crate) message_filter: Option<MessageFilter>,
+    pub(crate) dispatcher: Option<Dispatcher>,
+    pub(crate) mailboxes: Mailboxes,
+    pub(crate) health: Arc<HealthTracker>,
+    pub(crate) runtime: Option<Handle>,
+}
+
+/// A callback for messages addressed to a [plugin][Plugin] which are not handled by this crate.
+///
+/// See [Plugin::set_addressed_message_handler].
+pub type AddressedMessageHandler = Box<dyn Fn(&Message) -> Result<(), String> + Send + Sync>;
+
+/// A callback for messages which are not routed by this crate, e.g. those of newer gateway features.
+///
+/// See [Plugin::set_unknown_message_handler].
+pub type UnknownMessageHandler =
+    Box<dyn FnMut(&Message) -> Result<MessageResult, String> + Send + Sync>;
+
+/// A callback which gets invoked with every raw text frame received from the gateway, before it is parsed.
+///
+/// See [Plugin::set_raw_frame_hook].
+pub type RawFrameHook = Arc<dyn Fn(&str) + Send + Sync>;
+
+/// A predicate which decides whether a message received from the gateway is handled.
+///
+/// See [Plugin::set_message_filter].
+pub type MessageFilter = Box<dyn Fn(&Message) -> bool + Send + Sync>;
+
+/// A callback which [Plugin::fail] invokes with the exit code instead of exiting the process.
+///
+/// See [Plugin::set_exit_strategy].
+pub type ExitStrategy = Box<dyn Fn(i32) + Send + Sync>;
+
+fn device_target(message: &Message) -> Option<(&str, &str)> {
+    match message {
+        Message::DeviceSetPropertyCommand(msg) => Some((&msg.data.adapter_id, &msg.data.device_id)),
+        Message::DeviceRequestActionRequest(msg) => {
+            Some((&msg.data.adapter_id, &msg.data.device_id))
+        }
+        Message::DeviceRemoveActionRequest(msg) => {
+            Some((&msg.data.adapter_id, &msg.data.device_id))
+        }
+        _ => None,
+    }
+}
+
+pub(crate) fn default_log_prefix(plugin_id: &str) -> String {
+    format!("[{}] ", plugin_id)
 }
 
 impl Plugin {
+    /// ID of this plugin.
+    pub fn plugin_id(&self) -> &str {
+        &self.plugin_id
+    }
+
+    /// Name of the package of this plugin as reported to the gateway. Defaults to the plugin id.
+    pub fn package_name(&self) -> &str {
+        &self.package_name
+    }
+
+    /// Set the name of the package of this plugin, if it differs from the plugin id.
+    ///
+    /// It is sent when [adding adapters][Plugin::add_adapter] and [API handlers][Plugin::set_api_handler],
+    /// so set it before.
+    pub async fn set_package_name(&mut self, package_name: impl Into<String>) {
+        self.package_name = package_name.into();
+        self.api_handler
+            .lock()
+            .await
+            .api_handler_handle_mut()
+            .package_name = self.package_name.clone();
+    }
+
     /// Start the event loop of this plugin.
     ///
-    /// This will block your current thread.
+    /// This will block your current thread until the gateway unloads the plugin or the connection is closed.
     pub async fn event_loop(&mut self) {
-        loop {
-            match plugin_connection::read(&mut self.stream).await {
-                None => {}
-                Some(result) => match result {
-                    Ok(message) => match self.handle_message(message).await {
-                        Ok(MessageResult::Continue) => {}
-                        Ok(MessageResult::Terminate) => {
-                            break;
-                        }
-                        Err(err) => log::warn!("Could not handle message: {}", err),
-                    },
-                    Err(err) => log::warn!("Could not read message: {}", err),
+        let mut messages = Box::pin(self.message_stream());
+        while let Some(result) = messages.next().await {
+            match result {
+                Ok(message) => match self.dispatch(message).await {
+                    Ok(MessageResult::Continue) => {}
+                    Ok(MessageResult::Terminate) => {
+                        break;
+                    }
+                    Err(err) if err.is_transport() => {
+                        log::error!("{}Could not handle message: {}", self.log_prefix, err)
+                    }
+                    Err(err) => {
+                        log::warn!("{}Could not handle message: {}", self.log_prefix, err)
+                    }
                 },
+                Err(err) => {
+                    log::warn!("{}{}", self.log_prefix, err);
+                }
             }
         }
     }
 
+    /// A [stream][Stream] of the messages received from the gateway.
+    ///
+    /// Use this together with [dispatch][Plugin::dispatch] instead of [event_loop][Plugin::event_loop]
+    /// to integrate the gateway connection into your own loop.
+    /// The stream does not borrow the plugin. It ends when the connection is closed.
+    /// Messages are not handled until you [dispatch][Plugin::dispatch] them.
+    ///
+    /// Only consume one message stream at a time, as concurrent streams split the messages between them.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use gateway_addon_rust::{plugin::{connect, MessageResult}, error::WebthingsError};
+    /// # use futures::stream::StreamExt;
+    /// # use std::time::Duration;
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), WebthingsError> {
+    ///     let mut plugin = connect("example-addon").await?;
+    ///     let mut messages = Box::pin(plugin.message_stream());
+    ///     let mut interval = tokio::time::interval(Duration::from_secs(60));
+    ///     loop {
+    ///         tokio::select! {
+    ///             message = messages.next() => match message {
+    ///                 Some(message) => {
+    ///                     if let Ok(MessageResult::Terminate) = plugin.dispatch(message?).await {
+    ///                         break;
+    ///                     }
+    ///                 }
+    ///                 None => break,
+    ///             },
+    ///             _ = interval.tick() => {
+    ///                 // ...
+    ///             }
+    ///         }
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn message_stream(
+        &self,
+    ) -> impl Stream<Item = Result<Message, WebthingsError>> + Send + 'static {
+        let state = (
+            self.stream.clone(),
+            self.log_prefix.clone(),
+            self.health.clone(),
+            self.raw_frame_hook.clone(),
+        );
+        stream::unfold(
+            state,
+            |(stream, log_prefix, health, raw_frame_hook)| async move {
+                let result = plugin_connection::read(
+                    &mut *stream.lock().await,
+                    &log_prefix,
+                    &health,
+                    raw_frame_hook.as_ref(),
+                )
+                .await?;
+                let result = match result {
+                    Ok(message) => {
+                        health.record_message();
+                        Ok(message)
+                    }
+                    Err(err) => {
+                        health.record_error(err.clone());
+                        Err(WebthingsError::Read(err))
+                    }
+                };
+                Some((result, (stream, log_prefix, health, raw_frame_hook)))
+            },
+        )
+    }
+
+    /// Run the default handling for a message received from the gateway.
+    ///
+    /// This is what [event_loop][Plugin::event_loop] does for every message of the [message stream][Plugin::message_stream].
+    /// Returns [MessageResult::Terminate] once the gateway requested to unload the plugin.
+    pub async fn dispatch(&mut self, message: Message) -> Result<MessageResult, HandleError> {
+        tap_message(&self.message_tap, &self.log_prefix, &message);
+        if let Some(message_filter) = &self.message_filter {
+            if !message_filter(&message) {
+                log::debug!("{}Dropped filtered message {:?}", self.log_prefix, message);
+                return Ok(MessageResult::Continue);
+            }
+        }
+        let message = match self.dispatch_to_mailbox(message).await {
+            Some(message) => message,
+            None => return Ok(MessageResult::Continue),
+        };
+        self.close_mailboxes(&message);
+        self.handle_message(message).await
+    }
+
+    /// Dispatch device messages onto spawned tasks if a [concurrency limit][Plugin::set_concurrency_limit] is set.
+    ///
+    /// Returns the message if it has to be handled by the event loop itself.
+    async fn dispatch_to_mailbox(&mut self, message: Message) -> Option<Message> {
+        if self.dispatcher.is_none() {
+            return Some(message);
+        }
+        let (adapter_id, device_id) = match device_target(&message) {
+            Some((adapter_id, device_id)) => (adapter_id.to_owned(), device_id.to_owned()),
+            None => return Some(message),
+        };
+
+        let device = match self.borrow_adapter(&adapter_id) {
+            Ok(adapter) => adapter.lock().await.adapter_handle().get_device(&device_id),
+            Err(err) => {
+                log::warn!("{}Could not handle message: {}", self.log_prefix, err);
+                return None;
+            }
+        };
+        let device = match device {
+            Some(device) => device,
+            None => {
+                log::warn!(
+                    "{}Could not handle message: Unknown device: {}",
+                    self.log_prefix,
+                    device_id
+                );
+                return None;
+            }
+        };
+
+        let log_prefix = self.log_prefix.clone();
+        self.dispatcher
+            .as_mut()?
+            .dispatch(mailbox_key(&adapter_id, &device_id), async move {
+                if let Err(err) = device.lock().await.handle_message(message).await {
+                    log::warn!("{}Could not handle message: {}", log_prefix, err)
+                }
+            });
+        None
+    }
+
+    /// Close the mailboxes of devices which are about to be removed or unloaded.
+    ///
+    /// Messages which are already queued for these devices are still handled.
+    fn close_mailboxes(&mut self, message: &Message) {
+        if let Some(dispatcher) = self.dispatcher.as_mut() {
+            match message {
+                Message::AdapterRemoveDeviceRequest(msg) => {
+                    dispatcher.close(&mailbox_key(&msg.data.adapter_id, &msg.data.device_id))
+                }
+                Message::AdapterUnloadRequest(msg) => {
+                    dispatcher.close_prefixed(&mailbox_key(&msg.data.adapter_id, ""))
+                }
+                Message::PluginUnloadRequest(_) => dispatcher.close_prefixed(""),
+                _ => {}
+            }
+        }
+    }
+
+    /// Handle device messages on spawned tasks, running at most `limit` of them at the same time.
+    ///
+    /// Every device gets its own mailbox, so messages for the same device are still handled in
+    /// order while a slow device only delays its own messages. All other messages are handled by the
+    /// [event loop][Plugin::event_loop] itself. The mailbox of a device is closed when the device is
+    /// removed or its adapter is unloaded, after the messages queued so far have been handled.
+    ///
+    /// By default (`None`), all messages are handled sequentially.
+    pub fn set_concurrency_limit(&mut self, limit: Option<usize>) {
+        self.mailboxes.close_prefixed("");
+        self.dispatcher =
+            limit.map(|limit| Dispatcher::new(limit, self.runtime.clone(), self.mailboxes.clone()));
+    }
+
+    /// Get the runtime which background tasks of this plugin are spawned on.
+    ///
+    /// `None` means the runtime which is current when a task is spawned.
+    pub fn runtime(&self) -> Option<&Handle> {
+        self.runtime.as_ref()
+    }
+
+    /// Set the runtime which background tasks like [property polling][crate::Property::poll_interval]
+    /// and [concurrent message handling][Plugin::set_concurrency_limit] are spawned on.
+    ///
+    /// Only [adapters][crate::Adapter] added afterwards, and their devices, use the new runtime.
+    pub fn set_runtime(&mut self, runtime: Handle) {
+        if let Some(dispatcher) = self.dispatcher.as_mut() {
+            dispatcher.runtime = Some(runtime.clone());
+        }
+        self.runtime = Some(runtime);
+    }
+
+    /// Get a snapshot of the [health][PluginHealth] of this plugin.
+    ///
+    /// This is cheap and does not wait for pending messages, so it can e.g. be served by an
+    /// [api handler][crate::api_handler::ApiHandler].
+    pub fn health(&self) -> PluginHealth {
+        self.health.snapshot()
+    }
+
+    /// Get the [traffic counters][IoStats] of this plugin.
+    ///
+    /// Counts all messages and their serialized size since the plugin connected. Like
+    /// [health][Plugin::health], this is cheap and does not wait for pending messages.
+    pub fn io_stats(&self) -> IoStats {
+        self.health.io_stats()
+    }
+
+    /// Get the prefix which is prepended to log messages of this plugin.
+    pub fn log_prefix(&self) -> &str {
+        &self.log_prefix
+    }
+
+    /// Set the prefix which is prepended to log messages of this plugin.
+    ///
+    /// Defaults to `"[<plugin_id>] "`. Use an empty string to disable prefixing.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use gateway_addon_rust::{plugin::connect, error::WebthingsError};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), WebthingsError> {
+    /// let mut plugin = connect("example-addon").await?;
+    /// plugin.set_log_prefix("example: ").await;
+    /// #   Ok(())
+    /// # }
+    /// ```
+    pub async fn set_log_prefix(&mut self, log_prefix: impl Into<String>) {
+        self.log_prefix = log_prefix.into();
+        self.client
+            .lock()
+            .await
+            .set_log_prefix(self.log_prefix.clone());
+        for adapter in self.adapters.values() {
+            let mut adapter = adapter.lock().await;
+            let adapter_handle = adapter.adapter_handle_mut();
+            adapter_handle.log_prefix = self.log_prefix.clone();
+            for device in adapter_handle.devices().values() {
+                device.lock().await.device_handle_mut().log_prefix = self.log_prefix.clone();
+            }
+        }
+        self.api_handler
+            .lock()
+            .await
+            .api_handler_handle_mut()
+            .log_prefix = self.log_prefix.clone();
+    }
+
+    /// Set the [policy][RetryPolicy] for retrying messages which failed to send due to a transient error.
+    ///
+    /// By default, messages are not retried.
+    pub async fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+        self.client.lock().await.set_retry_policy(retry_policy);
+    }
+
+    /// Set the [capacity and overflow policies][QueueConfig] of the queue of outgoing messages.
+    ///
+    /// By default, the queue is unbounded.
+    pub async fn set_queue_config(&mut self, queue_config: QueueConfig) {
+        self.client.lock().await.set_queue_config(queue_config);
+    }
+
+    /// Set a callback which gets invoked with every message sent to and received from the gateway.
+    ///
+    /// This can be used to record IPC sessions, e.g. to replay them in tests.
+    /// The direction of a message is implied by its type.
+    /// Panics inside the callback are caught and logged.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use gateway_addon_rust::{plugin::connect, error::WebthingsError};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), WebthingsError> {
+    /// let mut plugin = connect("example-addon").await?;
+    /// plugin
+    ///     .set_message_tap(Box::new(|message| println!("{:?}", message)))
+    ///     .await;
+    /// #   Ok(())
+    /// # }
+    /// ```
+    pub async fn set_message_tap(&mut self, message_tap: Box<dyn Fn(&Message) + Send + Sync>) {
+        let message_tap: MessageTap = message_tap.into();
+        self.message_tap = Some(message_tap.clone());
+        self.client.lock().await.set_message_tap(Some(message_tap));
+    }
+
+    /// Remove the callback set by [set_message_tap][Plugin::set_message_tap].
+    pub async fn clear_message_tap(&mut self) {
+        self.message_tap = None;
+        self.client.lock().await.set_message_tap(None);
+    }
+
+    /// Set a callback which gets invoked with every raw text frame received from the gateway, before it is parsed.
+    ///
+    /// Meant for protocol debugging, as it also sees frames which cannot be parsed into a message.
+    /// The frame is borrowed, so copy only what you need from large frames.
+    /// Panics inside the callback are caught and logged.
+    ///
+    /// Set it before starting the [event loop][Plugin::event_loop] or a [message stream][Plugin::message_stream],
+    /// which keep the callback that was set when they started.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use gateway_addon_rust::{plugin::connect, error::WebthingsError};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), WebthingsError> {
+    /// let mut plugin = connect("example-addon").await?;
+    /// plugin.set_raw_frame_hook(Box::new(|frame| println!("{}", frame)));
+    /// #   Ok(())
+    /// # }
+    /// ```
+    pub fn set_raw_frame_hook(&mut self, raw_frame_hook: Box<dyn Fn(&str) + Send + Sync>) {
+        self.raw_frame_hook = Some(raw_frame_hook.into());
+    }
+
+    /// Remove the callback set by [set_raw_frame_hook][Plugin::set_raw_frame_hook].
+    pub fn clear_raw_frame_hook(&mut self) {
+        self.raw_frame_hook = None;
+    }
+
+    /// Run the given trigger and drive the plugin until it sends a message matching the predicate.
+    ///
+    /// Incoming messages are [dispatched][Plugin::dispatch] while waiting. The matching outgoing
+    /// message is returned, or [WebthingsError::MessageTimeout] if none was sent within the given duration.
+    /// Any [message tap][Plugin::set_message_tap] keeps receiving all messages meanwhile.
+    ///
+    /// Only available with the `testing` feature.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use gateway_addon_rust::{prelude::*, plugin::connect, example::ExampleAdapter, error::WebthingsError};
+    /// # use serde_json::json;
+    /// # use std::time::Duration;
+    /// # use webthings_gateway_ipc_types::Message;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), WebthingsError> {
+    /// #   let mut plugin = connect("example-addon").await?;
+    /// #   let adapter = plugin.add_adapter(ExampleAdapter::new()).await?;
+    /// #   let device = adapter.lock().await.adapter_handle().get_device("example-device").unwrap();
+    /// let message = plugin
+    ///     .wait_for_message(
+    ///         async {
+    ///             let device = device.lock().await;
+    ///             let device_handle = device.device_handle();
+    ///             device_handle.set_property_value("example-property", Some(json!(true))).await.unwrap();
+    ///         },
+    ///         |message| matches!(message, Message::DevicePropertyChangedNotification(_)),
+    ///         Duration::from_secs(1),
+    ///     )
+    ///     .await?;
+    /// #   Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "testing")]
+    pub async fn wait_for_message<F>(
+        &mut self,
+        trigger: impl Future,
+        predicate: F,
+        duration: Duration,
+    ) -> Result<Message, WebthingsError>
+    where
+        F: Fn(&Message) -> bool + Send + Sync + 'static,
+    {
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let previous_tap = self.message_tap.clone();
+        let log_prefix = self.log_prefix.clone();
+        let message_tap: MessageTap = Arc::new(move |message| {
+            tap_message(&previous_tap, &log_prefix, message);
+            if predicate(message) {
+                let _ = sender.send(message.clone());
+            }
+        });
+        self.client.lock().await.set_message_tap(Some(message_tap));
+
+        let result = timeout(duration, async {
+            trigger.await;
+            let mut messages = Box::pin(self.message_stream());
+            let mut connected = true;
+            loop {
+                tokio::select! {
+                    Some(message) = receiver.recv() => return message,
+                    message = messages.next(), if connected => match message {
+                        Some(Ok(message)) => {
+                            if let Ok(MessageResult::Terminate) = self.dispatch(message).await {
+                                connected = false;
+                            }
+                        }
+                        Some(Err(err)) => log::warn!("{}{}", self.log_prefix, err),
+                        None => connected = false,
+                    },
+                }
+            }
+        })
+        .await;
+
+        let message_tap = self.message_tap.clone();
+        self.client.lock().await.set_message_tap(message_tap);
+        result.map_err(|_| WebthingsError::MessageTimeout)
+    }
+
+    /// Set a callback which is invoked for messages which are addressed to this plugin but not
+    /// handled by this crate, e.g. custom notifications routed by the gateway on behalf of another addon.
+    ///
+    /// Messages addressed to other plugins are ignored. Note that the IPC protocol currently has no
+    /// dedicated message type for addon-to-addon notifications, so this crate offers no way to send them.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use gateway_addon_rust::{plugin::connect, error::WebthingsError};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), WebthingsError> {
+    /// let mut plugin = connect("example-addon").await?;
+    /// plugin.set_addressed_message_handler(Box::new(|message| {
+    ///     println!("{:?}", message);
+    ///     Ok(())
+    /// }));
+    /// #   Ok(())
+    /// # }
+    /// ```
+    pub fn set_addressed_message_handler(&mut self, handler: AddressedMessageHandler) {
+        self.addressed_message_handler = Some(handler);
+    }
+
+    /// Set a callback which is invoked for messages this crate does not route, before they are
+    /// rejected as unexpected.
+    ///
+    /// Messages addressed to this plugin go to the
+    /// [addressed message handler][Plugin::set_addressed_message_handler] instead, if one is set.
+    /// Return `Err` if the message is not recognized either, in which case the original
+    /// [unexpected message][crate::error::HandleError::UnexpectedMessage] error is returned.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use gateway_addon_rust::{plugin::{connect, MessageResult}, error::WebthingsError};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), WebthingsError> {
+    /// let mut plugin = connect("example-addon").await?;
+    /// plugin.set_unknown_message_handler(Box::new(|message| {
+    ///     println!("{:?}", message);
+    ///     Ok(MessageResult::Continue)
+    /// }));
+    /// #   Ok(())
+    /// # }
+    /// ```
+    pub fn set_unknown_message_handler(&mut self, handler: UnknownMessageHandler) {
+        self.unknown_message_handler = Some(handler);
+    }
+
+    /// Set a predicate which runs on every message received from the gateway before it is handled.
+    ///
+    /// Messages for which it returns `false` are dropped, e.g. commands for devices this addon does not own.
+    /// The predicate runs on the event loop after the [message tap][Plugin::set_message_tap],
+    /// so keep it cheap. Message order is not affected.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use gateway_addon_rust::{plugin::connect, error::WebthingsError};
+    /// # use webthings_gateway_ipc_types::Message;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), WebthingsError> {
+    /// let mut plugin = connect("example-addon").await?;
+    /// plugin.set_message_filter(Box::new(|message| match message {
+    ///     Message::DeviceSetPropertyCommand(msg) => msg.data.device_id.starts_with("example-"),
+    ///     _ => true,
+    /// }));
+    /// #   Ok(())
+    /// # }
+    /// ```
+    pub fn set_message_filter(&mut self, message_filter: MessageFilter) {
+        self.message_filter = Some(message_filter);
+    }
+
+    /// Remove the predicate set by [set_message_filter][Plugin::set_message_filter].
+    pub fn clear_message_filter(&mut self) {
+        self.message_filter = None;
+    }
+
+    /// Set a callback which [fail][Plugin::fail] invokes instead of exiting the process.
+    ///
+    /// Use this when the plugin is embedded in a larger binary which manages its own lifecycle.
+    /// The callback receives the exit code which tells the gateway not to restart the plugin.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use gateway_addon_rust::{plugin::connect, error::WebthingsError};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), WebthingsError> {
+    /// let mut plugin = connect("example-addon").await?;
+    /// plugin.set_exit_strategy(Box::new(|code| log::error!("Plugin failed with {}", code)));
+    /// #   Ok(())
+    /// # }
+    /// ```
+    pub fn set_exit_strategy(&mut self, exit_strategy: ExitStrategy) {
+        self.exit_strategy = Some(exit_strategy);
+    }
+
+    /// Remove the callback set by [set_exit_strategy][Plugin::set_exit_strategy],
+    /// so [fail][Plugin::fail] exits the process again.
+    pub fn clear_exit_strategy(&mut self) {
+        self.exit_strategy = None;
+    }
+
+    /// Remove the callback set by [set_addressed_message_handler][Plugin::set_addressed_message_handler].
+    pub fn clear_addressed_message_handler(&mut self) {
+        self.addressed_message_handler = None;
+    }
+
+    /// Remove the callback set by [set_unknown_message_handler][Plugin::set_unknown_message_handler].
+    pub fn clear_unknown_message_handler(&mut self) {
+        self.unknown_message_handler = None;
+    }
+
+    /// Get all adapters of this plugin by id.
+    ///
+    /// Returns clones of the adapter references, so the map can be used while the plugin is borrowed mutably.
+    pub fn adapters(&self) -> HashMap<String, Arc<Mutex<Box<dyn Adapter>>>> {
+        self.adapters.clone()
+    }
+
+    /// Get the sorted ids of all adapters of this plugin.
+    pub fn adapter_ids(&self) -> Vec<String> {
+        let mut adapter_ids: Vec<String> = self.adapters.keys().cloned().collect();
+        adapter_ids.sort();
+        adapter_ids
+    }
+
     /// Borrow the adapter with the given id.
     pub fn borrow_adapter(
         &mut self,
@@ -105,17 +715,20 @@ impl Plugin {
             plugin_id: self.plugin_id.clone(),
             adapter_id: adapter_id.clone(),
             name: adapter_name,
-            package_name: self.plugin_id.clone(),
+            package_name: self.package_name.clone(),
         }
         .into();
 
-        self.client.lock().await.send_message(&message).await?;
+        client::send_message(&self.client, &message).await?;
 
-        let adapter_handle = AdapterHandle::new(
+        let mut adapter_handle = AdapterHandle::new(
             self.client.clone(),
             self.plugin_id.clone(),
             adapter_id.clone(),
         );
+        adapter_handle.runtime = self.runtime.clone();
+        adapter_handle.log_prefix = self.log_prefix.clone();
+        adapter_handle.mailboxes = self.mailboxes.clone();
 
         let adapter: Arc<Mutex<Box<dyn Adapter>>> =
             Arc::new(Mutex::new(Box::new(T::build(adapter, adapter_handle))));
@@ -131,19 +744,43 @@ impl Plugin {
         &mut self,
         api_handler: T,
     ) -> Result<(), WebthingsError> {
-        self.api_handler = Arc::new(Mutex::new(T::build(
-            api_handler,
-            ApiHandlerHandle::new(self.client.clone(), self.plugin_id.clone()),
-        )));
+        let mut api_handler_handle =
+            ApiHandlerHandle::new(self.client.clone(), self.plugin_id.clone());
+        api_handler_handle.package_name = self.package_name.clone();
+        api_handler_handle.log_prefix = self.log_prefix.clone();
+        self.api_handler = Arc::new(Mutex::new(T::build(api_handler, api_handler_handle)));
         let message: Message = ApiHandlerAddedNotificationMessageData {
             plugin_id: self.plugin_id.clone(),
-            package_name: self.plugin_id.clone(),
+            package_name: self.package_name.clone(),
         }
         .into();
-        self.client.lock().await.send_message(&message).await?;
+        client::send_message(&self.client, &message).await?;
         Ok(())
     }
 
+    /// Stop pairing of all adapters before unloading, so discovery does not outlive the plugin.
+    ///
+    /// Aborts their [discovery tasks][crate::AdapterHandle::spawn_discovery] and calls
+    /// [Adapter::on_cancel_pairing] of adapters which are still pairing. Errors are only logged.
+    pub(crate) async fn cancel_pairing(&self) {
+        for adapter_id in self.adapter_ids() {
+            let mut adapter = self.adapters[&adapter_id].lock().await;
+            let pairing = adapter.adapter_handle().pairing();
+            adapter.adapter_handle_mut().cancel_pairing();
+            if !pairing {
+                continue;
+            }
+            if let Err(err) = adapter.on_cancel_pairing().await {
+                log::warn!(
+                    "{}Could not cancel pairing of adapter {}: {}",
+                    self.log_prefix,
+                    adapter_id,
+                    err
+                );
+            }
+        }
+    }
+
     /// Unload this plugin.
     pub async fn unload(&self) -> Result<(), WebthingsError> {
         let message: Message = PluginUnloadResponseMessageData {
@@ -151,12 +788,15 @@ impl Plugin {
         }
         .into();
 
-        self.client.lock().await.send_message(&message).await
+        client::send_message(&self.client, &message).await
     }
 
     /// Fail this plugin.
     ///
     /// This should be done when an error occurs which we cannot recover from.
+    ///
+    /// Exits the process unless an [exit strategy][Plugin::set_exit_strategy] is set,
+    /// in which case this returns after invoking it.
     pub async fn fail(&self, message: impl Into<String>) -> Result<(), WebthingsError> {
         let message: Message = PluginErrorNotificationMessageData {
             plugin_id: self.plugin_id.clone(),
@@ -164,13 +804,33 @@ impl Plugin {
         }
         .into();
 
-        self.client.lock().await.send_message(&message).await?;
+        client::send_message(&self.client, &message).await?;
 
         self.unload().await?;
 
-        sleep(Duration::from_millis(500)).await;
+        self.close(FAIL_CLOSE_TIMEOUT).await;
+
+        match &self.exit_strategy {
+            Some(exit_strategy) => {
+                exit_strategy(DONT_RESTART_EXIT_CODE);
+                Ok(())
+            }
+            None => process::exit(DONT_RESTART_EXIT_CODE),
+        }
+    }
 
-        process::exit(DONT_RESTART_EXIT_CODE);
+    /// Flush pending messages and close the connection, giving up after the timeout.
+    async fn close(&self, close_timeout: Duration) {
+        let close = async { self.client.lock().await.close().await };
+        match timeout(close_timeout, close).await {
+            Ok(Ok(())) => {}
+            Ok(Err(err)) => log::warn!("{}Could not close connection: {}", self.log_prefix, err),
+            Err(_) => log::warn!(
+                "{}Could not close connection within {:?}",
+                self.log_prefix,
+                close_timeout
+            ),
+        }
     }
 
     /// Get the associated config database of this plugin.
@@ -182,14 +842,28 @@ impl Plugin {
 
 #[cfg(test)]
 pub(crate) mod tests {
+    #[cfg(feature = "testing")]
+    use crate::client::{tap_message, MessageTap};
     use crate::{
-        adapter::tests::MockAdapter, api_handler::tests::MockApiHandler, plugin::connect, Adapter,
-        Plugin,
+        adapter::tests::{add_mock_device, MockAdapter},
+        api_handler::tests::MockApiHandler,
+        client::{QueueConfig, RetryPolicy},
+        device::tests::MockDevice,
+        error::WebthingsError,
+        plugin::{connect, mailbox_key, plugin_struct::DONT_RESTART_EXIT_CODE, MessageResult},
+        property::tests::BuiltMockProperty,
+        Adapter, Plugin,
     };
+    use as_any::Downcast;
+    use futures::stream::StreamExt;
     use rstest::{fixture, rstest};
-    use std::sync::Arc;
-    use tokio::sync::Mutex;
-    use webthings_gateway_ipc_types::Message;
+    use serde_json::json;
+    use std::{sync::Arc, time::Duration};
+    use tokio::{sync::Mutex, time::sleep};
+    use webthings_gateway_ipc_types::{
+        AdapterRemoveDeviceRequestMessageData, DeviceSetPropertyCommandMessageData, Message,
+        PluginUnloadRequestMessageData,
+    };
 
     pub async fn add_mock_adapter(
         plugin: &mut Plugin,
@@ -216,7 +890,7 @@ pub(crate) mod tests {
     }
 
     pub async fn set_mock_api_handler(plugin: &mut Plugin) {
-        let plugin_id = plugin.plugin_id.to_owned();
+        let plugin_id = plugin.plugin_id().to_owned();
 
         plugin
             .client
@@ -240,6 +914,54 @@ pub(crate) mod tests {
 
     const PLUGIN_ID: &str = "plugin_id";
     const ADAPTER_ID: &str = "adapter_id";
+    const DEVICE_ID: &str = "device_id";
+
+    #[rstest]
+    #[case(None, PLUGIN_ID)]
+    #[case(Some("example-package"), "example-package")]
+    #[tokio::test]
+    async fn test_package_name(
+        mut plugin: Plugin,
+        #[case] package_name: Option<&'static str>,
+        #[case] expected: &'static str,
+    ) {
+        if let Some(package_name) = package_name {
+            plugin.set_package_name(package_name).await;
+        }
+        assert_eq!(plugin.package_name(), expected);
+
+        plugin
+            .client
+            .lock()
+            .await
+            .expect_send_message()
+            .withf(move |msg| match msg {
+                Message::AdapterAddedNotification(msg) => {
+                    msg.data.plugin_id == PLUGIN_ID && msg.data.package_name == expected
+                }
+                Message::ApiHandlerAddedNotification(msg) => {
+                    msg.data.plugin_id == PLUGIN_ID && msg.data.package_name == expected
+                }
+                _ => false,
+            })
+            .times(2)
+            .returning(|_| Ok(()));
+
+        plugin
+            .add_adapter(MockAdapter::new(ADAPTER_ID.to_owned()))
+            .await
+            .unwrap();
+        plugin.set_api_handler(MockApiHandler::new()).await.unwrap();
+        assert_eq!(
+            plugin
+                .api_handler
+                .lock()
+                .await
+                .api_handler_handle()
+                .package_name(),
+            expected
+        );
+    }
 
     #[rstest]
     #[tokio::test]
@@ -248,12 +970,455 @@ pub(crate) mod tests {
         assert!(plugin.borrow_adapter(ADAPTER_ID).is_ok());
     }
 
+    #[rstest]
+    #[tokio::test]
+    async fn test_adapters(mut plugin: Plugin) {
+        assert!(plugin.adapters().is_empty());
+        assert!(plugin.adapter_ids().is_empty());
+
+        let adapter = add_mock_adapter(&mut plugin, ADAPTER_ID).await;
+
+        let adapters = plugin.adapters();
+        assert_eq!(adapters.len(), 1);
+        assert!(Arc::ptr_eq(&adapters[ADAPTER_ID], &adapter));
+        assert_eq!(plugin.adapter_ids(), vec![ADAPTER_ID.to_owned()]);
+    }
+
     #[rstest]
     #[tokio::test]
     async fn test_borrow_unknown_adapter(mut plugin: Plugin) {
         assert!(plugin.borrow_adapter(ADAPTER_ID).is_err());
     }
 
+    #[rstest]
+    #[tokio::test]
+    async fn test_default_log_prefix(plugin: Plugin) {
+        assert_eq!(plugin.log_prefix(), "[plugin_id] ");
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_set_log_prefix(mut plugin: Plugin) {
+        plugin
+            .client
+            .lock()
+            .await
+            .expect_set_log_prefix()
+            .withf(|log_prefix| log_prefix == "foo: ")
+            .times(1)
+            .return_const(());
+
+        let adapter = add_mock_adapter(&mut plugin, ADAPTER_ID).await;
+        assert_eq!(
+            adapter.lock().await.adapter_handle().log_prefix,
+            "[plugin_id] "
+        );
+        let device = add_mock_device(adapter.lock().await.adapter_handle_mut(), DEVICE_ID).await;
+        assert_eq!(
+            device.lock().await.device_handle().log_prefix,
+            "[plugin_id] "
+        );
+
+        plugin.set_log_prefix("foo: ").await;
+        assert_eq!(plugin.log_prefix(), "foo: ");
+        assert_eq!(adapter.lock().await.adapter_handle().log_prefix, "foo: ");
+        assert_eq!(device.lock().await.device_handle().log_prefix, "foo: ");
+        assert_eq!(
+            plugin
+                .api_handler
+                .lock()
+                .await
+                .api_handler_handle()
+                .log_prefix,
+            "foo: "
+        );
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_set_retry_policy(mut plugin: Plugin) {
+        let retry_policy = RetryPolicy {
+            retries: 3,
+            backoff: Duration::from_millis(10),
+        };
+
+        plugin
+            .client
+            .lock()
+            .await
+            .expect_set_retry_policy()
+            .withf(move |p| *p == retry_policy)
+            .times(1)
+            .return_const(());
+
+        plugin.set_retry_policy(retry_policy).await;
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_set_queue_config(mut plugin: Plugin) {
+        let queue_config = QueueConfig {
+            capacity: Some(16),
+            ..QueueConfig::default()
+        };
+
+        plugin
+            .client
+            .lock()
+            .await
+            .expect_set_queue_config()
+            .withf(move |c| *c == queue_config)
+            .times(1)
+            .return_const(());
+
+        plugin.set_queue_config(queue_config).await;
+    }
+
+    #[rstest]
+    #[case(None)]
+    #[case(Some(2))]
+    #[tokio::test]
+    async fn test_dispatch(mut plugin: Plugin, #[case] concurrency_limit: Option<usize>) {
+        let adapter = add_mock_adapter(&mut plugin, ADAPTER_ID).await;
+        let device = add_mock_device(adapter.lock().await.adapter_handle_mut(), DEVICE_ID).await;
+        plugin.set_concurrency_limit(concurrency_limit);
+
+        {
+            let device = device.lock().await;
+            let property = device
+                .device_handle()
+                .get_property(MockDevice::PROPERTY_I32)
+                .unwrap();
+            let mut property = property.lock().await;
+            let property = property.downcast_mut::<BuiltMockProperty<i32>>().unwrap();
+            property
+                .expect_on_update()
+                .times(concurrency_limit.map_or(0, |_| 1))
+                .returning(|_| Ok(()));
+        }
+
+        plugin
+            .client
+            .lock()
+            .await
+            .expect_send_message()
+            .times(concurrency_limit.map_or(0, |_| 1))
+            .returning(|_| Ok(()));
+
+        let message: Message = DeviceSetPropertyCommandMessageData {
+            plugin_id: PLUGIN_ID.to_owned(),
+            adapter_id: ADAPTER_ID.to_owned(),
+            device_id: DEVICE_ID.to_owned(),
+            property_name: MockDevice::PROPERTY_I32.to_owned(),
+            property_value: json!(42),
+        }
+        .into();
+
+        assert_eq!(
+            plugin.dispatch_to_mailbox(message).await.is_some(),
+            concurrency_limit.is_none()
+        );
+        sleep(Duration::from_millis(20)).await;
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_close_mailbox_on_remove_device(mut plugin: Plugin) {
+        plugin.set_concurrency_limit(Some(2));
+        let key = mailbox_key(ADAPTER_ID, DEVICE_ID);
+        let dispatcher = plugin.dispatcher.as_mut().unwrap();
+        dispatcher.dispatch(key.clone(), async {});
+        assert!(dispatcher.is_open(&key));
+
+        let message: Message = AdapterRemoveDeviceRequestMessageData {
+            plugin_id: PLUGIN_ID.to_owned(),
+            adapter_id: ADAPTER_ID.to_owned(),
+            device_id: DEVICE_ID.to_owned(),
+        }
+        .into();
+        plugin.close_mailboxes(&message);
+        assert!(!plugin.dispatcher.as_ref().unwrap().is_open(&key));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_close_mailbox_on_adapter_remove_device(mut plugin: Plugin) {
+        plugin.set_concurrency_limit(Some(2));
+        let adapter = add_mock_adapter(&mut plugin, ADAPTER_ID).await;
+        add_mock_device(adapter.lock().await.adapter_handle_mut(), DEVICE_ID).await;
+        let key = mailbox_key(ADAPTER_ID, DEVICE_ID);
+        plugin
+            .dispatcher
+            .as_mut()
+            .unwrap()
+            .dispatch(key.clone(), async {});
+        assert!(plugin.dispatcher.as_ref().unwrap().is_open(&key));
+
+        adapter
+            .lock()
+            .await
+            .adapter_handle_mut()
+            .client
+            .lock()
+            .await
+            .expect_send_message()
+            .returning(|_| Ok(()));
+        adapter
+            .lock()
+            .await
+            .adapter_handle_mut()
+            .remove_device(DEVICE_ID)
+            .await
+            .unwrap();
+        assert!(!plugin.dispatcher.as_ref().unwrap().is_open(&key));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_set_message_tap(mut plugin: Plugin) {
+        plugin
+            .client
+            .lock()
+            .await
+            .expect_set_message_tap()
+            .withf(|message_tap| message_tap.is_some())
+            .times(1)
+            .return_const(());
+
+        plugin.set_message_tap(Box::new(|_| {})).await;
+        assert!(plugin.message_tap.is_some());
+
+        plugin
+            .client
+            .lock()
+            .await
+            .expect_set_message_tap()
+            .withf(|message_tap| message_tap.is_none())
+            .times(1)
+            .return_const(());
+
+        plugin.clear_message_tap().await;
+        assert!(plugin.message_tap.is_none());
+    }
+
+    #[cfg(feature = "testing")]
+    async fn forward_message_tap(plugin: &mut Plugin) -> Arc<std::sync::Mutex<Option<MessageTap>>> {
+        let message_tap = Arc::new(std::sync::Mutex::new(None));
+        let stored_tap = message_tap.clone();
+        plugin
+            .client
+            .lock()
+            .await
+            .expect_set_message_tap()
+            .returning(move |message_tap| *stored_tap.lock().unwrap() = message_tap);
+        message_tap
+    }
+
+    #[cfg(feature = "testing")]
+    #[rstest]
+    #[tokio::test]
+    async fn test_wait_for_message(mut plugin: Plugin) {
+        let adapter = add_mock_adapter(&mut plugin, ADAPTER_ID).await;
+        let device = add_mock_device(adapter.lock().await.adapter_handle_mut(), DEVICE_ID).await;
+        let message_tap = forward_message_tap(&mut plugin).await;
+
+        let sent_tap = message_tap.clone();
+        plugin
+            .client
+            .lock()
+            .await
+            .expect_send_message()
+            .times(1)
+            .returning(move |message| {
+                tap_message(&sent_tap.lock().unwrap(), "", message);
+                Ok(())
+            });
+
+        let message = plugin
+            .wait_for_message(
+                async {
+                    let device = device.lock().await;
+                    device
+                        .device_handle()
+                        .set_property_value(MockDevice::PROPERTY_I32, Some(json!(42)))
+                        .await
+                        .unwrap();
+                },
+                |message| matches!(message, Message::DevicePropertyChangedNotification(_)),
+                Duration::from_secs(1),
+            )
+            .await
+            .unwrap();
+
+        assert!(
+            matches!(message, Message::DevicePropertyChangedNotification(msg) if msg.data.property.value == Some(json!(42)))
+        );
+        assert!(message_tap.lock().unwrap().is_none());
+    }
+
+    #[cfg(feature = "testing")]
+    #[rstest]
+    #[tokio::test]
+    async fn test_wait_for_message_timeout(mut plugin: Plugin) {
+        forward_message_tap(&mut plugin).await;
+
+        let result = plugin
+            .wait_for_message(async {}, |_| true, Duration::from_millis(10))
+            .await;
+
+        assert!(matches!(result, Err(WebthingsError::MessageTimeout)));
+    }
+
+    #[rstest]
+    fn test_io_stats(plugin: Plugin) {
+        assert_eq!(plugin.io_stats().messages_sent, 0);
+
+        plugin.health.record_sent(12);
+        plugin.health.record_received(34);
+
+        let io_stats = plugin.io_stats();
+        assert_eq!(io_stats.messages_sent, 1);
+        assert_eq!(io_stats.bytes_sent, 12);
+        assert_eq!(io_stats.messages_received, 1);
+        assert_eq!(io_stats.bytes_received, 34);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_message_stream_ends_with_connection(plugin: Plugin) {
+        let mut messages = Box::pin(plugin.message_stream());
+        assert!(messages.next().await.is_none());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_raw_frame_hook(mut plugin: Plugin) {
+        let frames = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let frames_clone = frames.clone();
+        plugin.set_raw_frame_hook(Box::new(move |frame| {
+            frames_clone.lock().unwrap().push(frame.to_owned())
+        }));
+
+        let message: Message = PluginUnloadRequestMessageData {
+            plugin_id: PLUGIN_ID.to_owned(),
+        }
+        .into();
+        let valid = serde_json::to_string(&message).unwrap();
+        let invalid = "{\"messageType\": -1}".to_owned();
+        plugin
+            .stream
+            .lock()
+            .await
+            .extend(vec![valid.clone(), invalid.clone()]);
+
+        let mut messages = Box::pin(plugin.message_stream());
+        assert!(matches!(messages.next().await, Some(Ok(_))));
+        assert!(matches!(
+            messages.next().await,
+            Some(Err(WebthingsError::Read(_)))
+        ));
+        assert!(messages.next().await.is_none());
+        assert_eq!(*frames.lock().unwrap(), vec![valid, invalid]);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_dispatch_unload(mut plugin: Plugin) {
+        let message: Message = PluginUnloadRequestMessageData {
+            plugin_id: PLUGIN_ID.to_owned(),
+        }
+        .into();
+
+        plugin
+            .client
+            .lock()
+            .await
+            .expect_send_message()
+            .withf(move |msg| matches!(msg, Message::PluginUnloadResponse(_)))
+            .times(1)
+            .returning(|_| Ok(()));
+
+        assert!(matches!(
+            plugin.dispatch(message).await,
+            Ok(MessageResult::Terminate)
+        ));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_dispatch_filtered(mut plugin: Plugin) {
+        let message: Message = PluginUnloadRequestMessageData {
+            plugin_id: PLUGIN_ID.to_owned(),
+        }
+        .into();
+
+        plugin.set_message_filter(Box::new(|msg| {
+            !matches!(msg, Message::PluginUnloadRequest(_))
+        }));
+        plugin.client.lock().await.expect_send_message().times(0);
+
+        assert!(matches!(
+            plugin.dispatch(message.clone()).await,
+            Ok(MessageResult::Continue)
+        ));
+
+        plugin.clear_message_filter();
+        plugin
+            .client
+            .lock()
+            .await
+            .expect_send_message()
+            .withf(move |msg| matches!(msg, Message::PluginUnloadResponse(_)))
+            .times(1)
+            .returning(|_| Ok(()));
+
+        assert!(matches!(
+            plugin.dispatch(message).await,
+            Ok(MessageResult::Terminate)
+        ));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_close(plugin: Plugin) {
+        plugin
+            .client
+            .lock()
+            .await
+            .expect_close()
+            .times(1)
+            .returning(|| Ok(()));
+
+        plugin.close(Duration::from_millis(100)).await;
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_fail_with_exit_strategy(mut plugin: Plugin) {
+        let exit_code = Arc::new(std::sync::Mutex::new(None));
+        let exit_code_clone = exit_code.clone();
+        plugin.set_exit_strategy(Box::new(move |code| {
+            *exit_code_clone.lock().unwrap() = Some(code);
+        }));
+
+        let mut client = plugin.client.lock().await;
+        client
+            .expect_send_message()
+            .withf(|msg| matches!(msg, Message::PluginErrorNotification(msg) if msg.data.message == "foo"))
+            .times(1)
+            .returning(|_| Ok(()));
+        client
+            .expect_send_message()
+            .withf(|msg| matches!(msg, Message::PluginUnloadResponse(_)))
+            .times(1)
+            .returning(|_| Ok(()));
+        client.expect_close().times(1).returning(|| Ok(()));
+        drop(client);
+
+        plugin.fail("foo").await.unwrap();
+        assert_eq!(*exit_code.lock().unwrap(), Some(DONT_RESTART_EXIT_CODE));
+    }
+
     #[rstest]
     #[tokio::test]
     async fn test_get_config_database(plugin: Plugin) {
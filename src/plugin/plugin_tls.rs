@@ -0,0 +1,112 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+
+use crate::error::WebthingsError;
+use native_tls::{Certificate, TlsConnector};
+
+const PEM_END: &[u8] = b"-----END CERTIFICATE-----";
+
+/// TLS settings for connecting to a gateway via `wss://`, e.g. behind a private CA.
+///
+/// Only used if the gateway url has the `wss` scheme, `ws://` connections ignore it.
+///
+/// # Examples
+/// ```no_run
+/// # use gateway_addon_rust::{error::WebthingsError, plugin::TlsConfig, Plugin};
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() -> Result<(), WebthingsError> {
+/// let ca = std::fs::read("internal-ca.pem").unwrap();
+/// let mut plugin = Plugin::builder("example-addon")
+///     .gateway_url("wss://gateway.internal:4443")
+///     .tls_config(TlsConfig::default().root_certificate_pem(ca))
+///     .connect()
+///     .await?;
+/// plugin.event_loop().await;
+/// #   Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TlsConfig {
+    root_certificates: Vec<Vec<u8>>,
+}
+
+/// # Builder methods
+impl TlsConfig {
+    /// Trust the PEM encoded root certificates in addition to the system's ones.
+    ///
+    /// `pem` may be a bundle of several certificates.
+    #[must_use]
+    pub fn root_certificate_pem(mut self, pem: impl Into<Vec<u8>>) -> Self {
+        self.root_certificates.push(pem.into());
+        self
+    }
+}
+
+impl TlsConfig {
+    /// Build the connector used for `wss://` connections.
+    ///
+    /// Fails with [WebthingsError::Tls] if a certificate is invalid.
+    pub(crate) fn connector(&self) -> Result<TlsConnector, WebthingsError> {
+        let mut builder = TlsConnector::builder();
+        for pem in &self.root_certificates {
+            for certificate in split_pem(pem) {
+                builder.add_root_certificate(
+                    Certificate::from_pem(certificate).map_err(WebthingsError::Tls)?,
+                );
+            }
+        }
+        builder.build().map_err(WebthingsError::Tls)
+    }
+}
+
+/// Split a PEM bundle into its certificates.
+fn split_pem(pem: &[u8]) -> Vec<&[u8]> {
+    let mut certificates = Vec::new();
+    let mut rest = pem;
+    while let Some(end) = rest
+        .windows(PEM_END.len())
+        .position(|window| window == PEM_END)
+    {
+        let (certificate, remainder) = rest.split_at(end + PEM_END.len());
+        certificates.push(certificate);
+        rest = remainder;
+    }
+    if certificates.is_empty() {
+        certificates.push(pem);
+    }
+    certificates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{split_pem, TlsConfig};
+    use crate::error::WebthingsError;
+
+    const CERTIFICATE: &str = "-----BEGIN CERTIFICATE-----\nfoo\n-----END CERTIFICATE-----";
+
+    #[test]
+    fn test_split_pem() {
+        let bundle = format!("{}\n{}\n", CERTIFICATE, CERTIFICATE);
+        let certificates = split_pem(bundle.as_bytes());
+        assert_eq!(certificates.len(), 2);
+        assert_eq!(certificates[0], CERTIFICATE.as_bytes());
+        assert_eq!(certificates[1], format!("\n{}", CERTIFICATE).as_bytes());
+    }
+
+    #[test]
+    fn test_invalid_certificate() {
+        let tls_config = TlsConfig::default().root_certificate_pem("foo");
+        assert!(matches!(
+            tls_config.connector(),
+            Err(WebthingsError::Tls(_))
+        ));
+    }
+
+    #[test]
+    fn test_no_certificates() {
+        assert!(TlsConfig::default().connector().is_ok());
+    }
+}
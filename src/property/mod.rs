@@ -6,21 +6,32 @@
 
 //! A module for everything related to WoT properties.
 
+#[cfg(feature = "runtime")]
 mod property_builder;
 mod property_description;
+#[cfg(feature = "runtime")]
 mod property_handle;
+#[cfg(feature = "runtime")]
 mod property_macro;
+mod property_patch;
+#[cfg(feature = "runtime")]
 mod property_trait;
 mod property_value;
 
+#[cfg(feature = "runtime")]
 pub use property_builder::*;
 pub use property_description::*;
+#[cfg(feature = "runtime")]
 pub use property_handle::*;
+#[cfg(feature = "runtime")]
 pub use property_macro::*;
+pub use property_patch::*;
+#[cfg(feature = "runtime")]
 pub use property_trait::*;
 pub use property_value::*;
 
 /// Convenience type for a collection of [PropertyBuilderBase].
+#[cfg(feature = "runtime")]
 pub type Properties = Vec<Box<dyn PropertyBuilderBase>>;
 
 /// Convenience macro for building a [Properties].
@@ -31,6 +42,7 @@ pub type Properties = Vec<Box<dyn PropertyBuilderBase>>;
 /// properties![ExampleProperty::new()]
 /// # ;
 /// ```
+#[cfg(feature = "runtime")]
 #[macro_export]
 macro_rules! properties [
     ($($e:expr),*) => ({
@@ -39,7 +51,7 @@ macro_rules! properties [
     })
 ];
 
-#[cfg(test)]
+#[cfg(all(test, feature = "runtime"))]
 pub(crate) mod tests {
     pub use super::{property_builder::tests::*, property_trait::tests::*};
 }
@@ -7,12 +7,13 @@
 use crate::{
     client::Client,
     error::WebthingsError,
-    property::{PropertyBase, Value},
+    property::{PropertyBase, PropertyHandleBase, Value},
     Device, Property, PropertyDescription, PropertyHandle,
 };
+use as_any::Downcast;
 use std::sync::{Arc, Weak};
 use tokio::sync::Mutex;
-use webthings_gateway_ipc_types::Property as FullPropertyDescription;
+use webthings_gateway_ipc_types::{Message, Property as FullPropertyDescription};
 
 /// A trait used to specify the structure of a WoT property.
 ///
@@ -138,6 +139,18 @@ pub trait PropertyBuilderBase: Send + Sync + 'static {
         adapter_id: String,
         device_id: String,
     ) -> Box<dyn PropertyBase>;
+
+    #[doc(hidden)]
+    fn update_message(
+        &self,
+        property_handle: &dyn PropertyHandleBase,
+    ) -> Result<Message, WebthingsError>;
+
+    #[doc(hidden)]
+    fn update_handle(
+        &self,
+        property_handle: &mut dyn PropertyHandleBase,
+    ) -> Result<(), WebthingsError>;
 }
 
 impl<T: PropertyBuilder> PropertyBuilderBase for T {
@@ -168,11 +181,41 @@ impl<T: PropertyBuilder> PropertyBuilderBase for T {
         );
         Box::new(<T as PropertyBuilder>::build(*self, property_handle))
     }
+
+    fn update_message(
+        &self,
+        property_handle: &dyn PropertyHandleBase,
+    ) -> Result<Message, WebthingsError> {
+        let property_handle = property_handle
+            .downcast_ref::<PropertyHandle<<Self as PropertyStructure>::Value>>()
+            .ok_or_else(|| type_mismatch(self))?;
+        let value = property_handle.description.value.clone();
+        property_handle.value_message_with(&self.description().value(value))
+    }
+
+    fn update_handle(
+        &self,
+        property_handle: &mut dyn PropertyHandleBase,
+    ) -> Result<(), WebthingsError> {
+        let property_handle = property_handle
+            .downcast_mut::<PropertyHandle<<Self as PropertyStructure>::Value>>()
+            .ok_or_else(|| type_mismatch(self))?;
+        let value = property_handle.description.value.clone();
+        property_handle.description = self.description().value(value);
+        Ok(())
+    }
+}
+
+fn type_mismatch<T: PropertyStructure>(property: &T) -> WebthingsError {
+    WebthingsError::PropertyTypeMismatch(property.name(), std::any::type_name::<T::Value>())
 }
 
 #[cfg(test)]
 pub(crate) mod tests {
-    use std::ops::{Deref, DerefMut};
+    use std::{
+        ops::{Deref, DerefMut},
+        time::Duration,
+    };
 
     use crate::{
         property::{self, tests::BuiltMockProperty, PropertyBuilder},
@@ -184,12 +227,16 @@ pub(crate) mod tests {
         pub PropertyHelper<T> {
             pub fn on_update(&self, value: T) -> Result<(), String>;
             pub fn post_init(&mut self);
+            pub fn poll(&mut self) -> Result<T, String>;
         }
     }
 
     pub struct MockProperty<T: property::Value> {
         property_name: String,
         pub expect_post_init: bool,
+        pub poll_interval: Option<Duration>,
+        pub patchable: bool,
+        pub description: PropertyDescription<T>,
         pub property_helper: MockPropertyHelper<T>,
     }
 
@@ -198,6 +245,9 @@ pub(crate) mod tests {
             Self {
                 property_name,
                 expect_post_init: false,
+                poll_interval: None,
+                patchable: false,
+                description: PropertyDescription::default(),
                 property_helper: MockPropertyHelper::new(),
             }
         }
@@ -225,7 +275,7 @@ pub(crate) mod tests {
         }
 
         fn description(&self) -> PropertyDescription<Self::Value> {
-            PropertyDescription::default()
+            self.description.clone()
         }
     }
 
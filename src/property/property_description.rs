@@ -4,8 +4,14 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
  */
 
-use crate::{error::WebthingsError, property::Value, type_::Type};
-use std::marker::PhantomData;
+use crate::{
+    description::{parse_at_type, IntoFullDescription},
+    error::WebthingsError,
+    property::Value,
+    type_::Type,
+};
+use serde::Deserialize;
+use std::{marker::PhantomData, sync::Arc};
 use webthings_gateway_ipc_types::{Link, Property as FullPropertyDescription};
 
 /// A struct which represents a WoT [property description][webthings_gateway_ipc_types::Property].
@@ -31,6 +37,10 @@ pub struct PropertyDescription<T: Value> {
     pub at_type: Option<AtType>,
     pub description: Option<String>,
     pub enum_: Option<Vec<T>>,
+    /// Display labels of the `enum` values, in the same order.
+    ///
+    /// Not part of the IPC property description, see [enum_labeled][PropertyDescription::enum_labeled].
+    pub enum_labels: Option<Vec<String>>,
     pub links: Option<Vec<Link>>,
     pub maximum: Option<f64>,
     pub minimum: Option<f64>,
@@ -41,11 +51,73 @@ pub struct PropertyDescription<T: Value> {
     pub unit: Option<String>,
     pub value: T,
     pub visible: Option<bool>,
+    /// Only notify the gateway about a new value if it differs from the current one.
+    ///
+    /// Not part of the WoT description, see [notify_on_change_only][PropertyDescription::notify_on_change_only].
+    pub notify_on_change_only: bool,
+    /// How to handle a `unit` which does not match the `@type`.
+    ///
+    /// Not part of the WoT description, see [unit_validation][PropertyDescription::unit_validation].
+    pub unit_validation: UnitValidation,
+    /// How to send a value which serializes to `None`.
+    ///
+    /// Not part of the WoT description, see [none_policy][PropertyDescription::none_policy].
+    pub none_policy: NonePolicy,
+    /// Number of decimal places of floating point values sent to the gateway.
+    ///
+    /// Not part of the WoT description, see [precision][PropertyDescription::precision].
+    pub precision: Option<u32>,
+    /// Whether changes of the value are reported to the gateway.
+    ///
+    /// Not part of the IPC property description, see [observable][PropertyDescription::observable].
+    pub observable: Option<bool>,
+    /// Whether the value can only be written but never read.
+    ///
+    /// Not part of the IPC property description, see [write_only][PropertyDescription::write_only].
+    pub write_only: Option<bool>,
+    /// Conversion of serialized values sent to the gateway.
+    ///
+    /// Not part of the WoT description, see [transform][PropertyDescription::transform].
+    pub serialize_transform: Option<ValueTransform>,
+    /// Conversion of serialized values received from the gateway.
+    ///
+    /// Not part of the WoT description, see [transform][PropertyDescription::transform].
+    pub deserialize_transform: Option<ValueTransform>,
     _value: PhantomData<T>,
 }
 
+/// A conversion of a serialized [value][Value] of a [property][PropertyDescription],
+/// see [transform][PropertyDescription::transform].
+pub type ValueTransform =
+    Arc<dyn Fn(serde_json::Value) -> serde_json::Value + Send + Sync + 'static>;
+
+/// How to send a [value][Value] of a [property][PropertyDescription] which serializes to `None`,
+/// e.g. `None` of an `Option<T>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NonePolicy {
+    /// Omit the `value`.
+    #[default]
+    Omit,
+    /// Send `null` as the `value`.
+    Null,
+}
+
+/// How to handle a `unit` of a [property][PropertyDescription] which does not match its `@type`.
+///
+/// See [AtType::units] for the expected units.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnitValidation {
+    /// Do not check the unit.
+    Off,
+    /// Log a warning.
+    #[default]
+    Warn,
+    /// Fail with [WebthingsError::UnitMismatch].
+    Error,
+}
+
 /// Possible values of `@type` for a [property][PropertyDescription].
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Deserialize)]
 pub enum AtType {
     AlarmProperty,
     BarometricPressureProperty,
@@ -76,14 +148,73 @@ pub enum AtType {
     ThermostatModeProperty,
     VideoProperty,
     VoltageProperty,
+    /// A `@type` unknown to this crate, e.g. of a description parsed with `from_full`.
+    Other(String),
 }
 
 impl ToString for AtType {
     fn to_string(&self) -> String {
-        format!("{:?}", self)
+        match self {
+            AtType::Other(at_type) => at_type.clone(),
+            at_type => format!("{:?}", at_type),
+        }
+    }
+}
+
+impl AtType {
+    /// Units which are consistent with this `@type`, compared case-insensitively.
+    ///
+    /// Returns `None` if the `@type` does not imply a unit.
+    pub fn units(&self) -> Option<&'static [&'static str]> {
+        let units: &'static [&'static str] = match self {
+            AtType::BarometricPressureProperty => {
+                &["hectopascal", "pascal", "kilopascal", "millibar", "bar"]
+            }
+            AtType::BrightnessProperty | AtType::HumidityProperty => &["percent", "%"],
+            AtType::ColorTemperatureProperty => &["kelvin"],
+            AtType::ConcentrationProperty => &["ppm", "ppb"],
+            AtType::CurrentProperty => &["ampere", "milliampere"],
+            AtType::DensityProperty => &["microgram per cubic metre", "micrograms per cubic metre"],
+            AtType::FrequencyProperty => &["hertz"],
+            AtType::InstantaneousPowerProperty => &["watt", "kilowatt"],
+            AtType::TargetTemperatureProperty | AtType::TemperatureProperty => &[
+                "degree celsius",
+                "degree fahrenheit",
+                "celsius",
+                "fahrenheit",
+                "kelvin",
+            ],
+            AtType::VoltageProperty => &["volt", "millivolt"],
+            _ => return None,
+        };
+        Some(units)
+    }
+}
+
+impl<T: Value> IntoFullDescription for PropertyDescription<T> {
+    type FullDescription = FullPropertyDescription;
+
+    fn into_full_description(
+        self,
+        name: String,
+    ) -> Result<FullPropertyDescription, WebthingsError> {
+        let value = self.serialize_value(self.value.clone())?;
+        self.into_full_description_with_value(name, value)
     }
 }
 
+/// Round a floating point `value` to `precision` decimal places.
+fn round(value: serde_json::Value, precision: u32) -> serde_json::Value {
+    let number = match value.as_f64() {
+        Some(number) if value.is_f64() => number,
+        _ => return value,
+    };
+    let factor = 10_f64.powi(precision as i32);
+    serde_json::Number::from_f64((number * factor).round() / factor)
+        .map(serde_json::Value::Number)
+        .unwrap_or(value)
+}
+
 /// # Builder methods
 impl<T: Value> PropertyDescription<T> {
     /// Build an empty [PropertyDescription].
@@ -92,6 +223,7 @@ impl<T: Value> PropertyDescription<T> {
             at_type: None,
             description: None,
             enum_: None,
+            enum_labels: None,
             links: None,
             maximum: None,
             minimum: None,
@@ -102,17 +234,34 @@ impl<T: Value> PropertyDescription<T> {
             unit: None,
             value: T::default(),
             visible: None,
+            notify_on_change_only: false,
+            unit_validation: UnitValidation::default(),
+            none_policy: NonePolicy::default(),
+            precision: None,
+            observable: None,
+            write_only: None,
+            serialize_transform: None,
+            deserialize_transform: None,
             _value: PhantomData,
         })
     }
 
     /// Set `@type`.
+    ///
+    /// This overrides the default `@type` of the [value type][Value::description].
     #[must_use]
     pub fn at_type(mut self, at_type: AtType) -> Self {
         self.at_type = Some(at_type);
         self
     }
 
+    /// Remove `@type`, including the default `@type` of the [value type][Value::description].
+    #[must_use]
+    pub fn clear_at_type(mut self) -> Self {
+        self.at_type = None;
+        self
+    }
+
     /// Set `description`.
     #[must_use]
     pub fn description(mut self, description: impl Into<String>) -> Self {
@@ -121,9 +270,37 @@ impl<T: Value> PropertyDescription<T> {
     }
 
     /// Set `enum`.
+    ///
+    /// The [value][PropertyDescription::value] of the property has to be one of these,
+    /// unless it serializes to `None`.
     #[must_use]
     pub fn enum_(mut self, enum_: Vec<T>) -> Self {
         self.enum_ = Some(enum_);
+        self.enum_labels = None;
+        self
+    }
+
+    /// Set `enum` with a display label for each value.
+    ///
+    /// The gateway only receives the plain values, as the IPC property description has no field for
+    /// labels. Use [labeled_enum][PropertyDescription::labeled_enum] to get the labeled options,
+    /// e.g. to serve them from an [API handler][crate::api_handler::ApiHandler].
+    ///
+    /// # Examples
+    /// ```
+    /// # use gateway_addon_rust::prelude::*;
+    /// # let _ =
+    /// PropertyDescription::<String>::default().enum_labeled(vec![
+    ///     ("eco".to_owned(), "Eco mode".to_owned()),
+    ///     ("comfort".to_owned(), "Comfort mode".to_owned()),
+    /// ])
+    /// # ;
+    /// ```
+    #[must_use]
+    pub fn enum_labeled(mut self, enum_: Vec<(T, String)>) -> Self {
+        let (values, labels) = enum_.into_iter().unzip();
+        self.enum_ = Some(values);
+        self.enum_labels = Some(labels);
         self
     }
 
@@ -139,19 +316,11 @@ impl<T: Value> PropertyDescription<T> {
     /// # Examples
     /// ```
     /// # use gateway_addon_rust::property::PropertyDescription;
-    /// # use webthings_gateway_ipc_types::Link;
+    /// # use gateway_addon_rust::link::{Link, LinkBuilder};
     /// # let _: PropertyDescription<i32> =
     /// PropertyDescription::default()
-    ///     .link(Link {
-    ///         href: "https://www.rust-lang.org/".to_owned(),
-    ///         media_type: None,
-    ///         rel: None,
-    ///     })
-    ///     .link(Link {
-    ///         href: "https://www.reddit.com/".to_owned(),
-    ///         media_type: None,
-    ///         rel: None,
-    ///     })
+    ///     .link(Link::new("https://www.rust-lang.org/"))
+    ///     .link(Link::new("https://www.reddit.com/"))
     /// # ;
     /// ```
     #[must_use]
@@ -164,6 +333,8 @@ impl<T: Value> PropertyDescription<T> {
     }
 
     /// Set `maximum`.
+    ///
+    /// Bounds are advertised as `f64`, so integer bounds beyond ±2^53 are rounded.
     #[must_use]
     pub fn maximum<F: Into<f64>>(mut self, maximum: F) -> Self {
         self.maximum = Some(maximum.into());
@@ -171,6 +342,8 @@ impl<T: Value> PropertyDescription<T> {
     }
 
     /// Set `minimum`.
+    ///
+    /// Bounds are advertised as `f64`, so integer bounds beyond ±2^53 are rounded.
     #[must_use]
     pub fn minimum<F: Into<f64>>(mut self, minimum: F) -> Self {
         self.minimum = Some(minimum.into());
@@ -233,15 +406,156 @@ impl<T: Value> PropertyDescription<T> {
         self
     }
 
+    /// Only notify the gateway in [set_value][crate::PropertyHandle::set_value] if the value changed.
+    ///
+    /// Values are compared by their serialized JSON form. By default, every value is sent.
+    #[must_use]
+    pub fn notify_on_change_only(mut self, notify_on_change_only: bool) -> Self {
+        self.notify_on_change_only = notify_on_change_only;
+        self
+    }
+
+    /// Set how to handle a `unit` which does not match the `@type`.
+    ///
+    /// By default, a warning is logged.
+    #[must_use]
+    pub fn unit_validation(mut self, unit_validation: UnitValidation) -> Self {
+        self.unit_validation = unit_validation;
+        self
+    }
+
+    /// Set how to send a value which serializes to `None`, in the description as well as in value notifications.
+    ///
+    /// By default, the `value` is omitted.
+    #[must_use]
+    pub fn none_policy(mut self, none_policy: NonePolicy) -> Self {
+        self.none_policy = none_policy;
+        self
+    }
+
+    /// Round floating point values to `precision` decimal places, in the description as well as in value notifications.
+    ///
+    /// Only the sent value is rounded, the value itself keeps full precision. With
+    /// [notify_on_change_only][PropertyDescription::notify_on_change_only], changes are detected on the rounded values.
+    #[must_use]
+    pub fn precision(mut self, precision: u32) -> Self {
+        self.precision = Some(precision);
+        self
+    }
+
+    /// Set `observable`.
+    ///
+    /// Combine it with [read_only][PropertyDescription::read_only] for an observe-only property,
+    /// whose value the gateway can observe but not change.
+    /// As the IPC property description has no `observable` field, this is not sent to the gateway.
+    #[must_use]
+    pub fn observable(mut self, observable: bool) -> Self {
+        self.observable = Some(observable);
+        self
+    }
+
+    /// Set `writeOnly`.
+    ///
+    /// The value of a write-only property, e.g. a momentary trigger, is never reported to the gateway:
+    /// it is omitted from the description, [set_value][crate::PropertyHandle::set_value] does not notify
+    /// and [notify_value][crate::PropertyHandle::notify_value] fails with [WebthingsError::WriteOnlyProperty].
+    /// A write-only property can neither be [read_only][PropertyDescription::read_only] nor
+    /// [observable][PropertyDescription::observable].
+    /// As the IPC property description has no `writeOnly` field, the flag itself is not sent to the gateway.
+    #[must_use]
+    pub fn write_only(mut self, write_only: bool) -> Self {
+        self.write_only = Some(write_only);
+        self
+    }
+
+    /// Convert values between their natural serialized form and the form sent to and received from the gateway.
+    ///
+    /// `serialize` is applied to values sent to the gateway, including the `enum` values, and
+    /// `deserialize` to values received from the gateway. They should be inverse to each other.
+    /// Values which serialize to `None` are not transformed.
+    ///
+    /// # Examples
+    /// ```
+    /// # use gateway_addon_rust::prelude::*;
+    /// # use serde_json::json;
+    /// // Stored in Kelvin, exposed in Celsius
+    /// let description = PropertyDescription::<f64>::default()
+    ///     .unit("degree celsius")
+    ///     .value(293.15)
+    ///     .transform(
+    ///         |kelvin| json!(kelvin.as_f64().unwrap_or_default() - 273.15),
+    ///         |celsius| json!(celsius.as_f64().unwrap_or_default() + 273.15),
+    ///     );
+    /// assert_eq!(description.serialize_value(293.15).unwrap(), Some(json!(20.0)));
+    /// ```
+    #[must_use]
+    pub fn transform(
+        mut self,
+        serialize: impl Fn(serde_json::Value) -> serde_json::Value + Send + Sync + 'static,
+        deserialize: impl Fn(serde_json::Value) -> serde_json::Value + Send + Sync + 'static,
+    ) -> Self {
+        self.serialize_transform = Some(Arc::new(serialize));
+        self.deserialize_transform = Some(Arc::new(deserialize));
+        self
+    }
+
+    /// Serialize a value like [Value::serialize] and apply the [serialize transform][PropertyDescription::transform].
+    pub fn serialize_value(&self, value: T) -> Result<Option<serde_json::Value>, WebthingsError> {
+        let value = T::serialize(value)?;
+        Ok(match &self.serialize_transform {
+            Some(transform) => value.map(|value| transform(value)),
+            None => value,
+        })
+    }
+
+    /// Apply the [deserialize transform][PropertyDescription::transform] and deserialize a value like [Value::deserialize].
+    pub fn deserialize_value(&self, value: Option<serde_json::Value>) -> Result<T, WebthingsError> {
+        let value = match &self.deserialize_transform {
+            Some(transform) => value.map(|value| transform(value)),
+            None => value,
+        };
+        T::deserialize(value)
+    }
+
+    /// Round a serialized value to the configured [precision][PropertyDescription::precision], if any.
+    pub(crate) fn round_value(
+        &self,
+        value: Option<serde_json::Value>,
+    ) -> Option<serde_json::Value> {
+        match (value, self.precision) {
+            (Some(value), Some(precision)) => Some(round(value, precision)),
+            (value, _) => value,
+        }
+    }
+
+    pub(crate) fn is_write_only(&self) -> bool {
+        self.write_only == Some(true)
+    }
+
+    pub(crate) fn is_read_only(&self) -> bool {
+        self.read_only == Some(true)
+    }
+
     #[doc(hidden)]
     pub fn into_full_description(
         self,
         name: String,
     ) -> Result<FullPropertyDescription, WebthingsError> {
-        let enum_ = if let Some(enum_) = self.enum_ {
+        IntoFullDescription::into_full_description(self, name)
+    }
+
+    /// Like [into_full_description][PropertyDescription::into_full_description], but with an already serialized value.
+    pub(crate) fn into_full_description_with_value(
+        self,
+        name: String,
+        value: Option<serde_json::Value>,
+    ) -> Result<FullPropertyDescription, WebthingsError> {
+        self.validate_unit(&name)?;
+        self.validate_access(&name)?;
+        let enum_ = if let Some(enum_) = &self.enum_ {
             let mut v = Vec::new();
             for e in enum_ {
-                v.push(T::serialize(e)?.ok_or_else(|| {
+                v.push(self.serialize_value(e.clone())?.ok_or_else(|| {
                     WebthingsError::Serialization(<serde_json::Error as serde::ser::Error>::custom(
                         "Expected Some, found None",
                     ))
@@ -251,6 +565,21 @@ impl<T: Value> PropertyDescription<T> {
         } else {
             None
         };
+        if let (Some(enum_), Some(value)) = (&enum_, &value) {
+            if !enum_.contains(value) {
+                return Err(WebthingsError::ValueNotInEnum(name, value.clone()));
+            }
+        }
+        let value = match (value, self.none_policy) {
+            (None, NonePolicy::Null) => Some(serde_json::Value::Null),
+            (value, _) => value,
+        };
+        let value = self.round_value(value);
+        let value = if self.write_only == Some(true) {
+            None
+        } else {
+            value
+        };
         Ok(FullPropertyDescription {
             at_type: self.at_type.map(|t| t.to_string()),
             description: self.description,
@@ -263,9 +592,417 @@ impl<T: Value> PropertyDescription<T> {
             title: self.title,
             type_: self.type_.to_string(),
             unit: self.unit,
-            value: T::serialize(self.value)?,
+            value,
             visible: self.visible,
             name: Some(name),
         })
     }
+
+    fn validate_access(&self, name: &str) -> Result<(), WebthingsError> {
+        if !self.is_write_only() {
+            return Ok(());
+        }
+        if self.is_read_only() {
+            return Err(WebthingsError::InvalidPropertyAccess(
+                name.to_owned(),
+                "read-only",
+            ));
+        }
+        if self.observable == Some(true) {
+            return Err(WebthingsError::InvalidPropertyAccess(
+                name.to_owned(),
+                "observable",
+            ));
+        }
+        Ok(())
+    }
+
+    fn validate_unit(&self, name: &str) -> Result<(), WebthingsError> {
+        if self.unit_validation == UnitValidation::Off {
+            return Ok(());
+        }
+        let (at_type, unit) = match (&self.at_type, &self.unit) {
+            (Some(at_type), Some(unit)) => (at_type, unit),
+            _ => return Ok(()),
+        };
+        let units = match at_type.units() {
+            Some(units) => units,
+            None => return Ok(()),
+        };
+        if units.iter().any(|u| u.eq_ignore_ascii_case(unit)) {
+            return Ok(());
+        }
+        let at_type = at_type.to_string();
+        match self.unit_validation {
+            UnitValidation::Error => Err(WebthingsError::UnitMismatch(
+                name.to_owned(),
+                at_type,
+                unit.clone(),
+            )),
+            _ => {
+                log::warn!(
+                    "Unit {:?} of property {:?} does not match its @type {}",
+                    unit,
+                    name,
+                    at_type
+                );
+                Ok(())
+            }
+        }
+    }
+
+    /// The `enum` values as json schema `oneOf` options, with a `title` for each
+    /// [labeled][PropertyDescription::enum_labeled] value.
+    ///
+    /// Values without a label are listed without a `title`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use gateway_addon_rust::prelude::*;
+    /// # use serde_json::json;
+    /// let description = PropertyDescription::<String>::default()
+    ///     .enum_labeled(vec![("eco".to_owned(), "Eco mode".to_owned())]);
+    /// assert_eq!(
+    ///     description.labeled_enum().unwrap(),
+    ///     Some(vec![json!({"const": "eco", "title": "Eco mode"})])
+    /// );
+    /// ```
+    pub fn labeled_enum(&self) -> Result<Option<Vec<serde_json::Value>>, WebthingsError> {
+        let enum_ = match &self.enum_ {
+            Some(enum_) => enum_,
+            None => return Ok(None),
+        };
+        let labels = self.enum_labels.as_deref().unwrap_or_default();
+
+        let mut options = Vec::new();
+        for (i, e) in enum_.iter().enumerate() {
+            let mut option = serde_json::Map::new();
+            option.insert(
+                "const".to_owned(),
+                self.serialize_value(e.clone())?
+                    .unwrap_or(serde_json::Value::Null),
+            );
+            if let Some(label) = labels.get(i) {
+                option.insert("title".to_owned(), label.clone().into());
+            }
+            options.push(option.into());
+        }
+        Ok(Some(options))
+    }
+
+    /// Parse an existing [full property description][FullPropertyDescription],
+    /// e.g. one received from another WoT source.
+    ///
+    /// Use `PropertyDescription<serde_json::Value>` if the value type of the property is unknown.
+    /// Unknown `@type`s are kept as [AtType::Other].
+    ///
+    /// # Examples
+    /// ```
+    /// # use gateway_addon_rust::property::PropertyDescription;
+    /// # use webthings_gateway_ipc_types::Property as FullPropertyDescription;
+    /// # use serde_json::json;
+    /// let full = FullPropertyDescription {
+    ///     at_type: None,
+    ///     description: None,
+    ///     enum_: None,
+    ///     links: None,
+    ///     maximum: None,
+    ///     minimum: None,
+    ///     multiple_of: None,
+    ///     name: Some("foo".to_owned()),
+    ///     read_only: None,
+    ///     title: Some("Foo".to_owned()),
+    ///     type_: "integer".to_owned(),
+    ///     unit: None,
+    ///     value: Some(json!(42)),
+    ///     visible: None,
+    /// };
+    /// let description = PropertyDescription::<i32>::from_full(full).unwrap();
+    /// assert_eq!(description.value, 42);
+    /// ```
+    pub fn from_full(description: FullPropertyDescription) -> Result<Self, WebthingsError> {
+        let enum_ = if let Some(enum_) = description.enum_ {
+            let mut v = Vec::new();
+            for e in enum_ {
+                v.push(T::deserialize(Some(e))?);
+            }
+            Some(v)
+        } else {
+            None
+        };
+        let at_type = description
+            .at_type
+            .map(|at_type| parse_at_type(at_type, AtType::Other));
+        Ok(Self {
+            at_type,
+            description: description.description,
+            enum_,
+            enum_labels: None,
+            links: description.links,
+            maximum: description.maximum,
+            minimum: description.minimum,
+            multiple_of: description.multiple_of,
+            read_only: description.read_only,
+            title: description.title,
+            type_: description.type_.parse()?,
+            unit: description.unit,
+            value: T::deserialize(description.value)?,
+            visible: description.visible,
+            notify_on_change_only: false,
+            unit_validation: UnitValidation::default(),
+            none_policy: NonePolicy::default(),
+            precision: None,
+            observable: None,
+            write_only: None,
+            serialize_transform: None,
+            deserialize_transform: None,
+            _value: PhantomData,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        error::WebthingsError,
+        property::{AtType, NonePolicy, SimpleValue, UnitValidation},
+        type_::Type,
+        PropertyDescription,
+    };
+    use rstest::rstest;
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+
+    #[derive(Clone, Default, Serialize, Deserialize)]
+    struct Level(i32);
+
+    impl SimpleValue for Level {
+        fn type_() -> Type {
+            Type::Integer
+        }
+
+        fn description(description: PropertyDescription<Self>) -> PropertyDescription<Self> {
+            description.at_type(AtType::LevelProperty)
+        }
+    }
+
+    #[test]
+    fn test_at_type_precedence() {
+        let description = PropertyDescription::<Level>::default();
+        assert!(matches!(description.at_type, Some(AtType::LevelProperty)));
+
+        let description =
+            PropertyDescription::<Level>::default().at_type(AtType::BrightnessProperty);
+        assert!(matches!(
+            description.at_type,
+            Some(AtType::BrightnessProperty)
+        ));
+
+        let full = PropertyDescription::<Level>::default()
+            .clear_at_type()
+            .into_full_description("level".to_owned())
+            .unwrap();
+        assert_eq!(full.at_type, None);
+    }
+
+    #[test]
+    fn test_enum_labeled() {
+        let description = PropertyDescription::<String>::default().enum_labeled(vec![
+            ("eco".to_owned(), "Eco mode".to_owned()),
+            ("comfort".to_owned(), "Comfort mode".to_owned()),
+        ]);
+        assert_eq!(
+            description.labeled_enum().unwrap(),
+            Some(vec![
+                json!({"const": "eco", "title": "Eco mode"}),
+                json!({"const": "comfort", "title": "Comfort mode"}),
+            ])
+        );
+
+        let full = description
+            .value("eco".to_owned())
+            .into_full_description("mode".to_owned())
+            .unwrap();
+        assert_eq!(full.enum_, Some(vec![json!("eco"), json!("comfort")]));
+    }
+
+    #[test]
+    fn test_enum_unlabeled() {
+        let description = PropertyDescription::<String>::default()
+            .enum_labeled(vec![("eco".to_owned(), "Eco mode".to_owned())])
+            .enum_(vec!["eco".to_owned()]);
+        assert_eq!(description.enum_labels, None);
+        assert_eq!(
+            description.labeled_enum().unwrap(),
+            Some(vec![json!({"const": "eco"})])
+        );
+
+        assert_eq!(
+            PropertyDescription::<String>::default()
+                .labeled_enum()
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_value_not_in_enum() {
+        let result = PropertyDescription::<String>::default()
+            .enum_(vec!["eco".to_owned(), "comfort".to_owned()])
+            .value("boost".to_owned())
+            .into_full_description("mode".to_owned());
+        assert!(matches!(
+            result,
+            Err(WebthingsError::ValueNotInEnum(name, value)) if name == "mode" && value == json!("boost")
+        ));
+    }
+
+    #[rstest]
+    #[case(NonePolicy::Omit, None, None)]
+    #[case(NonePolicy::Null, None, Some(json!(null)))]
+    #[case(NonePolicy::Omit, Some(1), Some(json!(1)))]
+    #[case(NonePolicy::Null, Some(1), Some(json!(1)))]
+    fn test_none_policy(
+        #[case] none_policy: NonePolicy,
+        #[case] value: Option<i32>,
+        #[case] expected: Option<serde_json::Value>,
+    ) {
+        let full = PropertyDescription::<Option<i32>>::default()
+            .enum_(vec![Some(1), Some(2)])
+            .value(value)
+            .none_policy(none_policy)
+            .into_full_description("foo".to_owned())
+            .unwrap();
+        assert_eq!(full.value, expected);
+    }
+
+    #[rstest]
+    #[case(UnitValidation::Off, "percent", true)]
+    #[case(UnitValidation::Warn, "percent", true)]
+    #[case(UnitValidation::Error, "percent", false)]
+    #[case(UnitValidation::Error, "Degree Celsius", true)]
+    fn test_unit_validation(
+        #[case] unit_validation: UnitValidation,
+        #[case] unit: &str,
+        #[case] ok: bool,
+    ) {
+        let result = PropertyDescription::<f64>::default()
+            .at_type(AtType::TemperatureProperty)
+            .unit(unit)
+            .unit_validation(unit_validation)
+            .into_full_description("temperature".to_owned());
+        if ok {
+            assert!(result.is_ok());
+        } else {
+            assert!(matches!(
+                result,
+                Err(WebthingsError::UnitMismatch(name, at_type, _)) if name == "temperature" && at_type == "TemperatureProperty"
+            ));
+        }
+    }
+
+    #[rstest]
+    #[case(None, json!(0.1 + 0.2))]
+    #[case(Some(2), json!(0.3))]
+    #[case(Some(0), json!(0.0))]
+    fn test_precision(#[case] precision: Option<u32>, #[case] expected: serde_json::Value) {
+        let mut description = PropertyDescription::<f64>::default().value(0.1 + 0.2);
+        if let Some(precision) = precision {
+            description = description.precision(precision);
+        }
+        let full = description.into_full_description("foo".to_owned()).unwrap();
+        assert_eq!(full.value, Some(expected));
+    }
+
+    #[test]
+    fn test_precision_integer() {
+        let full = PropertyDescription::<i32>::default()
+            .precision(0)
+            .value(5)
+            .into_full_description("foo".to_owned())
+            .unwrap();
+        assert_eq!(full.value, Some(json!(5)));
+    }
+
+    #[test]
+    fn test_write_only() {
+        let full = PropertyDescription::<bool>::default()
+            .write_only(true)
+            .observable(false)
+            .value(true)
+            .into_full_description("foo".to_owned())
+            .unwrap();
+        assert_eq!(full.value, None);
+    }
+
+    #[rstest]
+    #[case(PropertyDescription::default().read_only(true), "read-only")]
+    #[case(PropertyDescription::default().observable(true), "observable")]
+    fn test_write_only_conflict(
+        #[case] description: PropertyDescription<bool>,
+        #[case] conflict: &'static str,
+    ) {
+        assert!(matches!(
+            description.write_only(true).into_full_description("foo".to_owned()),
+            Err(WebthingsError::InvalidPropertyAccess(_, c)) if c == conflict
+        ));
+    }
+
+    #[test]
+    fn test_none_value_with_enum() {
+        let full = PropertyDescription::<Option<i32>>::default()
+            .enum_(vec![Some(1), Some(2)])
+            .into_full_description("foo".to_owned())
+            .unwrap();
+        assert_eq!(full.enum_, Some(vec![json!(1), json!(2)]));
+        assert_eq!(full.value, None);
+    }
+
+    #[test]
+    fn test_from_full() {
+        let full = PropertyDescription::<i32>::default()
+            .at_type(AtType::LevelProperty)
+            .title("Foo")
+            .enum_(vec![1, 2])
+            .value(2)
+            .into_full_description("foo".to_owned())
+            .unwrap();
+        let description = PropertyDescription::<i32>::from_full(full.clone()).unwrap();
+        assert_eq!(description.value, 2);
+        assert_eq!(
+            description.into_full_description("foo".to_owned()).unwrap(),
+            full
+        );
+    }
+
+    #[test]
+    fn test_from_full_value_roundtrip() {
+        let full = PropertyDescription::<serde_json::Value>::default()
+            .type_(Type::Array)
+            .value(json!([{"foo": 42}]))
+            .into_full_description("foo".to_owned())
+            .unwrap();
+        let description =
+            PropertyDescription::<serde_json::Value>::from_full(full.clone()).unwrap();
+        assert_eq!(
+            description.into_full_description("foo".to_owned()).unwrap(),
+            full
+        );
+    }
+
+    #[test]
+    fn test_from_full_unknown_at_type() {
+        let mut full = PropertyDescription::<i32>::default()
+            .into_full_description("foo".to_owned())
+            .unwrap();
+        full.at_type = Some("FooProperty".to_owned());
+        let description = PropertyDescription::<i32>::from_full(full.clone()).unwrap();
+        assert!(
+            matches!(description.at_type, Some(AtType::Other(ref at_type)) if at_type == "FooProperty")
+        );
+        assert_eq!(
+            description.into_full_description("foo".to_owned()).unwrap(),
+            full
+        );
+    }
 }
@@ -4,12 +4,19 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
  */
 
-use crate::{client::Client, error::WebthingsError, property::Value, Device, PropertyDescription};
+use crate::{
+    client::{self, Client},
+    error::WebthingsError,
+    property::Value,
+    Device, PropertyDescription,
+};
 use as_any::{AsAny, Downcast};
 use async_trait::async_trait;
 use std::{
+    any::Any,
     marker::PhantomData,
     sync::{Arc, Weak},
+    time::SystemTime,
 };
 use tokio::sync::Mutex;
 use webthings_gateway_ipc_types::{DevicePropertyChangedNotificationMessageData, Message};
@@ -17,6 +24,8 @@ use webthings_gateway_ipc_types::{DevicePropertyChangedNotificationMessageData,
 /// A struct which represents an instance of a WoT property.
 ///
 /// Use it to notify the gateway.
+///
+/// Prefer the id getters over the public id fields, which are going to become private.
 #[derive(Clone)]
 pub struct PropertyHandle<T: Value> {
     client: Arc<Mutex<Client>>,
@@ -26,10 +35,33 @@ pub struct PropertyHandle<T: Value> {
     pub adapter_id: String,
     pub device_id: String,
     pub name: String,
+    /// Use [set_value][PropertyHandle::set_value] to change the value.
+    ///
+    /// The serialized value is cached, so direct writes to `description.value` are only picked up
+    /// by [notify_value][PropertyHandle::notify_value].
     pub description: PropertyDescription<T>,
+    serialized_value: SerializedValue,
+    created: SystemTime,
     _value: PhantomData<T>,
 }
 
+/// Cache of the serialized [value][Value] of a [PropertyHandle].
+#[derive(Default)]
+struct SerializedValue(std::sync::Mutex<Option<Option<serde_json::Value>>>);
+
+impl Clone for SerializedValue {
+    fn clone(&self) -> Self {
+        Self(std::sync::Mutex::new(self.0.lock().unwrap().clone()))
+    }
+}
+
+/// The value of a [property][crate::Property] before an update, used to restore it if the update could not be sent.
+#[doc(hidden)]
+pub struct ValueSnapshot {
+    previous: Box<dyn Any + Send + Sync>,
+    written: Option<serde_json::Value>,
+}
+
 impl<T: Value> PropertyHandle<T> {
     pub(crate) fn new(
         client: Arc<Mutex<Client>>,
@@ -48,26 +80,164 @@ impl<T: Value> PropertyHandle<T> {
             device_id,
             name,
             description,
+            serialized_value: SerializedValue::default(),
+            created: SystemTime::now(),
             _value: PhantomData,
         }
     }
 
+    /// ID of the plugin this property belongs to.
+    pub fn plugin_id(&self) -> &str {
+        &self.plugin_id
+    }
+
+    /// ID of the adapter this property belongs to.
+    pub fn adapter_id(&self) -> &str {
+        &self.adapter_id
+    }
+
+    /// ID of the device this property belongs to.
+    pub fn device_id(&self) -> &str {
+        &self.device_id
+    }
+
+    /// Name of this property.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// When this property was registered.
+    pub fn created(&self) -> SystemTime {
+        self.created
+    }
+
     /// Sets the [value][Value] and notifies the gateway.
+    ///
+    /// The notification is skipped if the value did not change and the property is
+    /// [notify_on_change_only][PropertyDescription::notify_on_change_only].
     pub async fn set_value(&mut self, value: T) -> Result<(), WebthingsError> {
+        if let Some(message) = self.update_value(value)? {
+            client::send_message(&self.client, &message).await?;
+        }
+        Ok(())
+    }
+
+    /// Sets the [value][Value] and enqueues the notification without waiting.
+    ///
+    /// Meant for hot paths like high-frequency sensors. Returns
+    /// [WouldBlock][WebthingsError::WouldBlock] without changing the value if the client is
+    /// currently busy. The value is also left unchanged if the notification cannot be enqueued,
+    /// e.g. because the queue is full. See [Client::try_send_message][crate::client::WebsocketClient::try_send_message].
+    pub fn try_set_value(&mut self, value: T) -> Result<(), WebthingsError> {
+        let client = self.client.clone();
+        let mut client = client.try_lock().map_err(|_| WebthingsError::WouldBlock)?;
+        let (message, snapshot) = self.update_value_undoable(value)?;
+        if let Some(message) = message {
+            if let Err(err) = client.try_send_message(&message) {
+                self.restore(snapshot);
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets the [value][Value] and returns the notification to send, if any.
+    pub(crate) fn update_value(&mut self, value: T) -> Result<Option<Message>, WebthingsError> {
+        let serialized = self.description.serialize_value(value.clone())?;
+        let changed = !self.description.notify_on_change_only
+            || self.description.round_value(self.serialized_value()?)
+                != self.description.round_value(serialized.clone());
+
         self.description.value = value;
+        *self.serialized_value.0.lock().unwrap() = Some(serialized);
+        if changed && !self.description.is_write_only() {
+            self.value_message().map(Some)
+        } else {
+            Ok(None)
+        }
+    }
 
+    /// Like [update_value][PropertyHandle::update_value], but also returns a snapshot of the
+    /// previous [value][Value], which [restore][PropertyHandle::restore] sets again.
+    pub(crate) fn update_value_undoable(
+        &mut self,
+        value: T,
+    ) -> Result<(Option<Message>, ValueSnapshot), WebthingsError> {
+        let previous = (
+            self.description.value.clone(),
+            self.serialized_value.0.lock().unwrap().clone(),
+        );
+        let message = self.update_value(value)?;
+        let snapshot = ValueSnapshot {
+            previous: Box::new(previous),
+            written: self.serialized_value()?,
+        };
+        Ok((message, snapshot))
+    }
+
+    /// Set the previous [value][Value] of a [snapshot][PropertyHandle::update_value_undoable] again
+    /// without notifying the gateway.
+    ///
+    /// Nothing happens if the value was changed again since the snapshot was taken.
+    pub(crate) fn restore(&mut self, snapshot: ValueSnapshot) {
+        if !matches!(self.serialized_value(), Ok(current) if current == snapshot.written) {
+            return;
+        }
+        if let Ok(previous) = snapshot
+            .previous
+            .downcast::<(T, Option<Option<serde_json::Value>>)>()
+        {
+            let (value, serialized) = *previous;
+            self.description.value = value;
+            *self.serialized_value.0.lock().unwrap() = serialized;
+        }
+    }
+
+    /// Notifies the gateway about the current [value][Value] without changing it.
+    ///
+    /// Fails with [WebthingsError::WriteOnlyProperty] if the property is [write-only][PropertyDescription::write_only].
+    pub async fn notify_value(&self) -> Result<(), WebthingsError> {
+        if self.description.is_write_only() {
+            return Err(WebthingsError::WriteOnlyProperty(self.name.clone()));
+        }
+        self.serialized_value.0.lock().unwrap().take();
+        let message = self.value_message()?;
+        client::send_message(&self.client, &message).await
+    }
+
+    pub(crate) fn value_message(&self) -> Result<Message, WebthingsError> {
+        self.value_message_with(&self.description)
+    }
+
+    /// Like [value_message][PropertyHandle::value_message], but with another description.
+    pub(crate) fn value_message_with(
+        &self,
+        description: &PropertyDescription<T>,
+    ) -> Result<Message, WebthingsError> {
         let message: Message = DevicePropertyChangedNotificationMessageData {
             plugin_id: self.plugin_id.clone(),
             adapter_id: self.adapter_id.clone(),
             device_id: self.device_id.clone(),
-            property: self
-                .description
+            property: description
                 .clone()
-                .into_full_description(self.name.clone())?,
+                .into_full_description_with_value(self.name.clone(), self.serialized_value()?)?,
         }
         .into();
 
-        self.client.lock().await.send_message(&message).await
+        Ok(message)
+    }
+
+    /// The serialized current value, which is only computed again after the value changed.
+    fn serialized_value(&self) -> Result<Option<serde_json::Value>, WebthingsError> {
+        let mut serialized_value = self.serialized_value.0.lock().unwrap();
+        if let Some(serialized) = &*serialized_value {
+            return Ok(serialized.clone());
+        }
+        let serialized = self
+            .description
+            .serialize_value(self.description.value.clone())?;
+        *serialized_value = Some(serialized.clone());
+        Ok(serialized)
     }
 }
 
@@ -81,7 +251,29 @@ pub trait PropertyHandleBase: Send + Sync + AsAny + 'static {
     /// Sets the [value][Value] and notifies the gateway.
     ///
     /// Make sure that the type of the provided value is compatible.
+    /// Fails with [WebthingsError::InvalidPropertyValue] otherwise.
     async fn set_value(&mut self, value: Option<serde_json::Value>) -> Result<(), WebthingsError>;
+
+    /// Notifies the gateway about the current [value][Value] without changing it.
+    async fn notify_value(&self) -> Result<(), WebthingsError>;
+
+    #[doc(hidden)]
+    fn value_message(&self) -> Result<Message, WebthingsError>;
+
+    /// Whether the value can be read, i.e. the property is not [write-only][PropertyDescription::write_only].
+    fn readable(&self) -> bool;
+
+    /// Whether the gateway may write the value, i.e. the property is not [read-only][PropertyDescription::read_only].
+    fn writable(&self) -> bool;
+
+    #[doc(hidden)]
+    fn update_value(
+        &mut self,
+        value: Option<serde_json::Value>,
+    ) -> Result<(Option<Message>, ValueSnapshot), WebthingsError>;
+
+    #[doc(hidden)]
+    fn restore(&mut self, snapshot: ValueSnapshot);
 }
 
 impl Downcast for dyn PropertyHandleBase {}
@@ -89,17 +281,64 @@ impl Downcast for dyn PropertyHandleBase {}
 #[async_trait]
 impl<T: Value> PropertyHandleBase for PropertyHandle<T> {
     async fn set_value(&mut self, value: Option<serde_json::Value>) -> Result<(), WebthingsError> {
-        let value = <T as Value>::deserialize(value)?;
+        let value = self.deserialize_value(value)?;
         PropertyHandle::set_value(self, value).await
     }
+
+    async fn notify_value(&self) -> Result<(), WebthingsError> {
+        PropertyHandle::notify_value(self).await
+    }
+
+    fn value_message(&self) -> Result<Message, WebthingsError> {
+        PropertyHandle::value_message(self)
+    }
+
+    fn readable(&self) -> bool {
+        !self.description.is_write_only()
+    }
+
+    fn writable(&self) -> bool {
+        !self.description.is_read_only()
+    }
+
+    fn update_value(
+        &mut self,
+        value: Option<serde_json::Value>,
+    ) -> Result<(Option<Message>, ValueSnapshot), WebthingsError> {
+        let value = self.deserialize_value(value)?;
+        PropertyHandle::update_value_undoable(self, value)
+    }
+
+    fn restore(&mut self, snapshot: ValueSnapshot) {
+        PropertyHandle::restore(self, snapshot)
+    }
+}
+
+impl<T: Value> PropertyHandle<T> {
+    fn deserialize_value(&self, value: Option<serde_json::Value>) -> Result<T, WebthingsError> {
+        self.description.deserialize_value(value).map_err(|err| {
+            WebthingsError::InvalidPropertyValue(self.name.clone(), T::type_(), err.to_string())
+        })
+    }
 }
 
 #[cfg(test)]
 pub(crate) mod tests {
-    use crate::{client::Client, property::Value, PropertyDescription, PropertyHandle};
+    use crate::{
+        client::Client,
+        error::WebthingsError,
+        property::{NonePolicy, PropertyHandleBase, Value},
+        type_::Type,
+        PropertyDescription, PropertyHandle,
+    };
 
+    use mockall::Sequence;
     use rstest::rstest;
-    use std::sync::{Arc, Weak};
+    use serde_json::json;
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Weak,
+    };
     use tokio::sync::Mutex;
     use webthings_gateway_ipc_types::Message;
 
@@ -155,4 +394,384 @@ pub(crate) mod tests {
 
         assert!(property.description.value == value);
     }
+
+    #[tokio::test]
+    async fn test_try_set_value() {
+        let client = Arc::new(Mutex::new(Client::new()));
+
+        let mut property = PropertyHandle::new(
+            client.clone(),
+            Weak::new(),
+            PLUGIN_ID.to_owned(),
+            ADAPTER_ID.to_owned(),
+            DEVICE_ID.to_owned(),
+            PROPERTY_NAME.to_owned(),
+            PropertyDescription::<i32>::default(),
+        );
+
+        client
+            .lock()
+            .await
+            .expect_try_send_message()
+            .withf(|msg| match msg {
+                Message::DevicePropertyChangedNotification(msg) => {
+                    msg.data.property.value == Some(serde_json::json!(42))
+                }
+                _ => false,
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+
+        property.try_set_value(42).unwrap();
+        assert_eq!(property.description.value, 42);
+    }
+
+    #[tokio::test]
+    async fn test_try_set_value_would_block() {
+        let client = Arc::new(Mutex::new(Client::new()));
+
+        let mut property = PropertyHandle::new(
+            client.clone(),
+            Weak::new(),
+            PLUGIN_ID.to_owned(),
+            ADAPTER_ID.to_owned(),
+            DEVICE_ID.to_owned(),
+            PROPERTY_NAME.to_owned(),
+            PropertyDescription::<i32>::default(),
+        );
+
+        let _guard = client.lock().await;
+
+        assert!(matches!(
+            property.try_set_value(42),
+            Err(WebthingsError::WouldBlock)
+        ));
+        assert_eq!(property.description.value, 0);
+    }
+
+    #[rstest]
+    #[case(WebthingsError::QueueFull)]
+    #[case(WebthingsError::WouldBlock)]
+    #[tokio::test]
+    async fn test_try_set_value_queue_full(#[case] error: WebthingsError) {
+        let client = Arc::new(Mutex::new(Client::new()));
+
+        let mut property = PropertyHandle::new(
+            client.clone(),
+            Weak::new(),
+            PLUGIN_ID.to_owned(),
+            ADAPTER_ID.to_owned(),
+            DEVICE_ID.to_owned(),
+            PROPERTY_NAME.to_owned(),
+            PropertyDescription::<i32>::default().notify_on_change_only(true),
+        );
+
+        let mut sequence = Sequence::new();
+        let mut error = Some(error);
+        client
+            .lock()
+            .await
+            .expect_try_send_message()
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(move |_| Err(error.take().unwrap()));
+        client
+            .lock()
+            .await
+            .expect_try_send_message()
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_| Ok(()));
+
+        assert!(property.try_set_value(42).is_err());
+        assert_eq!(property.description.value, 0);
+
+        // The unchanged value is still sent, as the cache was restored as well
+        property.try_set_value(42).unwrap();
+        assert_eq!(property.description.value, 42);
+    }
+
+    #[rstest]
+    #[case(false, 42, 1)]
+    #[case(true, 42, 0)]
+    #[case(true, 43, 1)]
+    #[tokio::test]
+    async fn test_set_value_notify_on_change_only(
+        #[case] notify_on_change_only: bool,
+        #[case] value: i32,
+        #[case] notifications: usize,
+    ) {
+        let client = Arc::new(Mutex::new(Client::new()));
+
+        let mut property = PropertyHandle::new(
+            client.clone(),
+            Weak::new(),
+            PLUGIN_ID.to_owned(),
+            ADAPTER_ID.to_owned(),
+            DEVICE_ID.to_owned(),
+            PROPERTY_NAME.to_owned(),
+            PropertyDescription::<i32>::default()
+                .value(42)
+                .notify_on_change_only(notify_on_change_only),
+        );
+
+        client
+            .lock()
+            .await
+            .expect_send_message()
+            .times(notifications)
+            .returning(|_| Ok(()));
+
+        property.set_value(value).await.unwrap();
+
+        assert_eq!(property.description.value, value);
+    }
+
+    static SERIALIZATIONS: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(Clone, Default, PartialEq, Debug)]
+    struct Counted(i32);
+
+    impl Value for Counted {
+        fn serialize(value: Self) -> Result<Option<serde_json::Value>, WebthingsError> {
+            SERIALIZATIONS.fetch_add(1, Ordering::SeqCst);
+            Ok(Some(serde_json::json!(value.0)))
+        }
+
+        fn deserialize(value: Option<serde_json::Value>) -> Result<Self, WebthingsError> {
+            Ok(Counted(
+                serde_json::from_value(value.unwrap_or_default()).unwrap_or_default(),
+            ))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_serialized_value_cache() {
+        let client = Arc::new(Mutex::new(Client::new()));
+
+        let mut property = PropertyHandle::new(
+            client.clone(),
+            Weak::new(),
+            PLUGIN_ID.to_owned(),
+            ADAPTER_ID.to_owned(),
+            DEVICE_ID.to_owned(),
+            PROPERTY_NAME.to_owned(),
+            PropertyDescription::<Counted>::default().notify_on_change_only(true),
+        );
+
+        client
+            .lock()
+            .await
+            .expect_send_message()
+            .times(3)
+            .returning(|_| Ok(()));
+
+        // The new value and the lazily serialized initial value
+        property.set_value(Counted(1)).await.unwrap();
+        assert_eq!(SERIALIZATIONS.load(Ordering::SeqCst), 2);
+
+        // Unchanged values are only serialized once for the comparison
+        property.set_value(Counted(1)).await.unwrap();
+        assert_eq!(SERIALIZATIONS.load(Ordering::SeqCst), 3);
+
+        // Resyncing reuses the cached value
+        property.value_message().unwrap();
+        property.value_message().unwrap();
+        assert_eq!(SERIALIZATIONS.load(Ordering::SeqCst), 3);
+
+        // Direct writes are picked up by notify_value
+        property.description.value = Counted(2);
+        property.notify_value().await.unwrap();
+        assert_eq!(SERIALIZATIONS.load(Ordering::SeqCst), 4);
+
+        property.set_value(Counted(3)).await.unwrap();
+        assert_eq!(SERIALIZATIONS.load(Ordering::SeqCst), 5);
+        assert_eq!(property.description.value, Counted(3));
+    }
+
+    #[tokio::test]
+    async fn test_set_value_none_policy() {
+        let client = Arc::new(Mutex::new(Client::new()));
+
+        let mut property = PropertyHandle::new(
+            client.clone(),
+            Weak::new(),
+            PLUGIN_ID.to_owned(),
+            ADAPTER_ID.to_owned(),
+            DEVICE_ID.to_owned(),
+            PROPERTY_NAME.to_owned(),
+            PropertyDescription::<Option<i32>>::default()
+                .value(Some(42))
+                .none_policy(NonePolicy::Null),
+        );
+
+        client
+            .lock()
+            .await
+            .expect_send_message()
+            .withf(|msg| match msg {
+                Message::DevicePropertyChangedNotification(msg) => {
+                    msg.data.property.value == Some(serde_json::Value::Null)
+                }
+                _ => false,
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+
+        property.set_value(None).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_set_value_precision() {
+        let client = Arc::new(Mutex::new(Client::new()));
+
+        let mut property = PropertyHandle::new(
+            client.clone(),
+            Weak::new(),
+            PLUGIN_ID.to_owned(),
+            ADAPTER_ID.to_owned(),
+            DEVICE_ID.to_owned(),
+            PROPERTY_NAME.to_owned(),
+            PropertyDescription::<f64>::default()
+                .precision(1)
+                .notify_on_change_only(true),
+        );
+
+        client
+            .lock()
+            .await
+            .expect_send_message()
+            .withf(|msg| match msg {
+                Message::DevicePropertyChangedNotification(msg) => {
+                    msg.data.property.value == Some(json!(0.3))
+                }
+                _ => false,
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+
+        property.set_value(0.1 + 0.2).await.unwrap();
+        assert_eq!(property.description.value, 0.1 + 0.2);
+        property.set_value(0.3).await.unwrap();
+        assert_eq!(property.description.value, 0.3);
+    }
+
+    #[tokio::test]
+    async fn test_set_value_transform() {
+        let client = Arc::new(Mutex::new(Client::new()));
+
+        // Stored in Kelvin, exposed in Celsius
+        let mut property = PropertyHandle::new(
+            client.clone(),
+            Weak::new(),
+            PLUGIN_ID.to_owned(),
+            ADAPTER_ID.to_owned(),
+            DEVICE_ID.to_owned(),
+            PROPERTY_NAME.to_owned(),
+            PropertyDescription::<i32>::default()
+                .value(273)
+                .enum_(vec![273, 293, 298])
+                .transform(
+                    |kelvin| json!(kelvin.as_i64().unwrap() - 273),
+                    |celsius| json!(celsius.as_i64().unwrap() + 273),
+                ),
+        );
+
+        let full = property
+            .description
+            .clone()
+            .into_full_description(PROPERTY_NAME.to_owned())
+            .unwrap();
+        assert_eq!(full.value, Some(json!(0)));
+        assert_eq!(full.enum_, Some(vec![json!(0), json!(20), json!(25)]));
+
+        client
+            .lock()
+            .await
+            .expect_send_message()
+            .withf(|msg| match msg {
+                Message::DevicePropertyChangedNotification(msg) => {
+                    msg.data.property.value == Some(json!(20))
+                        && msg.data.property.enum_ == Some(vec![json!(0), json!(20), json!(25)])
+                }
+                _ => false,
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+        property.set_value(293).await.unwrap();
+        client.lock().await.checkpoint();
+
+        client
+            .lock()
+            .await
+            .expect_send_message()
+            .withf(|msg| match msg {
+                Message::DevicePropertyChangedNotification(msg) => {
+                    msg.data.property.value == Some(json!(25))
+                }
+                _ => false,
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+        PropertyHandleBase::set_value(&mut property, Some(json!(25)))
+            .await
+            .unwrap();
+        assert_eq!(property.description.value, 298);
+    }
+
+    #[tokio::test]
+    async fn test_set_value_write_only() {
+        let client = Arc::new(Mutex::new(Client::new()));
+
+        let mut property = PropertyHandle::new(
+            client.clone(),
+            Weak::new(),
+            PLUGIN_ID.to_owned(),
+            ADAPTER_ID.to_owned(),
+            DEVICE_ID.to_owned(),
+            PROPERTY_NAME.to_owned(),
+            PropertyDescription::<bool>::default().write_only(true),
+        );
+
+        client.lock().await.expect_send_message().times(0);
+
+        property.set_value(true).await.unwrap();
+        assert!(property.description.value);
+        assert!(!PropertyHandleBase::readable(&property));
+        assert!(PropertyHandleBase::writable(&property));
+        assert!(matches!(
+            property.notify_value().await,
+            Err(WebthingsError::WriteOnlyProperty(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_set_value_invalid_type() {
+        let client = Arc::new(Mutex::new(Client::new()));
+
+        let mut property = PropertyHandle::new(
+            client.clone(),
+            Weak::new(),
+            PLUGIN_ID.to_owned(),
+            ADAPTER_ID.to_owned(),
+            DEVICE_ID.to_owned(),
+            PROPERTY_NAME.to_owned(),
+            PropertyDescription::<String>::default(),
+        );
+
+        client.lock().await.expect_send_message().times(0);
+
+        let err = PropertyHandleBase::set_value(&mut property, Some(serde_json::json!(42)))
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            err,
+            WebthingsError::InvalidPropertyValue(ref name, Type::String, _) if name == PROPERTY_NAME
+        ));
+        let message = err.to_string();
+        assert!(message.contains(PROPERTY_NAME));
+        assert!(message.contains("expected string"));
+        assert!(property.description.value.is_empty());
+    }
 }
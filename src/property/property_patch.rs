@@ -0,0 +1,73 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+
+/// Apply a [JSON merge patch](https://datatracker.ietf.org/doc/html/rfc7386) to a target value.
+///
+/// Members of `patch` which are `null` get removed from `target`, all others get merged recursively.
+/// A `patch` which is not an object replaces `target` entirely.
+///
+/// # Examples
+/// ```
+/// # use gateway_addon_rust::property::merge_patch;
+/// # use serde_json::json;
+/// let mut value = json!({"monday": {"from": 6, "to": 22}, "sunday": {"from": 8, "to": 23}});
+/// merge_patch(&mut value, &json!({"monday": {"from": 7}, "sunday": null}));
+/// assert_eq!(value, json!({"monday": {"from": 7, "to": 22}}));
+/// ```
+pub fn merge_patch(target: &mut serde_json::Value, patch: &serde_json::Value) {
+    if let serde_json::Value::Object(patch) = patch {
+        if !target.is_object() {
+            *target = serde_json::Value::Object(serde_json::Map::new());
+        }
+        if let serde_json::Value::Object(target) = target {
+            for (key, value) in patch {
+                if value.is_null() {
+                    target.remove(key);
+                } else {
+                    merge_patch(
+                        target.entry(key.clone()).or_insert(serde_json::Value::Null),
+                        value,
+                    );
+                }
+            }
+        }
+    } else {
+        *target = patch.clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::property::merge_patch;
+    use rstest::rstest;
+    use serde_json::json;
+
+    // Test cases from RFC 7386, appendix A
+    #[rstest]
+    #[case(json!({"a":"b"}), json!({"a":"c"}), json!({"a":"c"}))]
+    #[case(json!({"a":"b"}), json!({"b":"c"}), json!({"a":"b","b":"c"}))]
+    #[case(json!({"a":"b"}), json!({"a":null}), json!({}))]
+    #[case(json!({"a":"b","b":"c"}), json!({"a":null}), json!({"b":"c"}))]
+    #[case(json!({"a":["b"]}), json!({"a":"c"}), json!({"a":"c"}))]
+    #[case(json!({"a":"c"}), json!({"a":["b"]}), json!({"a":["b"]}))]
+    #[case(json!({"a":{"b":"c"}}), json!({"a":{"b":"d","c":null}}), json!({"a":{"b":"d"}}))]
+    #[case(json!({"a":[{"b":"c"}]}), json!({"a":[1]}), json!({"a":[1]}))]
+    #[case(json!(["a","b"]), json!(["c","d"]), json!(["c","d"]))]
+    #[case(json!({"a":"b"}), json!(["c"]), json!(["c"]))]
+    #[case(json!({"a":"foo"}), json!(null), json!(null))]
+    #[case(json!({"a":"foo"}), json!("bar"), json!("bar"))]
+    #[case(json!({"e":null}), json!({"a":1}), json!({"e":null,"a":1}))]
+    #[case(json!([1,2]), json!({"a":"b","c":null}), json!({"a":"b"}))]
+    #[case(json!({}), json!({"a":{"bb":{"ccc":null}}}), json!({"a":{"bb":{}}}))]
+    fn test_merge_patch(
+        #[case] mut target: serde_json::Value,
+        #[case] patch: serde_json::Value,
+        #[case] expected: serde_json::Value,
+    ) {
+        merge_patch(&mut target, &patch);
+        assert_eq!(target, expected);
+    }
+}
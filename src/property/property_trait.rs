@@ -5,11 +5,12 @@
  */
 
 use crate::{
-    property::{PropertyHandleBase, Value},
+    property::{merge_patch, PropertyHandleBase, Value},
     PropertyHandle,
 };
 use as_any::{AsAny, Downcast};
 use async_trait::async_trait;
+use std::time::Duration;
 
 /// A trait used to specify the behaviour of a WoT property.
 ///
@@ -57,8 +58,91 @@ pub trait Property: BuiltProperty + Send + Sync + 'static {
         Ok(())
     }
 
+    /// Called when the [value][Value] has been updated through the gateway, together with the previous value.
+    ///
+    /// `changed` tells whether the serialized new value differs from the previous one, e.g. to skip
+    /// redundant hardware writes when the gateway re-sends the current value.
+    ///
+    /// The default implementation calls [on_update][Property::on_update].
+    async fn on_update_with_previous(
+        &mut self,
+        _previous: <Self as BuiltProperty>::Value,
+        value: <Self as BuiltProperty>::Value,
+        _changed: bool,
+    ) -> Result<(), String> {
+        self.on_update(value).await
+    }
+
+    /// Whether values received through the gateway are treated as partial updates.
+    ///
+    /// If this returns `true`, object values received for this property are passed to
+    /// [on_patch][Property::on_patch] instead of [on_update][Property::on_update].
+    /// Defaults to `false`.
+    fn patchable(&self) -> bool {
+        false
+    }
+
+    /// Called when a partial update has been received through the gateway for a
+    /// [patchable][Property::patchable] property.
+    ///
+    /// The default implementation applies the [JSON merge patch][merge_patch] to the current value
+    /// and passes the result to [on_update_with_previous][Property::on_update_with_previous].
+    ///
+    /// Should return the new value when the patch is accepted and an `Err` otherwise.
+    async fn on_patch(
+        &mut self,
+        patch: serde_json::Value,
+    ) -> Result<<Self as BuiltProperty>::Value, String> {
+        let description = &self.property_handle().description;
+        let previous = description.value.clone();
+        let serialized_previous = description
+            .serialize_value(previous.clone())
+            .map_err(|err| format!("Could not serialize value: {:?}", err))?;
+        let mut value = serialized_previous
+            .clone()
+            .unwrap_or(serde_json::Value::Null);
+        merge_patch(&mut value, &patch);
+        let changed = serialized_previous.as_ref() != Some(&value);
+        let value = description
+            .deserialize_value(Some(value))
+            .map_err(|err| format!("Could not deserialize value: {:?}", err))?;
+        self.on_update_with_previous(previous, value.clone(), changed)
+            .await?;
+        Ok(value)
+    }
+
     /// Called once after initialization.
     fn post_init(&mut self) {}
+
+    /// Interval at which [poll][Property::poll] gets called.
+    ///
+    /// Polling starts once the device was added to the gateway, the first poll happens one interval later.
+    /// Polling is disabled if this returns `None`, which is the default.
+    fn poll_interval(&self) -> Option<Duration> {
+        None
+    }
+
+    /// What to do with polls which were missed because [poll][Property::poll] took longer than the
+    /// [poll interval][Property::poll_interval].
+    fn missed_poll_behavior(&self) -> MissedPollBehavior {
+        MissedPollBehavior::Skip
+    }
+
+    /// Called periodically if a [poll interval][Property::poll_interval] is set.
+    ///
+    /// The returned value becomes the new value of this property and is sent to the gateway.
+    async fn poll(&mut self) -> Result<<Self as BuiltProperty>::Value, String> {
+        Err("Polling is not implemented".to_owned())
+    }
+}
+
+/// Possible ways to handle polls of a [property][Property] which were missed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MissedPollBehavior {
+    /// Drop missed polls and continue at the next regular interval.
+    Skip,
+    /// Perform missed polls immediately one after another.
+    Queue,
 }
 
 /// An object safe variant of [Property] + [BuiltProperty].
@@ -79,8 +163,32 @@ pub trait PropertyBase: Send + Sync + AsAny + 'static {
     #[doc(hidden)]
     async fn on_update(&mut self, value: serde_json::Value) -> Result<(), String>;
 
+    #[doc(hidden)]
+    fn patchable(&self) -> bool {
+        false
+    }
+
+    #[doc(hidden)]
+    async fn on_patch(
+        &mut self,
+        patch: serde_json::Value,
+    ) -> Result<Option<serde_json::Value>, String>;
+
     #[doc(hidden)]
     fn post_init(&mut self) {}
+
+    #[doc(hidden)]
+    fn poll_interval(&self) -> Option<Duration> {
+        None
+    }
+
+    #[doc(hidden)]
+    fn missed_poll_behavior(&self) -> MissedPollBehavior {
+        MissedPollBehavior::Skip
+    }
+
+    #[doc(hidden)]
+    async fn poll(&mut self) -> Result<(), String>;
 }
 
 impl Downcast for dyn PropertyBase {}
@@ -96,14 +204,54 @@ impl<T: Property> PropertyBase for T {
     }
 
     async fn on_update(&mut self, value: serde_json::Value) -> Result<(), String> {
-        let value = <T as BuiltProperty>::Value::deserialize(Some(value))
+        let description = &<T as BuiltProperty>::property_handle(self).description;
+        let value = description
+            .deserialize_value(Some(value))
             .map_err(|err| format!("Could not deserialize value: {:?}", err))?;
-        <T as Property>::on_update(self, value).await
+        let previous = description.value.clone();
+        let serialize = |value| {
+            description
+                .serialize_value(value)
+                .map_err(|err| format!("Could not serialize value: {:?}", err))
+        };
+        let changed = serialize(previous.clone())? != serialize(value.clone())?;
+        <T as Property>::on_update_with_previous(self, previous, value, changed).await
+    }
+
+    fn patchable(&self) -> bool {
+        <T as Property>::patchable(self)
+    }
+
+    async fn on_patch(
+        &mut self,
+        patch: serde_json::Value,
+    ) -> Result<Option<serde_json::Value>, String> {
+        let value = <T as Property>::on_patch(self, patch).await?;
+        <T as BuiltProperty>::property_handle(self)
+            .description
+            .serialize_value(value)
+            .map_err(|err| format!("Could not serialize value: {:?}", err))
     }
 
     fn post_init(&mut self) {
         <T as Property>::post_init(self)
     }
+
+    fn poll_interval(&self) -> Option<Duration> {
+        <T as Property>::poll_interval(self)
+    }
+
+    fn missed_poll_behavior(&self) -> MissedPollBehavior {
+        <T as Property>::missed_poll_behavior(self)
+    }
+
+    async fn poll(&mut self) -> Result<(), String> {
+        let value = <T as Property>::poll(self).await?;
+        <T as BuiltProperty>::property_handle_mut(self)
+            .set_value(value)
+            .await
+            .map_err(|err| format!("Could not set polled value: {}", err))
+    }
 }
 
 /// A trait used to wrap a [property handle][PropertyHandle].
@@ -142,10 +290,14 @@ pub trait BuiltProperty {
 #[cfg(test)]
 pub(crate) mod tests {
     use crate::{
-        property::{self, tests::MockProperty, BuiltProperty},
-        Property, PropertyHandle,
+        client::Client,
+        property::{self, tests::MockProperty, BuiltProperty, PropertyBase},
+        Property, PropertyDescription, PropertyHandle,
     };
     use async_trait::async_trait;
+    use serde_json::json;
+    use std::sync::{Arc, Weak};
+    use tokio::sync::Mutex;
 
     pub struct BuiltMockProperty<T: property::Value> {
         data: MockProperty<T>,
@@ -197,5 +349,99 @@ pub(crate) mod tests {
                 self.property_helper.post_init();
             }
         }
+
+        fn patchable(&self) -> bool {
+            self.data.patchable
+        }
+
+        fn poll_interval(&self) -> Option<std::time::Duration> {
+            self.data.poll_interval
+        }
+
+        async fn poll(&mut self) -> Result<Self::Value, String> {
+            self.property_helper.poll()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_on_patch() {
+        let property_handle = PropertyHandle::new(
+            Arc::new(Mutex::new(Client::new())),
+            Weak::new(),
+            "plugin_id".to_owned(),
+            "adapter_id".to_owned(),
+            "device_id".to_owned(),
+            "property_name".to_owned(),
+            PropertyDescription::<serde_json::Value>::default().value(json!({"a": 1, "b": 2})),
+        );
+        let mut property = BuiltMockProperty::new(
+            MockProperty::new("property_name".to_owned()),
+            property_handle,
+        );
+        property
+            .expect_on_update()
+            .withf(|value| value == &json!({"a": 1, "c": 3}))
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let value = PropertyBase::on_patch(&mut property, json!({"b": null, "c": 3}))
+            .await
+            .unwrap();
+        assert_eq!(value, Some(json!({"a": 1, "c": 3})));
+    }
+
+    struct RecordingProperty {
+        property_handle: PropertyHandle<i32>,
+        updates: Vec<(i32, i32, bool)>,
+    }
+
+    impl BuiltProperty for RecordingProperty {
+        type Value = i32;
+
+        fn property_handle(&self) -> &PropertyHandle<i32> {
+            &self.property_handle
+        }
+
+        fn property_handle_mut(&mut self) -> &mut PropertyHandle<i32> {
+            &mut self.property_handle
+        }
+    }
+
+    #[async_trait]
+    impl Property for RecordingProperty {
+        async fn on_update_with_previous(
+            &mut self,
+            previous: i32,
+            value: i32,
+            changed: bool,
+        ) -> Result<(), String> {
+            self.updates.push((previous, value, changed));
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_on_update_with_previous() {
+        let property_handle = PropertyHandle::new(
+            Arc::new(Mutex::new(Client::new())),
+            Weak::new(),
+            "plugin_id".to_owned(),
+            "adapter_id".to_owned(),
+            "device_id".to_owned(),
+            "property_name".to_owned(),
+            PropertyDescription::<i32>::default().value(1),
+        );
+        let mut property = RecordingProperty {
+            property_handle,
+            updates: Vec::new(),
+        };
+
+        PropertyBase::on_update(&mut property, json!(1))
+            .await
+            .unwrap();
+        PropertyBase::on_update(&mut property, json!(2))
+            .await
+            .unwrap();
+        assert_eq!(property.updates, vec![(1, 1, false), (1, 2, true)]);
     }
 }
@@ -7,6 +7,7 @@
 use crate::{error::WebthingsError, type_::Type, PropertyDescription};
 use serde::{de::DeserializeOwned, Serialize};
 use serde_json::json;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 
 /// A trait which converts between Rust types and WoT [types][Type].
 ///
@@ -91,17 +92,56 @@ pub trait SimpleValue:
         ))
     }
 
+    /// Whether [deserialize][SimpleValue::deserialize] rejects object fields which are not part of `Self`.
+    ///
+    /// Permissive by default, like [serde_json::from_value]. If enabled, the deserialized value is
+    /// serialized again and every field of the received object, including nested objects, has to
+    /// be present in it. Fields skipped during serialization are thereby considered unknown.
+    fn deny_unknown_fields() -> bool {
+        false
+    }
+
     /// Deserialize the value.
     fn deserialize(value: Option<serde_json::Value>) -> Result<Self, WebthingsError> {
-        serde_json::from_value(value.ok_or_else(|| {
+        let value = value.ok_or_else(|| {
             WebthingsError::Serialization(<serde_json::Error as serde::de::Error>::custom(
                 "Expected Some, found None",
             ))
-        })?)
-        .map_err(WebthingsError::Serialization)
+        })?;
+        let deserialized: Self =
+            serde_json::from_value(value.clone()).map_err(WebthingsError::Serialization)?;
+        if Self::deny_unknown_fields() {
+            let known =
+                serde_json::to_value(&deserialized).map_err(WebthingsError::Serialization)?;
+            if let Some(field) = unknown_field(&value, &known) {
+                return Err(WebthingsError::Serialization(
+                    <serde_json::Error as serde::de::Error>::custom(format!(
+                        "unknown field `{}`",
+                        field
+                    )),
+                ));
+            }
+        }
+        Ok(deserialized)
     }
 }
 
+/// Find the path of the first field of `value` which is missing in `known`.
+fn unknown_field(value: &serde_json::Value, known: &serde_json::Value) -> Option<String> {
+    let (value, known) = match (value, known) {
+        (serde_json::Value::Object(value), serde_json::Value::Object(known)) => (value, known),
+        _ => return None,
+    };
+    value
+        .iter()
+        .find_map(|(field, value)| match known.get(field) {
+            Some(known) => {
+                unknown_field(value, known).map(|nested| format!("{}.{}", field, nested))
+            }
+            None => Some(field.clone()),
+        })
+}
+
 impl<T: SimpleValue> Value for T {
     fn type_() -> Type {
         <T as SimpleValue>::type_()
@@ -120,6 +160,59 @@ impl<T: SimpleValue> Value for T {
     }
 }
 
+/// Derive [Value] for an enum whose variants are represented as strings.
+///
+/// The generated implementation uses [Type::String] and advertises all variants via
+/// [enum_][PropertyDescription::enum_]. Variants are named like their Rust identifier
+/// unless renamed with `#[serde(rename = "...")]`. Variants carrying data are rejected.
+///
+/// [Value] requires [Clone] and [Default], so these still need to be implemented.
+///
+/// # Examples
+/// ```
+/// # use gateway_addon_rust::property::StringEnumValue;
+/// #[derive(Clone, Default, StringEnumValue)]
+/// enum ThermostatMode {
+///     #[default]
+///     #[serde(rename = "off")]
+///     Off,
+///     #[serde(rename = "heat")]
+///     Heat,
+///     #[serde(rename = "cool")]
+///     Cool,
+/// }
+/// ```
+pub use gateway_addon_rust_codegen::StringEnumValue;
+
+#[doc(hidden)]
+pub fn deserialize_string_enum<T: Clone>(
+    value: Option<serde_json::Value>,
+    variants: &[(&str, T)],
+) -> Result<T, WebthingsError> {
+    let value = value.ok_or_else(|| {
+        WebthingsError::Serialization(<serde_json::Error as serde::de::Error>::custom(
+            "Expected Some, found None",
+        ))
+    })?;
+    let name = value.as_str().ok_or_else(|| {
+        WebthingsError::Serialization(<serde_json::Error as serde::de::Error>::custom(format!(
+            "Expected string, found {}",
+            value
+        )))
+    })?;
+    variants
+        .iter()
+        .find(|(variant, _)| *variant == name)
+        .map(|(_, value)| value.clone())
+        .ok_or_else(|| {
+            let expected: Vec<&str> = variants.iter().map(|(variant, _)| *variant).collect();
+            WebthingsError::Serialization(<serde_json::Error as serde::de::Error>::custom(format!(
+                "Unknown variant {:?}, expected one of {:?}",
+                name, expected
+            )))
+        })
+}
+
 impl SimpleValue for i8 {
     fn type_() -> Type {
         Type::Integer
@@ -280,6 +373,8 @@ impl<T: Value> Value for Option<T> {
         description.minimum = t_description.minimum;
         description.multiple_of = t_description.multiple_of;
         description.read_only = t_description.read_only;
+        description.observable = t_description.observable;
+        description.write_only = t_description.write_only;
         description.title = t_description.title;
         description.unit = t_description.unit;
         description.visible = t_description.visible;
@@ -306,10 +401,55 @@ impl<T: Value> Value for Option<T> {
     }
 }
 
+/// Network addresses have no default, so only optional addresses are [values][Value].
+///
+/// `None` serializes to `null`, addresses serialize to their string representation.
+macro_rules! impl_address_value {
+    ($($t:ty),*) => {
+        $(
+            impl Value for Option<$t> {
+                fn type_() -> Type {
+                    Type::String
+                }
+
+                fn serialize(value: Self) -> Result<Option<serde_json::Value>, WebthingsError> {
+                    Ok(value.map(|address| json!(address.to_string())))
+                }
+
+                fn deserialize(value: Option<serde_json::Value>) -> Result<Self, WebthingsError> {
+                    match value {
+                        None | Some(serde_json::Value::Null) => Ok(None),
+                        Some(serde_json::Value::String(address)) => {
+                            address.parse::<$t>().map(Some).map_err(|err| {
+                                WebthingsError::Serialization(
+                                    <serde_json::Error as serde::de::Error>::custom(format!(
+                                        "Invalid address {:?}: {}",
+                                        address, err
+                                    )),
+                                )
+                            })
+                        }
+                        Some(_) => Err(WebthingsError::Serialization(
+                            <serde_json::Error as serde::de::Error>::custom("Expected String"),
+                        )),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_address_value!(IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr);
+
 #[cfg(test)]
 mod tests {
-    use crate::property::{self, Value};
+    use crate::{
+        error::WebthingsError,
+        property::{self, Value},
+        type_::Type,
+    };
     use serde_json::json;
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 
     #[test]
     fn test_serialize_bool() {
@@ -514,4 +654,125 @@ mod tests {
             Some(json!({"i": 42, "s": "foo", "o": {"b": true}}))
         );
     }
+
+    #[test]
+    fn test_ip_addr_round_trip() {
+        for address in ["192.168.1.1", "::1", "fe80::1ff:fe23:4567:890a"] {
+            let value = Some(address.parse::<IpAddr>().unwrap());
+            let serialized = Option::<IpAddr>::serialize(value).unwrap();
+            assert_eq!(serialized, Some(json!(address)));
+            assert_eq!(Option::<IpAddr>::deserialize(serialized).unwrap(), value);
+        }
+        assert_eq!(Option::<IpAddr>::serialize(None).unwrap(), None);
+        assert_eq!(
+            Option::<IpAddr>::deserialize(Some(json!(null))).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_ip_addr_versions() {
+        assert_eq!(
+            Option::<Ipv4Addr>::deserialize(Some(json!("10.0.0.1"))).unwrap(),
+            Some(Ipv4Addr::new(10, 0, 0, 1))
+        );
+        assert!(Option::<Ipv4Addr>::deserialize(Some(json!("::1"))).is_err());
+        assert_eq!(
+            Option::<Ipv6Addr>::deserialize(Some(json!("::1"))).unwrap(),
+            Some(Ipv6Addr::LOCALHOST)
+        );
+        assert!(Option::<Ipv6Addr>::deserialize(Some(json!("10.0.0.1"))).is_err());
+    }
+
+    #[test]
+    fn test_socket_addr_round_trip() {
+        for address in ["192.168.1.1:8080", "[::1]:443"] {
+            let value = Some(address.parse::<SocketAddr>().unwrap());
+            let serialized = Option::<SocketAddr>::serialize(value).unwrap();
+            assert_eq!(serialized, Some(json!(address)));
+            assert_eq!(
+                Option::<SocketAddr>::deserialize(serialized).unwrap(),
+                value
+            );
+        }
+        assert!(Option::<SocketAddr>::deserialize(Some(json!("192.168.1.1"))).is_err());
+    }
+
+    #[test]
+    fn test_deserialize_invalid_addr() {
+        assert!(Option::<IpAddr>::deserialize(Some(json!("256.1.1.1"))).is_err());
+        assert!(Option::<IpAddr>::deserialize(Some(json!("localhost"))).is_err());
+        assert!(Option::<IpAddr>::deserialize(Some(json!(42))).is_err());
+        assert!(matches!(<Option<IpAddr> as Value>::type_(), Type::String));
+    }
+
+    #[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Color {
+        brightness: u8,
+        hue: Hue,
+    }
+
+    #[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Hue {
+        degrees: u16,
+    }
+
+    impl property::SimpleValue for Color {}
+
+    #[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct StrictColor {
+        brightness: u8,
+        hue: Hue,
+    }
+
+    impl property::SimpleValue for StrictColor {
+        fn deny_unknown_fields() -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn test_deserialize_unknown_field_permissive() {
+        assert_eq!(
+            Color::deserialize(Some(
+                json!({"brightness": 50, "brightnes": 50, "hue": {"degrees": 90}})
+            ))
+            .unwrap(),
+            Color {
+                brightness: 50,
+                hue: Hue { degrees: 90 }
+            }
+        );
+    }
+
+    #[test]
+    fn test_deserialize_strict() {
+        assert_eq!(
+            StrictColor::deserialize(Some(json!({"brightness": 50, "hue": {"degrees": 90}})))
+                .unwrap(),
+            StrictColor {
+                brightness: 50,
+                hue: Hue { degrees: 90 }
+            }
+        );
+    }
+
+    #[test]
+    fn test_deserialize_strict_unknown_field() {
+        let err = StrictColor::deserialize(Some(
+            json!({"brightness": 50, "brightnes": 50, "hue": {"degrees": 90}}),
+        ))
+        .unwrap_err();
+        assert!(
+            matches!(err, WebthingsError::Serialization(err) if err.to_string() == "unknown field `brightnes`")
+        );
+
+        let err = StrictColor::deserialize(Some(
+            json!({"brightness": 50, "hue": {"degrees": 90, "degree": 90}}),
+        ))
+        .unwrap_err();
+        assert!(
+            matches!(err, WebthingsError::Serialization(err) if err.to_string() == "unknown field `hue.degree`")
+        );
+    }
 }
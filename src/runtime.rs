@@ -0,0 +1,38 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+
+use std::future::Future;
+use tokio::{runtime::Handle, task::JoinHandle};
+
+/// Spawn a task on the given runtime, or on the current runtime if none is given.
+pub(crate) fn spawn<F>(runtime: Option<&Handle>, future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    match runtime {
+        Some(runtime) => runtime.spawn(future),
+        None => tokio::spawn(future),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::spawn;
+    use tokio::runtime::Builder;
+
+    #[test]
+    fn test_spawn_on_runtime() {
+        let runtime = Builder::new_current_thread().enable_all().build().unwrap();
+        let task = spawn(Some(runtime.handle()), async { 42 });
+        assert_eq!(runtime.block_on(task).unwrap(), 42);
+    }
+
+    #[tokio::test]
+    async fn test_spawn_on_current_runtime() {
+        assert_eq!(spawn(None, async { 42 }).await.unwrap(), 42);
+    }
+}
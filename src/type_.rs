@@ -7,10 +7,18 @@
 //! A module for working with WoT datatypes.
 
 use crate::{action::Input, error::WebthingsError, event::Data, property::Value};
+use serde::{Deserialize, Serialize};
 use serde_json::json;
+use std::str::FromStr;
 
 /// An enum of all WoT datatypes.
-#[derive(Debug, Clone)]
+///
+/// Serializes to the lowercase WoT representation, e.g. `"integer"`, which is also what
+/// [to_string][ToString::to_string] returns and what the gateway validates `type` against.
+/// In contrast, the `@type` enums (e.g. [property::AtType][crate::property::AtType]) use the
+/// CamelCase names of the WebThings capability schemas.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Type {
     Null,
     Boolean,
@@ -36,6 +44,25 @@ impl ToString for Type {
     }
 }
 
+impl FromStr for Type {
+    type Err = WebthingsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "null" => Ok(Type::Null),
+            "boolean" => Ok(Type::Boolean),
+            "integer" => Ok(Type::Integer),
+            "number" => Ok(Type::Number),
+            "string" => Ok(Type::String),
+            "array" => Ok(Type::Array),
+            "object" => Ok(Type::Object),
+            _ => Err(WebthingsError::Serialization(
+                <serde_json::Error as serde::de::Error>::custom(format!("Unknown type {}", s)),
+            )),
+        }
+    }
+}
+
 /// An equivalent of the WoT [type][Type] null.
 #[derive(Clone, Default, PartialEq, Debug)]
 pub struct Null;
@@ -90,7 +117,11 @@ impl Input for Null {
 
 #[cfg(test)]
 mod tests {
-    use crate::{action, event, property, type_::Null};
+    use crate::{
+        action, event, property,
+        type_::{Null, Type},
+    };
+    use rstest::rstest;
     use serde_json::json;
 
     #[tokio::test]
@@ -127,4 +158,78 @@ mod tests {
             Some(json!(null))
         );
     }
+
+    #[rstest]
+    #[case(Type::Null)]
+    #[case(Type::Boolean)]
+    #[case(Type::Integer)]
+    #[case(Type::Number)]
+    #[case(Type::String)]
+    #[case(Type::Array)]
+    #[case(Type::Object)]
+    fn test_type_from_str(#[case] type_: Type) {
+        assert_eq!(
+            type_.to_string().parse::<Type>().unwrap().to_string(),
+            type_.to_string()
+        );
+    }
+
+    #[test]
+    fn test_type_from_str_unknown() {
+        assert!("foo".parse::<Type>().is_err());
+    }
+
+    #[test]
+    fn test_type_serialize() {
+        assert_eq!(
+            serde_json::to_value(Type::Integer).unwrap(),
+            json!("integer")
+        );
+    }
+
+    #[rstest]
+    #[case(Type::Null)]
+    #[case(Type::Boolean)]
+    #[case(Type::Integer)]
+    #[case(Type::Number)]
+    #[case(Type::String)]
+    #[case(Type::Array)]
+    #[case(Type::Object)]
+    fn test_type_serialize_matches_to_string(#[case] type_: Type) {
+        assert_eq!(
+            serde_json::to_value(&type_).unwrap(),
+            json!(type_.to_string())
+        );
+        assert_eq!(
+            serde_json::from_value::<Type>(json!(type_.to_string()))
+                .unwrap()
+                .to_string(),
+            type_.to_string()
+        );
+    }
+
+    #[test]
+    fn test_type_deserialize_debug_casing() {
+        assert!(serde_json::from_value::<Type>(json!("Integer")).is_err());
+    }
+
+    #[rstest]
+    #[case(Type::Null, "null")]
+    #[case(Type::Boolean, "boolean")]
+    #[case(Type::Integer, "integer")]
+    #[case(Type::Number, "number")]
+    #[case(Type::String, "string")]
+    #[case(Type::Array, "array")]
+    #[case(Type::Object, "object")]
+    fn test_type_to_string(#[case] type_: Type, #[case] expected: &str) {
+        assert_eq!(type_.to_string(), expected);
+    }
+
+    #[test]
+    fn test_property_description_type() {
+        let description = property::PropertyDescription::<i32>::default()
+            .into_full_description("foo".to_owned())
+            .unwrap();
+        assert_eq!(description.type_, "integer");
+    }
 }
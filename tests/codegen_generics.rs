@@ -0,0 +1,79 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+
+#![cfg(feature = "runtime")]
+
+use gateway_addon_rust::{
+    event::{Data, EventBuilder},
+    prelude::*,
+    property::{PropertyBuilder, Value},
+};
+use std::marker::PhantomData;
+
+#[property]
+struct GenericProperty<T: Value> {
+    name: String,
+    _value: PhantomData<T>,
+}
+
+impl<T: Value> PropertyStructure for GenericProperty<T> {
+    type Value = T;
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn description(&self) -> PropertyDescription<Self::Value> {
+        PropertyDescription::default()
+    }
+}
+
+impl<T: Value> Property for BuiltGenericProperty<T> {}
+
+#[event]
+struct GenericEvent<T>
+where
+    T: Data,
+{
+    _data: PhantomData<T>,
+}
+
+impl<T> EventStructure for GenericEvent<T>
+where
+    T: Data,
+{
+    type Data = T;
+
+    fn name(&self) -> String {
+        "generic-event".to_owned()
+    }
+
+    fn description(&self) -> EventDescription<Self::Data> {
+        EventDescription::default()
+    }
+}
+
+impl<T: Data> Event for BuiltGenericEvent<T> {}
+
+#[test]
+fn test_generic_property() {
+    let property = GenericProperty::<i32> {
+        name: "level".to_owned(),
+        _value: PhantomData,
+    };
+    assert_eq!(PropertyStructure::name(&property), "level");
+    assert!(property.full_description().is_ok());
+    let _: fn(GenericProperty<i32>, PropertyHandle<i32>) -> BuiltGenericProperty<i32> =
+        <GenericProperty<i32> as PropertyBuilder>::build;
+}
+
+#[test]
+fn test_generic_event() {
+    let event = GenericEvent::<bool> { _data: PhantomData };
+    assert_eq!(EventStructure::name(&event), "generic-event");
+    let _: fn(GenericEvent<bool>, EventHandle<bool>) -> BuiltGenericEvent<bool> =
+        <GenericEvent<bool> as EventBuilder>::build;
+}
@@ -0,0 +1,42 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+
+use gateway_addon_rust::action::{Input, SimpleInput};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::json;
+
+#[derive(Clone, Deserialize, JsonSchema, SimpleInput)]
+struct FadeInput {
+    #[webthings(unit = "percent", minimum = 0, maximum = 100)]
+    level: u8,
+    #[webthings(unit = "second", at_type = "DurationProperty", multiple_of = 0.5)]
+    duration: f32,
+    other: i32,
+}
+
+#[test]
+fn test_annotated_input() {
+    let input = <FadeInput as Input>::input().unwrap();
+    let properties = &input["properties"];
+    assert_eq!(properties["level"]["unit"], json!("percent"));
+    assert_eq!(properties["level"]["minimum"], json!(0));
+    assert_eq!(properties["level"]["maximum"], json!(100));
+    assert_eq!(properties["duration"]["unit"], json!("second"));
+    assert_eq!(properties["duration"]["@type"], json!("DurationProperty"));
+    assert_eq!(properties["duration"]["multipleOf"], json!(0.5));
+    assert!(properties["other"].get("unit").is_none());
+}
+
+#[test]
+fn test_annotated_input_deserialize() {
+    let input =
+        <FadeInput as Input>::deserialize(json!({"level": 50, "duration": 1.5, "other": 2}))
+            .unwrap();
+    assert_eq!(input.level, 50);
+    assert_eq!(input.duration, 1.5);
+    assert_eq!(input.other, 2);
+}
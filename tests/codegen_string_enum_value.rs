@@ -0,0 +1,63 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
+ */
+
+use gateway_addon_rust::{
+    property::{StringEnumValue, Value},
+    type_::Type,
+    PropertyDescription,
+};
+use serde_json::json;
+
+#[derive(Clone, Debug, Default, PartialEq, StringEnumValue)]
+enum ThermostatMode {
+    #[default]
+    Off,
+    #[serde(rename = "heat")]
+    Heat,
+    #[serde(rename = "cool")]
+    Cool,
+}
+
+#[test]
+fn test_string_enum_type() {
+    assert!(matches!(<ThermostatMode as Value>::type_(), Type::String));
+}
+
+#[test]
+fn test_string_enum_description() {
+    let description = <ThermostatMode as Value>::description(PropertyDescription::default());
+    assert_eq!(
+        description.enum_,
+        Some(vec![
+            ThermostatMode::Off,
+            ThermostatMode::Heat,
+            ThermostatMode::Cool
+        ])
+    );
+}
+
+#[test]
+fn test_string_enum_serialize() {
+    assert_eq!(
+        <ThermostatMode as Value>::serialize(ThermostatMode::Off).unwrap(),
+        Some(json!("Off"))
+    );
+    assert_eq!(
+        <ThermostatMode as Value>::serialize(ThermostatMode::Heat).unwrap(),
+        Some(json!("heat"))
+    );
+}
+
+#[test]
+fn test_string_enum_deserialize() {
+    assert_eq!(
+        <ThermostatMode as Value>::deserialize(Some(json!("cool"))).unwrap(),
+        ThermostatMode::Cool
+    );
+    assert!(<ThermostatMode as Value>::deserialize(Some(json!("Cool"))).is_err());
+    assert!(<ThermostatMode as Value>::deserialize(Some(json!(1))).is_err());
+    assert!(<ThermostatMode as Value>::deserialize(None).is_err());
+}
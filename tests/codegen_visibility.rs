@@ -4,6 +4,8 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/.*
  */
 
+#![cfg(feature = "runtime")]
+
 use gateway_addon_rust::prelude::*;
 
 mod private_module {
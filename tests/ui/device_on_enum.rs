@@ -0,0 +1,9 @@
+use gateway_addon_rust::prelude::*;
+
+#[device]
+enum TestDevice {
+    Foo,
+    Bar,
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use gateway_addon_rust::action::SimpleInput;
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+#[derive(Clone, Deserialize, JsonSchema, SimpleInput)]
+struct FadeInput {
+    #[webthings(foo::bar = 1)]
+    level: u8,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use gateway_addon_rust::property::StringEnumValue;
+
+#[derive(Clone, Default, StringEnumValue)]
+enum Mode {
+    #[default]
+    Off,
+    Level(u8),
+}
+
+fn main() {}